@@ -0,0 +1,406 @@
+use std::collections::HashMap;
+
+use log::{error, info};
+use serde::{Deserialize, Serialize};
+use zbus::zvariant::Value as Variant;
+
+use crate::format::format_time;
+use crate::{Batteries, ChargeStatus};
+
+/// Alert levels of discharging batteries
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, PartialOrd, Serialize)]
+pub enum AlertLevel {
+    #[default]
+    None,
+    Warning,
+    Critical,
+}
+
+/// Charge thresholds at which alerts are raised
+#[derive(Clone, Copy, Default)]
+pub struct Thresholds {
+    pub warning: Option<f64>,  // warning charge threshold (%)
+    pub critical: Option<f64>, // critical charge threshold (%)
+}
+
+impl Thresholds {
+    /// Returns the alert level of the batteries
+    ///
+    /// Alerts are only raised while discharging.
+    pub fn level(&self, batteries: &Batteries) -> AlertLevel {
+        if batteries.charge_status != ChargeStatus::Discharging {
+            return AlertLevel::None;
+        }
+
+        let charge = batteries.charge_percent;
+        if self.critical.is_some_and(|v| charge <= v) {
+            AlertLevel::Critical
+        } else if self.warning.is_some_and(|v| charge <= v) {
+            AlertLevel::Warning
+        } else {
+            AlertLevel::None
+        }
+    }
+}
+
+/// Alerts that already fired
+///
+/// Alerts are persisted in the `State`, or kept in memory in persistent mode,
+/// so that each alert fires once per threshold crossing rather than once per
+/// invocation of the block.
+#[derive(Default, Deserialize, Serialize)]
+pub struct Alerts {
+    fired: AlertLevel, // highest level fired since the batteries discharge
+}
+
+impl Alerts {
+    /// Records the present alert level
+    ///
+    /// Alerts are re-armed once the batteries stop discharging.
+    ///
+    /// # Arguments
+    ///
+    /// - `batteries`: The present `Batteries`
+    /// - `thresholds`: The alert `Thresholds`
+    ///
+    /// # Returns
+    ///
+    /// The alert level to fire, if a threshold was crossed
+    pub fn update(&mut self, batteries: &Batteries, thresholds: &Thresholds) -> Option<AlertLevel> {
+        if batteries.charge_status != ChargeStatus::Discharging {
+            self.fired = AlertLevel::None;
+            return None;
+        }
+
+        let level = thresholds.level(batteries);
+        if level > self.fired {
+            self.fired = level;
+            Some(level)
+        } else {
+            None
+        }
+    }
+}
+
+/// Fires an alert
+///
+/// A desktop notification is sent through the `notifier`, if any, and the
+/// `command`, if any, is spawned through `sh` for critical alerts.
+///
+/// # Arguments
+///
+/// - `level`: The alert level to fire
+/// - `batteries`: The present `Batteries`
+/// - `notifier`: A `Notifier` sending desktop notifications
+/// - `command`: A shell command to run on critical alerts
+pub fn fire_alert(
+    level: AlertLevel,
+    batteries: &Batteries,
+    notifier: Option<&dyn Notifier>,
+    command: Option<&str>,
+) {
+    let summary = match level {
+        AlertLevel::Warning => "Battery low",
+        AlertLevel::Critical => "Battery critical",
+        AlertLevel::None => return,
+    };
+    let body = match batteries.time_remaining {
+        Some(time) => format!(
+            "{:.0}% remaining ({})",
+            batteries.charge_percent,
+            format_time(time)
+        ),
+        None => format!("{:.0}% remaining", batteries.charge_percent),
+    };
+    info!("battery::fire_alert: {}, {}", summary, body);
+
+    if let Some(notifier) = notifier {
+        if let Err(e) = notifier.notify(summary, &body, level == AlertLevel::Critical) {
+            error!("battery::fire_alert: {}", e);
+        }
+    }
+
+    if let (AlertLevel::Critical, Some(command)) = (level, command) {
+        match std::process::Command::new("sh")
+            .arg("-c")
+            .arg(command)
+            .spawn()
+        {
+            Ok(_) => info!("battery::fire_alert: spawned `{}`", command),
+            Err(e) => error!("battery::fire_alert: unable to spawn `{}`, {}", command, e),
+        }
+    }
+}
+
+/// Sender of desktop notifications
+pub trait Notifier {
+    fn notify(&self, summary: &str, body: &str, critical: bool) -> Result<(), String>;
+}
+
+/// Notifier using the freedesktop Notifications D-Bus interface
+pub struct DesktopNotifier {
+    connection: zbus::blocking::Connection,
+}
+
+impl DesktopNotifier {
+    /// Connects to the session bus
+    ///
+    /// # Returns
+    ///
+    /// A `Result`:
+    /// - `Ok`: A `DesktopNotifier` connected to the session bus
+    /// - `Err`: A `String` describing the connection failure
+    pub fn new() -> Result<DesktopNotifier, String> {
+        match zbus::blocking::Connection::session() {
+            Ok(connection) => Ok(DesktopNotifier::with_connection(connection)),
+            Err(e) => Err(format!("unable to connect to session bus, {}", e)),
+        }
+    }
+
+    /// Uses an existing D-Bus connection
+    ///
+    /// # Arguments
+    ///
+    /// - `connection`: A connection to a bus or peer implementing the
+    ///   `org.freedesktop.Notifications` interface
+    pub fn with_connection(connection: zbus::blocking::Connection) -> DesktopNotifier {
+        DesktopNotifier { connection }
+    }
+}
+
+impl Notifier for DesktopNotifier {
+    fn notify(&self, summary: &str, body: &str, critical: bool) -> Result<(), String> {
+        let mut hints: HashMap<&str, Variant> = HashMap::new();
+        hints.insert("urgency", Variant::U8(if critical { 2 } else { 1 }));
+
+        let actions: Vec<&str> = Vec::new();
+        let expire_timeout: i32 = -1;
+        self.connection
+            .call_method(
+                Some("org.freedesktop.Notifications"),
+                "/org/freedesktop/Notifications",
+                Some("org.freedesktop.Notifications"),
+                "Notify",
+                &(
+                    "battery",
+                    0u32,
+                    "battery",
+                    summary,
+                    body,
+                    actions,
+                    hints,
+                    expire_timeout,
+                ),
+            )
+            .map(|_| ())
+            .map_err(|e| format!("unable to send notification, {}", e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::convert::TryFrom;
+    use std::os::unix::net::UnixStream;
+    use std::path::PathBuf;
+    use std::sync::{Arc, Mutex};
+
+    /// Notifier recording its notifications
+    #[derive(Default)]
+    struct RecordingNotifier(RefCell<Vec<(String, String, bool)>>);
+
+    impl Notifier for RecordingNotifier {
+        fn notify(&self, summary: &str, body: &str, critical: bool) -> Result<(), String> {
+            self.0
+                .borrow_mut()
+                .push((summary.into(), body.into(), critical));
+            Ok(())
+        }
+    }
+
+    /// Stand-in for a notification daemon
+    struct Notifications(Arc<Mutex<Vec<(String, String, u8)>>>);
+
+    #[zbus::interface(name = "org.freedesktop.Notifications")]
+    impl Notifications {
+        #[allow(clippy::too_many_arguments)]
+        fn notify(
+            &self,
+            _app_name: String,
+            _replaces_id: u32,
+            _app_icon: String,
+            summary: String,
+            body: String,
+            _actions: Vec<String>,
+            hints: HashMap<String, zbus::zvariant::OwnedValue>,
+            _expire_timeout: i32,
+        ) -> u32 {
+            let urgency = hints
+                .get("urgency")
+                .and_then(|v| u8::try_from(v).ok())
+                .unwrap_or(0);
+            let mut received = self.0.lock().unwrap();
+            received.push((summary, body, urgency));
+            received.len() as u32
+        }
+    }
+
+    fn batteries(path: &str) -> Batteries {
+        Batteries::new(&PathBuf::from(path), false).unwrap()
+    }
+
+    #[test]
+    fn test_level() {
+        // Discharging at 40%
+        let discharging = batteries("tests/two-batteries-dis-dis");
+        let thresholds = Thresholds {
+            warning: Some(50.0),
+            critical: Some(10.0),
+        };
+        assert_eq!(thresholds.level(&discharging), AlertLevel::Warning);
+
+        let thresholds = Thresholds {
+            warning: Some(50.0),
+            critical: Some(45.0),
+        };
+        assert_eq!(thresholds.level(&discharging), AlertLevel::Critical);
+
+        let thresholds = Thresholds {
+            warning: Some(20.0),
+            critical: None,
+        };
+        assert_eq!(thresholds.level(&discharging), AlertLevel::None);
+
+        // Charging at 40%
+        let charging = batteries("tests/two-batteries-chr-chr");
+        let thresholds = Thresholds {
+            warning: Some(50.0),
+            critical: Some(45.0),
+        };
+        assert_eq!(thresholds.level(&charging), AlertLevel::None);
+    }
+
+    #[test]
+    fn test_update() {
+        let discharging = batteries("tests/two-batteries-dis-dis");
+        let charging = batteries("tests/two-batteries-chr-chr");
+        let warning = Thresholds {
+            warning: Some(50.0),
+            critical: Some(10.0),
+        };
+        let critical = Thresholds {
+            warning: Some(50.0),
+            critical: Some(45.0),
+        };
+
+        let mut alerts = Alerts::default();
+        assert_eq!(
+            alerts.update(&discharging, &warning),
+            Some(AlertLevel::Warning)
+        );
+        assert_eq!(alerts.update(&discharging, &warning), None);
+        assert_eq!(
+            alerts.update(&discharging, &critical),
+            Some(AlertLevel::Critical)
+        );
+        assert_eq!(alerts.update(&discharging, &warning), None);
+        assert_eq!(alerts.update(&charging, &warning), None);
+        assert_eq!(
+            alerts.update(&discharging, &warning),
+            Some(AlertLevel::Warning)
+        );
+    }
+
+    #[test]
+    fn test_fire() {
+        let discharging = batteries("tests/two-batteries-dis-dis");
+        let notifier = RecordingNotifier::default();
+        let marker = std::env::temp_dir().join("battery-test-alert-fire");
+        let _ = std::fs::remove_file(&marker);
+        let command = format!("touch {}", marker.display());
+
+        fire_alert(
+            AlertLevel::Warning,
+            &discharging,
+            Some(&notifier),
+            Some(&command),
+        );
+        fire_alert(
+            AlertLevel::Critical,
+            &discharging,
+            Some(&notifier),
+            Some(&command),
+        );
+
+        let notifications = notifier.0.borrow();
+        assert_eq!(notifications.len(), 2);
+        assert_eq!(
+            notifications[0],
+            (
+                "Battery low".into(),
+                "40% remaining (1:11:47)".into(),
+                false
+            )
+        );
+        assert_eq!(
+            notifications[1],
+            (
+                "Battery critical".into(),
+                "40% remaining (1:11:47)".into(),
+                true
+            )
+        );
+
+        // Command is spawned in the background
+        for _ in 0..100 {
+            if marker.exists() {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        assert!(marker.exists());
+        std::fs::remove_file(&marker).unwrap();
+    }
+
+    #[test]
+    fn test_desktop_notifier() {
+        let (server_stream, client_stream) = UnixStream::pair().unwrap();
+        let received = Arc::new(Mutex::new(Vec::new()));
+
+        let daemon = Notifications(Arc::clone(&received));
+        let server = std::thread::spawn(move || {
+            zbus::blocking::connection::Builder::async_io_unix_stream(server_stream)
+                .server(zbus::Guid::generate())
+                .unwrap()
+                .p2p()
+                .serve_at("/org/freedesktop/Notifications", daemon)
+                .unwrap()
+                .build()
+                .unwrap()
+        });
+
+        let connection = zbus::blocking::connection::Builder::async_io_unix_stream(client_stream)
+            .p2p()
+            .build()
+            .unwrap();
+        let _server = server.join().unwrap();
+
+        let notifier = DesktopNotifier::with_connection(connection);
+        assert!(notifier
+            .notify("Battery low", "10% remaining", false)
+            .is_ok());
+        assert!(notifier
+            .notify("Battery critical", "5% remaining", true)
+            .is_ok());
+
+        let received = received.lock().unwrap();
+        assert_eq!(
+            *received,
+            vec![
+                ("Battery low".into(), "10% remaining".into(), 1),
+                ("Battery critical".into(), "5% remaining".into(), 2),
+            ]
+        );
+    }
+}
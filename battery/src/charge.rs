@@ -0,0 +1,503 @@
+use std::path::Path;
+
+use log::info;
+use serde::Deserialize;
+
+use crate::{parse_file, Batteries, Battery};
+
+// Constants
+const BEHAVIOURS: [&str; 3] = ["auto", "inhibit-charge", "force-discharge"];
+
+/// Charge control profile, e.g. conserving the battery at 80%
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct ChargeProfile {
+    pub name: String, // name of the profile
+
+    #[serde(default)]
+    start_threshold: Option<u64>, // charge below which charging starts (%)
+    #[serde(default)]
+    end_threshold: Option<u64>, // charge above which charging stops (%)
+    #[serde(default)]
+    behaviour: Option<String>, // charge behaviour, e.g. `inhibit-charge`
+}
+
+impl ChargeProfile {
+    /// Validates the profile's options
+    ///
+    /// # Returns
+    ///
+    /// A `Result`:
+    /// - `Ok`: If the thresholds are within [0, 100], the start threshold
+    ///   is below the end threshold and the behaviour is known
+    /// - `Err`: A `String` describing the invalid option
+    pub(crate) fn validate(&self) -> Result<(), String> {
+        for threshold in [self.start_threshold, self.end_threshold].iter().flatten() {
+            if *threshold > 100 {
+                return Err(format!(
+                    "invalid threshold {} of charge profile {}, select from [0, 100]",
+                    threshold, self.name
+                ));
+            }
+        }
+
+        if let (Some(start), Some(end)) = (self.start_threshold, self.end_threshold) {
+            if start >= end {
+                return Err(format!(
+                    "start threshold {} not below end threshold {} of charge profile {}",
+                    start, end, self.name
+                ));
+            }
+        }
+
+        if let Some(behaviour) = &self.behaviour {
+            if !BEHAVIOURS.contains(&behaviour.as_str()) {
+                return Err(format!(
+                    "invalid behaviour {} of charge profile {}, select from [{}]",
+                    behaviour,
+                    self.name,
+                    BEHAVIOURS.join(", ")
+                ));
+            }
+        }
+
+        if self.start_threshold.is_none()
+            && self.end_threshold.is_none()
+            && self.behaviour.is_none()
+        {
+            return Err(format!("charge profile {} sets nothing", self.name));
+        }
+
+        Ok(())
+    }
+
+    /// Returns whether the profile is applied to a cell
+    fn matches(&self, cell: &Battery) -> bool {
+        let control = &cell.charge_control;
+        (self.start_threshold.is_none() || self.start_threshold == control.start_threshold)
+            && (self.end_threshold.is_none() || self.end_threshold == control.end_threshold)
+            && (self.behaviour.is_none() || self.behaviour == control.behaviour)
+    }
+}
+
+/// Charge control attributes of a cell
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct ChargeControl {
+    pub(crate) start_threshold: Option<u64>, // charge below which charging starts (%)
+    pub(crate) end_threshold: Option<u64>,   // charge above which charging stops (%)
+    pub(crate) behaviour: Option<String>,    // active charge behaviour
+}
+
+impl ChargeControl {
+    /// Reads the charge control attributes of a cell
+    ///
+    /// # Arguments
+    ///
+    /// - `path`: Path to the cell's directory
+    pub(crate) fn new(path: &Path) -> ChargeControl {
+        ChargeControl {
+            start_threshold: parse_file(&path.join("charge_control_start_threshold")),
+            end_threshold: parse_file(&path.join("charge_control_end_threshold")),
+            behaviour: parse_file::<String>(&path.join("charge_behaviour"))
+                .and_then(|v| active_behaviour(&v)),
+        }
+    }
+
+    /// Returns whether the cell supports any charge control attribute
+    pub(crate) fn is_supported(&self) -> bool {
+        self.start_threshold.is_some() || self.end_threshold.is_some() || self.behaviour.is_some()
+    }
+}
+
+/// Returns the profile applied to all cells supporting charge control
+///
+/// # Arguments
+///
+/// - `profiles`: Charge profiles from the configuration file
+/// - `batteries`: The present `Batteries`
+///
+/// # Returns
+///
+/// The first matching profile, `None` if no profile matches or no cell
+/// supports charge control
+pub fn active_profile<'a>(
+    profiles: &'a [ChargeProfile],
+    batteries: &Batteries,
+) -> Option<&'a ChargeProfile> {
+    let cells: Vec<&Battery> = batteries
+        .cells
+        .iter()
+        .filter(|cell| cell.charge_control.is_supported())
+        .collect();
+    if cells.is_empty() {
+        return None;
+    }
+
+    profiles
+        .iter()
+        .find(|profile| cells.iter().all(|cell| profile.matches(cell)))
+}
+
+/// Applies the profile following the active one to all cells
+///
+/// Profiles are cycled in the order of the configuration file, starting
+/// from the first one if no profile is active. Each attribute is written
+/// directly, or through the `helper` command if the block lacks the
+/// privileges to write to sysfs, and verified by reading it back.
+///
+/// # Arguments
+///
+/// - `profiles`: Charge profiles from the configuration file
+/// - `batteries`: The present `Batteries`
+/// - `helper`: A command invoked as `helper <path> <value>` to write an
+///   attribute
+///
+/// # Returns
+///
+/// A `Result`:
+/// - `Ok`: The applied `ChargeProfile`
+/// - `Err`: A `String` describing the failure to apply the profile
+pub fn cycle_profile<'a>(
+    profiles: &'a [ChargeProfile],
+    batteries: &Batteries,
+    helper: Option<&str>,
+) -> Result<&'a ChargeProfile, String> {
+    if profiles.is_empty() {
+        return Err(String::from("no charge profiles"));
+    }
+
+    let next = match active_profile(profiles, batteries) {
+        Some(active) => {
+            let i = profiles.iter().position(|v| v == active).unwrap_or(0);
+            &profiles[(i + 1) % profiles.len()]
+        }
+        None => &profiles[0],
+    };
+
+    let cells: Vec<&Battery> = batteries
+        .cells
+        .iter()
+        .filter(|cell| cell.charge_control.is_supported())
+        .collect();
+    if cells.is_empty() {
+        return Err(String::from("no cell supports charge control"));
+    }
+
+    for cell in cells {
+        apply(next, cell, helper)?;
+    }
+
+    info!(
+        "battery::cycle_profile: applied charge profile {}",
+        next.name
+    );
+    Ok(next)
+}
+
+/// Returns the thresholds of a profile in the order they're written
+///
+/// Drivers reject a start threshold at or above the end threshold, so the
+/// end threshold is raised first if the new start would reach the present
+/// end, and the start threshold is lowered first otherwise, e.g. if the new
+/// end would reach the present start.
+///
+/// # Arguments
+///
+/// - `profile`: The profile to apply
+/// - `control`: The present charge control attributes of the cell
+///
+/// # Returns
+///
+/// The attribute names and values to write, or a `String` describing a
+/// threshold that can't be written without the other one
+fn thresholds(
+    profile: &ChargeProfile,
+    control: &ChargeControl,
+) -> Result<[Option<(&'static str, String)>; 2], String> {
+    let current_start = control.start_threshold.unwrap_or(0);
+    let current_end = control.end_threshold.unwrap_or(100);
+    let start = profile
+        .start_threshold
+        .map(|v| ("charge_control_start_threshold", v.to_string()));
+    let end = profile
+        .end_threshold
+        .map(|v| ("charge_control_end_threshold", v.to_string()));
+
+    match (profile.start_threshold, profile.end_threshold) {
+        (Some(v), None) if v >= current_end => Err(format!(
+            "start threshold {} of profile {} isn't below the end threshold {}",
+            v, profile.name, current_end
+        )),
+        (None, Some(v)) if v <= current_start => Err(format!(
+            "end threshold {} of profile {} isn't above the start threshold {}",
+            v, profile.name, current_start
+        )),
+        (Some(v), Some(_)) if v >= current_end => Ok([end, start]),
+        _ => Ok([start, end]),
+    }
+}
+
+/// Applies a profile to a cell
+fn apply(profile: &ChargeProfile, cell: &Battery, helper: Option<&str>) -> Result<(), String> {
+    let thresholds = thresholds(profile, &cell.charge_control)
+        .map_err(|e| format!("cell {}: {}", cell.name, e))?;
+
+    let behaviour = profile
+        .behaviour
+        .as_ref()
+        .map(|v| ("charge_behaviour", v.clone()));
+
+    for (name, value) in thresholds.iter().chain([behaviour].iter()).flatten() {
+        let path = cell.path.join(name);
+        if !path.exists() {
+            return Err(format!("cell {} doesn't support `{}`", cell.name, name));
+        }
+
+        write_attribute(&path, value, helper)?;
+
+        let written = parse_file::<String>(&path);
+        let written = match *name {
+            "charge_behaviour" => written.and_then(|v| active_behaviour(&v)),
+            _ => written,
+        };
+        if written.as_deref() != Some(value.as_str()) {
+            return Err(format!(
+                "`{}` of cell {} reads {:?} after writing {}",
+                name, cell.name, written, value
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Writes a sysfs attribute, through a helper command if any
+fn write_attribute(path: &Path, value: &str, helper: Option<&str>) -> Result<(), String> {
+    match helper {
+        Some(helper) => {
+            let status = std::process::Command::new("sh")
+                .arg("-c")
+                .arg(format!("{} \"$@\"", helper))
+                .arg("sh")
+                .arg(path)
+                .arg(value)
+                .status()
+                .map_err(|e| format!("unable to run `{}`, {}", helper, e))?;
+            if !status.success() {
+                return Err(format!(
+                    "`{}` failed to write {} to {}, {}",
+                    helper,
+                    value,
+                    path.display(),
+                    status
+                ));
+            }
+            Ok(())
+        }
+        None => std::fs::write(path, value)
+            .map_err(|e| format!("unable to write {} to {}, {}", value, path.display(), e)),
+    }
+}
+
+/// Returns the active charge behaviour
+///
+/// The kernel lists the supported behaviours with the active one in
+/// brackets, e.g. `[auto] inhibit-charge force-discharge`.
+fn active_behaviour(text: &str) -> Option<String> {
+    let mut tokens = text.split_whitespace();
+    let active = text
+        .split_whitespace()
+        .find(|v| v.starts_with('[') && v.ends_with(']'))
+        .map(|v| String::from(v.trim_matches(|c| c == '[' || c == ']')));
+
+    match (active, tokens.next(), tokens.next()) {
+        (Some(v), _, _) => Some(v),
+        (None, Some(v), None) => Some(String::from(v)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn profile(name: &str, start: u64, end: u64) -> ChargeProfile {
+        ChargeProfile {
+            name: String::from(name),
+            start_threshold: Some(start),
+            end_threshold: Some(end),
+            behaviour: None,
+        }
+    }
+
+    /// Copies the charge control fixture into a writable directory
+    fn fixture(name: &str) -> PathBuf {
+        let root = std::env::temp_dir().join(name);
+        let _ = std::fs::remove_dir_all(&root);
+
+        let source = PathBuf::from("tests/charge-control");
+        for cell in source.read_dir().unwrap().flatten() {
+            let target = root.join(cell.file_name());
+            std::fs::create_dir_all(&target).unwrap();
+            for file in cell.path().read_dir().unwrap().flatten() {
+                std::fs::copy(file.path(), target.join(file.file_name())).unwrap();
+            }
+        }
+
+        root
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(profile("conserve", 75, 80).validate().is_ok());
+        assert!(profile("invalid", 80, 75).validate().is_err());
+        assert!(profile("invalid", 75, 101).validate().is_err());
+
+        let mut behaviour = profile("inhibit", 0, 100);
+        behaviour.behaviour = Some(String::from("inhibit-charge"));
+        assert!(behaviour.validate().is_ok());
+        behaviour.behaviour = Some(String::from("sleep"));
+        assert!(behaviour.validate().is_err());
+
+        let empty = ChargeProfile {
+            name: String::from("empty"),
+            start_threshold: None,
+            end_threshold: None,
+            behaviour: None,
+        };
+        assert!(empty.validate().is_err());
+    }
+
+    #[test]
+    fn test_active_behaviour() {
+        assert_eq!(
+            active_behaviour("[auto] inhibit-charge force-discharge"),
+            Some(String::from("auto"))
+        );
+        assert_eq!(
+            active_behaviour("auto [inhibit-charge]"),
+            Some(String::from("inhibit-charge"))
+        );
+        assert_eq!(active_behaviour("auto"), Some(String::from("auto")));
+        assert_eq!(active_behaviour("auto inhibit-charge"), None);
+    }
+
+    #[test]
+    fn test_active_profile() {
+        let batteries = Batteries::new(&PathBuf::from("tests/charge-control"), false).unwrap();
+        let profiles = vec![profile("conserve", 75, 80), profile("travel", 95, 100)];
+        let active = active_profile(&profiles, &batteries);
+        assert_eq!(active.map(|v| v.name.as_str()), Some("conserve"));
+
+        let profiles = vec![profile("travel", 95, 100)];
+        assert!(active_profile(&profiles, &batteries).is_none());
+
+        // No cell supports charge control
+        let batteries = Batteries::new(&PathBuf::from("tests/one-battery"), false).unwrap();
+        let profiles = vec![profile("conserve", 75, 80)];
+        assert!(active_profile(&profiles, &batteries).is_none());
+    }
+
+    #[test]
+    fn test_cycle_profile() {
+        let root = fixture("battery-test-charge-cycle");
+        let mut travel = profile("travel", 95, 100);
+        travel.behaviour = Some(String::from("auto"));
+        let profiles = vec![profile("conserve", 75, 80), travel];
+
+        let batteries = Batteries::new(&root, false).unwrap();
+        let applied = cycle_profile(&profiles, &batteries, None).unwrap();
+        assert_eq!(applied.name, "travel");
+
+        let batteries = Batteries::new(&root, false).unwrap();
+        assert_eq!(
+            active_profile(&profiles, &batteries).map(|v| v.name.as_str()),
+            Some("travel")
+        );
+        let cell = root.join("BAT0");
+        assert_eq!(
+            parse_file::<u64>(&cell.join("charge_control_start_threshold")),
+            Some(95)
+        );
+        assert_eq!(
+            parse_file::<u64>(&cell.join("charge_control_end_threshold")),
+            Some(100)
+        );
+
+        let applied = cycle_profile(&profiles, &batteries, None).unwrap();
+        assert_eq!(applied.name, "conserve");
+        let batteries = Batteries::new(&root, false).unwrap();
+        assert_eq!(
+            active_profile(&profiles, &batteries).map(|v| v.name.as_str()),
+            Some("conserve")
+        );
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_thresholds_order() {
+        let control = ChargeControl {
+            start_threshold: Some(75),
+            end_threshold: Some(80),
+            behaviour: None,
+        };
+        let names = |profile: &ChargeProfile| -> Vec<&str> {
+            thresholds(profile, &control)
+                .unwrap()
+                .iter()
+                .flatten()
+                .map(|(name, _)| *name)
+                .collect()
+        };
+
+        // Raising both thresholds above the present end writes the end first
+        assert_eq!(
+            names(&profile("travel", 95, 100)),
+            [
+                "charge_control_end_threshold",
+                "charge_control_start_threshold"
+            ]
+        );
+
+        // Lowering both thresholds below the present start writes the start first
+        assert_eq!(
+            names(&profile("storage", 40, 60)),
+            [
+                "charge_control_start_threshold",
+                "charge_control_end_threshold"
+            ]
+        );
+
+        // A single threshold crossing the present other one can't be written
+        let mut end_only = profile("storage", 0, 60);
+        end_only.start_threshold = None;
+        assert!(thresholds(&end_only, &control).is_err());
+        end_only.end_threshold = Some(90);
+        assert_eq!(names(&end_only), ["charge_control_end_threshold"]);
+
+        let mut start_only = profile("travel", 85, 100);
+        start_only.end_threshold = None;
+        assert!(thresholds(&start_only, &control).is_err());
+    }
+
+    #[test]
+    fn test_cycle_profile_helper() {
+        let root = fixture("battery-test-charge-helper");
+        let profiles = vec![profile("conserve", 75, 80), profile("travel", 95, 100)];
+        let batteries = Batteries::new(&root, false).unwrap();
+
+        let helper = "sh -c 'printf \"%s\\n\" \"$2\" > \"$1\"' helper";
+        let applied = cycle_profile(&profiles, &batteries, Some(helper)).unwrap();
+        assert_eq!(applied.name, "travel");
+
+        // Failing helper
+        let batteries = Batteries::new(&root, false).unwrap();
+        assert!(cycle_profile(&profiles, &batteries, Some("false")).is_err());
+
+        // Helper not writing anything is caught by reading back
+        assert!(cycle_profile(&profiles, &batteries, Some("true")).is_err());
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,482 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use log::{error, info};
+use serde::Deserialize;
+
+use crate::{parse_file, ChargeStatus, Formatter, I3Block, DEFAULT_ERROR};
+
+/// Coarse charge levels reported by devices without a charge percentage
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(crate) enum CapacityLevel {
+    Critical,
+    Low,
+    Normal,
+    High,
+    Full,
+    #[default]
+    Unknown,
+}
+
+impl CapacityLevel {
+    pub(crate) fn parse(text: &str) -> CapacityLevel {
+        match text {
+            "Critical" => CapacityLevel::Critical,
+            "Low" => CapacityLevel::Low,
+            "Normal" => CapacityLevel::Normal,
+            "High" => CapacityLevel::High,
+            "Full" => CapacityLevel::Full,
+            _ => CapacityLevel::Unknown,
+        }
+    }
+
+    /// Returns a charge percentage representative of the level
+    pub(crate) fn charge(self) -> Option<f64> {
+        match self {
+            CapacityLevel::Critical => Some(5.0),
+            CapacityLevel::Low => Some(15.0),
+            CapacityLevel::Normal => Some(50.0),
+            CapacityLevel::High => Some(80.0),
+            CapacityLevel::Full => Some(100.0),
+            CapacityLevel::Unknown => None,
+        }
+    }
+}
+
+impl std::fmt::Display for CapacityLevel {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match *self {
+            CapacityLevel::Critical => write!(f, "critical"),
+            CapacityLevel::Low => write!(f, "low"),
+            CapacityLevel::Normal => write!(f, "normal"),
+            CapacityLevel::High => write!(f, "high"),
+            CapacityLevel::Full => write!(f, "full"),
+            CapacityLevel::Unknown => write!(f, "unknown"),
+        }
+    }
+}
+
+/// Device mode options
+#[derive(Clone, Deserialize)]
+#[serde(default)]
+pub struct DeviceOptions {
+    low_threshold: f64,                // default low charge threshold (%)
+    thresholds: BTreeMap<String, f64>, // low charge thresholds per device (%)
+    separator: String,                 // text between devices
+}
+
+impl Default for DeviceOptions {
+    fn default() -> Self {
+        DeviceOptions {
+            low_threshold: 20.0,
+            thresholds: BTreeMap::new(),
+            separator: String::from(" "),
+        }
+    }
+}
+
+impl DeviceOptions {
+    /// Validates the low charge thresholds
+    ///
+    /// # Returns
+    ///
+    /// A `Result`:
+    /// - `Ok`: If all thresholds are within [0, 100]
+    /// - `Err`: A `String` describing the invalid threshold
+    pub(crate) fn validate(&self) -> Result<(), String> {
+        let thresholds = std::iter::once(&self.low_threshold).chain(self.thresholds.values());
+        for threshold in thresholds {
+            if !(0.0..=100.0).contains(threshold) {
+                return Err(format!(
+                    "invalid device threshold {}, select from [0, 100]",
+                    threshold
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Peripheral device battery information, e.g. of a wireless mouse
+#[derive(Debug)]
+pub struct Device {
+    name: String,                  // name of the supply
+    model_name: Option<String>,    // model of the device
+    manufacturer: Option<String>,  // manufacturer of the device
+    capacity: Option<u8>,          // present charge (%)
+    capacity_level: CapacityLevel, // coarse charge level
+    charge_status: ChargeStatus,   // charging status
+}
+
+impl Device {
+    /// Parses a peripheral device battery
+    ///
+    /// # Arguments
+    ///
+    /// - `path`: Path to the supply's directory
+    ///
+    /// # Returns
+    ///
+    /// An `Option`:
+    /// - `Some`: A `Device` if the supply is a battery of `Device` scope
+    /// - `None`: If the supply is a system battery, of any other type or
+    ///   reports no charge at all
+    pub fn new(path: &Path) -> Option<Device> {
+        if !is_device(path)
+            || parse_file::<String>(&path.join("type")).as_deref() != Some("Battery")
+        {
+            return None;
+        }
+
+        let name = match path.file_name().and_then(|v| v.to_str()) {
+            Some(v) => String::from(v),
+            None => {
+                error!("battery::Device::new: unable to parse name");
+                return None;
+            }
+        };
+
+        let capacity = parse_file::<u8>(&path.join("capacity")).map(|v| v.min(100));
+        let capacity_level = parse_file::<String>(&path.join("capacity_level"))
+            .map(|v| CapacityLevel::parse(&v))
+            .unwrap_or(CapacityLevel::Unknown);
+        if capacity.is_none() && capacity_level == CapacityLevel::Unknown {
+            error!(
+                "battery::Device::new: unable to parse `capacity` or \
+                  `capacity_level` of device {}",
+                name
+            );
+            return None;
+        }
+
+        let charge_status = match parse_file::<String>(&path.join("status")).as_deref() {
+            Some("Charging") => ChargeStatus::Charging,
+            Some("Discharging") => ChargeStatus::Discharging,
+            Some("Full") => ChargeStatus::Full,
+            Some("Not charging") => ChargeStatus::Idle,
+            _ => ChargeStatus::Unknown,
+        };
+
+        Some(Device {
+            name,
+            model_name: parse_file(&path.join("model_name")),
+            manufacturer: parse_file(&path.join("manufacturer")),
+            capacity,
+            capacity_level,
+            charge_status,
+        })
+    }
+
+    /// Returns the name displayed for the device
+    ///
+    /// The model name is preferred over the name of the supply, which is
+    /// usually derived from the device's address.
+    pub fn label(&self) -> &str {
+        self.model_name.as_deref().unwrap_or(&self.name)
+    }
+
+    /// Returns the present charge of the device (%)
+    ///
+    /// Devices reporting only a capacity level are given a charge
+    /// representative of their level.
+    pub fn charge(&self) -> Option<f64> {
+        self.capacity
+            .map(f64::from)
+            .or_else(|| self.capacity_level.charge())
+    }
+
+    /// Returns whether the device is low on charge
+    ///
+    /// # Arguments
+    ///
+    /// - `options`: Device mode options with the low charge thresholds
+    pub fn is_low(&self, options: &DeviceOptions) -> bool {
+        if self.charge_status == ChargeStatus::Charging {
+            return false;
+        }
+
+        match self.capacity {
+            Some(capacity) => f64::from(capacity) <= self.threshold(options),
+            None => matches!(
+                self.capacity_level,
+                CapacityLevel::Critical | CapacityLevel::Low
+            ),
+        }
+    }
+
+    /// Returns the low charge threshold of the device (%)
+    ///
+    /// Thresholds are looked up by model name first, then by supply name.
+    fn threshold(&self, options: &DeviceOptions) -> f64 {
+        self.model_name
+            .as_ref()
+            .and_then(|v| options.thresholds.get(v))
+            .or_else(|| options.thresholds.get(&self.name))
+            .copied()
+            .unwrap_or(options.low_threshold)
+    }
+
+    /// Returns the charge displayed for the device
+    fn charge_text(&self) -> String {
+        match self.capacity {
+            Some(capacity) => format!("{}%", capacity),
+            None => self.capacity_level.to_string(),
+        }
+    }
+}
+
+impl std::fmt::Display for Device {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let manufacturer = self.manufacturer.as_deref().unwrap_or("unknown");
+        write!(
+            f,
+            "{} ({}), {}, {}",
+            self.label(),
+            manufacturer,
+            self.charge_text(),
+            self.charge_status
+        )
+    }
+}
+
+/// Peripheral device batteries
+#[derive(Default)]
+pub struct Devices {
+    devices: Vec<Device>, // available peripheral devices
+
+    options: DeviceOptions, // device mode options
+    formatter: Formatter,   // output format
+}
+
+impl Devices {
+    /// Gathers all peripheral device batteries
+    ///
+    /// Devices are sorted by name so that the list doesn't shuffle between
+    /// invocations of the block.
+    ///
+    /// # Arguments
+    ///
+    /// - `path`: Path to the `power_supply` directory
+    /// - `log`: Log device information
+    ///
+    /// # Returns
+    ///
+    /// A `Result`:
+    /// - `Ok`: `Devices`, possibly without any device
+    /// - `Err`: A `String` with error to be displayed by i3
+    pub fn new(path: &Path, log: bool) -> Result<Devices, String> {
+        let mut devices = Devices::default();
+
+        let entries = match path.read_dir() {
+            Ok(v) => v,
+            Err(e) => {
+                error!("battery::Devices::new: {}", e);
+                return Err(DEFAULT_ERROR.into());
+            }
+        };
+
+        devices.devices = entries
+            .flatten()
+            .filter_map(|entry| Device::new(&entry.path()))
+            .collect();
+        devices.devices.sort_by(|a, b| a.name.cmp(&b.name));
+
+        if log {
+            for device in &devices.devices {
+                info!("battery::Devices::new: device {}, {}", device.name, device);
+            }
+        }
+
+        Ok(devices)
+    }
+
+    /// Sets the device mode options
+    ///
+    /// # Arguments
+    ///
+    /// - `options`: Device mode options from the configuration file
+    pub fn set_options(&mut self, options: DeviceOptions) {
+        self.options = options;
+    }
+
+    /// Sets the output format
+    ///
+    /// # Arguments
+    ///
+    /// - `formatter`: A validated `Formatter`
+    pub fn set_formatter(&mut self, formatter: Formatter) {
+        self.formatter = formatter;
+    }
+
+    /// Returns whether any device is low on charge
+    pub fn any_low(&self) -> bool {
+        self.devices
+            .iter()
+            .any(|device| device.is_low(&self.options))
+    }
+}
+
+impl I3Block for Devices {
+    /// Lists the devices with their charge
+    ///
+    /// The short text omits the names of the devices. The list is colored by
+    /// the lowest charge of the devices that are low, and the output is empty
+    /// without any device so that i3blocks hides the block.
+    fn format_i3(&self) -> String {
+        if self.devices.is_empty() {
+            return String::new();
+        }
+
+        let separator = &self.options.separator;
+        let full_text: Vec<String> = self
+            .devices
+            .iter()
+            .map(|device| format!("{} {}", device.label(), device.charge_text()))
+            .collect();
+        let short_text: Vec<String> = self
+            .devices
+            .iter()
+            .map(|device| device.charge_text())
+            .collect();
+
+        let lowest = self
+            .devices
+            .iter()
+            .filter(|device| device.is_low(&self.options))
+            .filter_map(|device| device.charge())
+            .fold(None, |lowest: Option<f64>, charge| {
+                Some(lowest.map_or(charge, |v| v.min(charge)))
+            });
+        let color = match lowest {
+            Some(charge) => self.formatter.color(ChargeStatus::Discharging, charge),
+            None => self.formatter.color(ChargeStatus::Unknown, 100.0),
+        };
+
+        format!(
+            "{}\n{}\n{}",
+            full_text.join(separator),
+            short_text.join(separator),
+            color
+        )
+    }
+}
+
+/// Returns whether a supply belongs to a peripheral device
+///
+/// Supplies without a `scope` attribute are considered part of the system.
+pub(crate) fn is_device(path: &Path) -> bool {
+    parse_file::<String>(&path.join("scope")).as_deref() == Some("Device")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn devices(options: DeviceOptions) -> Devices {
+        let mut devices = Devices::new(&PathBuf::from("tests/devices"), false).unwrap();
+        devices.set_options(options);
+        devices
+    }
+
+    #[test]
+    fn test_device() {
+        let path = PathBuf::from("tests/devices/hidpp_battery_0");
+        let device = Device::new(&path);
+        assert!(device.is_some());
+
+        let device = device.unwrap();
+        assert_eq!(device.label(), "MX Master 3");
+        assert_eq!(device.charge(), Some(85.0));
+        assert_eq!(device.charge_status, ChargeStatus::Discharging);
+        assert_eq!(
+            device.to_string(),
+            "MX Master 3 (Logitech), 85%, discharging"
+        );
+    }
+
+    #[test]
+    fn test_device_capacity_level() {
+        let path = PathBuf::from("tests/devices/hid-e8:07:bf:12:34:56-battery");
+        let device = Device::new(&path);
+        assert!(device.is_some());
+
+        let device = device.unwrap();
+        assert_eq!(device.label(), "K380");
+        assert_eq!(device.charge(), Some(15.0));
+        assert!(device.is_low(&DeviceOptions::default()));
+    }
+
+    #[test]
+    fn test_device_system() {
+        assert!(Device::new(&PathBuf::from("tests/devices/BAT0")).is_none());
+        assert!(Device::new(&PathBuf::from("tests/devices/AC")).is_none());
+    }
+
+    #[test]
+    fn test_devices() {
+        let devices = devices(DeviceOptions::default());
+        let names: Vec<&str> = devices.devices.iter().map(|v| v.name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec![
+                "hid-e8:07:bf:12:34:56-battery",
+                "hidpp_battery_0",
+                "ps-controller-battery-a0:5a:5e:00:11:22",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_devices_thresholds() {
+        let headset = PathBuf::from("tests/devices/ps-controller-battery-a0:5a:5e:00:11:22");
+        let headset = Device::new(&headset).unwrap();
+        let mouse = Device::new(&PathBuf::from("tests/devices/hidpp_battery_0")).unwrap();
+
+        let mut options = DeviceOptions::default();
+        assert!(!headset.is_low(&options));
+        assert!(!mouse.is_low(&options));
+
+        options.thresholds.insert("MX Master 3".into(), 90.0);
+        options
+            .thresholds
+            .insert("ps-controller-battery-a0:5a:5e:00:11:22".into(), 40.0);
+        assert!(mouse.is_low(&options));
+
+        // Charging devices are never low
+        assert!(!headset.is_low(&options));
+    }
+
+    #[test]
+    fn test_devices_format() {
+        let options = DeviceOptions {
+            separator: String::from(" | "),
+            ..DeviceOptions::default()
+        };
+        let devices = devices(options);
+        assert!(devices.any_low());
+        assert_eq!(
+            devices.format_i3(),
+            "K380 low | MX Master 3 85% | Wireless Controller 30%\n\
+             low | 85% | 30%\n\
+             #FF3700"
+        );
+    }
+
+    #[test]
+    fn test_devices_empty() {
+        let devices = Devices::new(&PathBuf::from("tests/one-battery"), false).unwrap();
+        assert!(!devices.any_low());
+        assert_eq!(devices.format_i3(), "");
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(DeviceOptions::default().validate().is_ok());
+
+        let mut options = DeviceOptions::default();
+        options.thresholds.insert("K380".into(), 120.0);
+        assert!(options.validate().is_err());
+    }
+}
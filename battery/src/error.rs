@@ -0,0 +1,58 @@
+use std::fmt;
+use std::path::PathBuf;
+
+/// Errors reading battery cells from sysfs
+#[derive(Debug)]
+pub enum BatteryError {
+    MissingAttribute {
+        cell: String,            // name of the cell
+        attribute: &'static str, // attribute that is required but absent
+    },
+    InvalidValue {
+        cell: String,            // name of the cell
+        attribute: &'static str, // attribute with the invalid contents
+        value: String,           // contents of the attribute
+    },
+    InconsistentReading {
+        cell: String,   // name of the cell
+        reason: String, // readings that contradict each other
+    },
+    Io {
+        path: PathBuf,         // path that couldn't be read
+        error: std::io::Error, // underlying error
+    },
+    NoCells, // no cell was selected or readable
+}
+
+impl fmt::Display for BatteryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BatteryError::MissingAttribute { cell, attribute } => {
+                write!(f, "cell {} lacks `{}`", cell, attribute)
+            }
+            BatteryError::InvalidValue {
+                cell,
+                attribute,
+                value,
+            } => write!(
+                f,
+                "cell {} has an invalid `{}` of '{}'",
+                cell, attribute, value
+            ),
+            BatteryError::InconsistentReading { cell, reason } => {
+                write!(f, "cell {} has inconsistent readings, {}", cell, reason)
+            }
+            BatteryError::Io { path, error } => write!(f, "{}, {}", path.display(), error),
+            BatteryError::NoCells => write!(f, "no battery cells"),
+        }
+    }
+}
+
+impl std::error::Error for BatteryError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            BatteryError::Io { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,128 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::ChargeStatus;
+
+/// Rate history of a single cell
+#[derive(Deserialize, Serialize)]
+struct CellRates {
+    status: ChargeStatus, // charging status the samples belong to
+    average: Option<f64>, // smoothed rate, `None` before the first sample (mW)
+}
+
+impl CellRates {
+    fn new(status: ChargeStatus) -> CellRates {
+        CellRates {
+            status,
+            average: None,
+        }
+    }
+}
+
+/// Exponentially weighted moving average of the cells' (dis)charge rates
+///
+/// The estimator is persisted in the `State` between invocations of the
+/// `battery` block so that the rates can be smoothed over several i3blocks
+/// intervals.
+#[derive(Default, Deserialize, Serialize)]
+pub struct RateEstimator {
+    cells: BTreeMap<String, CellRates>, // rate history per cell
+}
+
+impl RateEstimator {
+    /// Adds a rate sample for a cell and returns its smoothed rate
+    ///
+    /// Only (dis)charging cells are smoothed, any other cell's rate is
+    /// returned unchanged. The history of a cell is reset whenever it flips
+    /// between charging and discharging, and zero samples are ignored so
+    /// that a momentary lack of reading doesn't skew the average.
+    ///
+    /// # Arguments
+    ///
+    /// - `name`: Name of the cell
+    /// - `status`: Charging status of the cell
+    /// - `rate`: Present (dis)charge rate of the cell (mW)
+    /// - `alpha`: Weight of the newest sample in the moving average
+    ///
+    /// # Returns
+    ///
+    /// The smoothed (dis)charge rate of the cell (mW)
+    pub(crate) fn update(
+        &mut self,
+        name: &str,
+        status: ChargeStatus,
+        rate: u64,
+        alpha: f64,
+    ) -> u64 {
+        if status != ChargeStatus::Charging && status != ChargeStatus::Discharging {
+            return rate;
+        }
+
+        let cell = self
+            .cells
+            .entry(String::from(name))
+            .or_insert_with(|| CellRates::new(status));
+        if cell.status != status {
+            *cell = CellRates::new(status);
+        }
+
+        if rate > 0 {
+            let rate = rate as f64;
+            cell.average = Some(match cell.average {
+                Some(average) => alpha * rate + (1.0 - alpha) * average,
+                None => rate,
+            });
+        }
+
+        cell.average.unwrap_or(0.0).round() as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Batteries, State};
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_update() {
+        let mut estimator = RateEstimator::default();
+        let mut update = |status, rate| estimator.update("BAT0", status, rate, 0.5);
+        assert_eq!(update(ChargeStatus::Discharging, 1000), 1000);
+        assert_eq!(update(ChargeStatus::Discharging, 2000), 1500);
+        assert_eq!(update(ChargeStatus::Discharging, 0), 1500);
+        assert_eq!(update(ChargeStatus::Full, 500), 500);
+        assert_eq!(update(ChargeStatus::Discharging, 3000), 2250);
+        assert_eq!(update(ChargeStatus::Charging, 800), 800);
+        assert_eq!(estimator.update("BAT1", ChargeStatus::Charging, 0, 0.5), 0);
+    }
+
+    #[test]
+    fn test_smoothing_sequence() {
+        // (present rate, time remaining) after each snapshot
+        let expected = [
+            (29337, 1973),
+            (24532, 2345),
+            (30159, 1896),
+            (30159, 1888),
+            (16650, 9513),
+        ];
+
+        let path = std::env::temp_dir().join("battery-test-estimator-sequence.yaml");
+        let _ = std::fs::remove_file(&path);
+
+        for (i, (rate, time)) in expected.iter().enumerate() {
+            let mut state = State::load(&path);
+            let snapshot = PathBuf::from(format!("tests/smoothing/{}", i));
+            let mut batteries = Batteries::new(&snapshot, false).unwrap();
+            batteries.smooth(&mut state.rates, 0.3);
+            state.save(&path);
+
+            assert_eq!(batteries.cells[0].present_rate, *rate);
+            assert_eq!(batteries.time_remaining, Some(*time));
+        }
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
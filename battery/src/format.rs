@@ -0,0 +1,624 @@
+use serde::Deserialize;
+
+use crate::ChargeStatus;
+
+// Constants
+const DEFAULT_FULL_TEXT: &str = "BAT {charge}%[ ({capacity}%)] {status}[ {time}]";
+const DEFAULT_SHORT_TEXT: &str = "BAT {charge}%[ {time}]";
+
+/// Threshold above which a color is used
+#[derive(Clone, Deserialize)]
+pub struct ColorStop {
+    threshold: f64, // charge (%) or power (W) threshold
+    color: String,  // color as `#RRGGBB`
+}
+
+/// Labels displayed for each charge status
+#[derive(Clone, Deserialize)]
+#[serde(default)]
+pub struct StatusLabels {
+    charging: String,
+    discharging: String,
+    full: String,
+    idle: String,
+    unknown: String,
+}
+
+impl Default for StatusLabels {
+    fn default() -> Self {
+        StatusLabels {
+            charging: String::from("CHR"),
+            discharging: String::from("DIS"),
+            full: String::from("FUL"),
+            idle: String::from("IDL"),
+            unknown: String::from("UKN"),
+        }
+    }
+}
+
+/// Display of the individual cells
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum CellDisplay {
+    #[default]
+    Combined, // all cells combined into a single text
+    Separate, // each cell rendered separately
+}
+
+/// Output format options as written in the configuration file
+#[derive(Clone, Deserialize)]
+#[serde(default)]
+pub struct Format {
+    full_text: String,
+    short_text: String,
+    status_labels: StatusLabels,
+    ac_label: String,
+    cell_display: CellDisplay,
+    cell_separator: String,
+    color_stops: Vec<ColorStop>,
+    power_color_stops: Vec<ColorStop>,
+    color_gradient: bool,
+    color_charging: String,
+    color_default: String,
+}
+
+impl Default for Format {
+    fn default() -> Self {
+        let stops = [
+            (90.0, "#00FF00"),
+            (80.0, "#37FF00"),
+            (70.0, "#75FF00"),
+            (60.0, "#AAFF00"),
+            (50.0, "#E1FF00"),
+            (40.0, "#FFE100"),
+            (30.0, "#FFAA00"),
+            (20.0, "#FF7300"),
+            (10.0, "#FF3700"),
+            (0.0, "#FF0000"),
+        ];
+
+        Format {
+            full_text: String::from(DEFAULT_FULL_TEXT),
+            short_text: String::from(DEFAULT_SHORT_TEXT),
+            status_labels: StatusLabels::default(),
+            ac_label: String::from("AC"),
+            cell_display: CellDisplay::Combined,
+            cell_separator: String::from(" "),
+            color_stops: stops
+                .iter()
+                .map(|(threshold, color)| ColorStop {
+                    threshold: *threshold,
+                    color: String::from(*color),
+                })
+                .collect(),
+            power_color_stops: Vec::new(),
+            color_gradient: false,
+            color_charging: String::from("#00FF00"),
+            color_default: String::from("#FFFFFF"),
+        }
+    }
+}
+
+/// Values that can be substituted into a template
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Value {
+    Ac,           // label shown while an external power source is online
+    Adapter,      // maximum power of the online external power sources (W)
+    Capacity,     // remaining capacity relative to design (%)
+    Charge,       // present charge (%)
+    Name,         // name of the cell, empty for all cells combined
+    Profile,      // name of the active charge profile
+    Status,       // charge status label
+    Time,         // remaining (dis)charge time
+    Watts,        // instantaneous power flow (W)
+    WattsAverage, // smoothed power flow (W)
+    Wear,         // capacity lost relative to design (%)
+    Eta,          // time of day the (dis)charge completes
+}
+
+/// Placeholder of a template, e.g. `{charge}` or `{charge:BAT0}`
+#[derive(Clone, Debug, PartialEq)]
+pub struct Placeholder {
+    pub value: Value,         // value to substitute
+    pub cell: Option<String>, // cell to read the value from, all if `None`
+}
+
+/// Segment of a template
+#[derive(Clone, Debug, PartialEq)]
+enum Segment {
+    Text(String),
+    Placeholder(Placeholder),
+    Optional(Vec<Segment>), // omitted if any of its placeholders is empty
+}
+
+/// Parsed text template
+///
+/// Templates are plain text with placeholders enclosed in braces, e.g.
+/// `BAT {charge}%`. Text enclosed in brackets, e.g. `[ {time}]`, is only
+/// displayed if all the placeholders within it have a value. Braces,
+/// brackets and backslashes are escaped with a backslash.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Template(Vec<Segment>);
+
+impl Template {
+    /// Parses a template
+    ///
+    /// # Arguments
+    ///
+    /// - `text`: The template text
+    ///
+    /// # Returns
+    ///
+    /// A `Result`:
+    /// - `Ok`: A parsed `Template`
+    /// - `Err`: A `String` describing why the template is invalid
+    pub fn parse(text: &str) -> Result<Template, String> {
+        let mut segments = Vec::new();
+        let mut optional: Option<(usize, Vec<Segment>)> = None;
+        let mut literal = String::new();
+
+        let mut chars = text.char_indices();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '\\' => match chars.next() {
+                    Some((_, c)) => literal.push(c),
+                    None => return Err(format!("dangling escape at {} in '{}'", i, text)),
+                },
+                '{' => {
+                    let mut name = String::new();
+                    let mut closed = false;
+                    for (_, c) in chars.by_ref() {
+                        match c {
+                            '}' => {
+                                closed = true;
+                                break;
+                            }
+                            '{' => return Err(format!("nested '{{' at {} in '{}'", i, text)),
+                            c => name.push(c),
+                        }
+                    }
+                    if !closed {
+                        return Err(format!("unclosed '{{' at {} in '{}'", i, text));
+                    }
+
+                    let placeholder = parse_placeholder(&name)
+                        .map_err(|e| format!("{} at {} in '{}'", e, i, text))?;
+
+                    let current = match &mut optional {
+                        Some((_, v)) => v,
+                        None => &mut segments,
+                    };
+                    if !literal.is_empty() {
+                        current.push(Segment::Text(std::mem::take(&mut literal)));
+                    }
+                    current.push(Segment::Placeholder(placeholder));
+                }
+                '}' => return Err(format!("unmatched '}}' at {} in '{}'", i, text)),
+                '[' => {
+                    if optional.is_some() {
+                        return Err(format!("nested '[' at {} in '{}'", i, text));
+                    }
+                    if !literal.is_empty() {
+                        segments.push(Segment::Text(std::mem::take(&mut literal)));
+                    }
+                    optional = Some((i, Vec::new()));
+                }
+                ']' => match optional.take() {
+                    Some((_, mut v)) => {
+                        if !literal.is_empty() {
+                            v.push(Segment::Text(std::mem::take(&mut literal)));
+                        }
+                        segments.push(Segment::Optional(v));
+                    }
+                    None => return Err(format!("unmatched ']' at {} in '{}'", i, text)),
+                },
+                c => literal.push(c),
+            }
+        }
+
+        if let Some((i, _)) = optional {
+            return Err(format!("unclosed '[' at {} in '{}'", i, text));
+        }
+        if !literal.is_empty() {
+            segments.push(Segment::Text(literal));
+        }
+
+        Ok(Template(segments))
+    }
+
+    /// Renders the template
+    ///
+    /// # Arguments
+    ///
+    /// - `resolve`: A function returning the text of a placeholder, an empty
+    ///   text if the placeholder has no value
+    pub fn render<F>(&self, resolve: F) -> String
+    where
+        F: Fn(&Placeholder) -> String,
+    {
+        let mut output = String::new();
+        for segment in &self.0 {
+            match segment {
+                Segment::Text(text) => output.push_str(text),
+                Segment::Placeholder(placeholder) => output.push_str(&resolve(placeholder)),
+                Segment::Optional(segments) => {
+                    let mut section = String::new();
+                    let mut complete = true;
+                    for segment in segments {
+                        match segment {
+                            Segment::Text(text) => section.push_str(text),
+                            Segment::Placeholder(placeholder) => {
+                                let value = resolve(placeholder);
+                                complete &= !value.is_empty();
+                                section.push_str(&value);
+                            }
+                            Segment::Optional(_) => (),
+                        }
+                    }
+                    if complete {
+                        output.push_str(&section);
+                    }
+                }
+            }
+        }
+
+        output
+    }
+}
+
+/// Parses the contents of a placeholder, e.g. `charge` or `charge:BAT0`
+fn parse_placeholder(name: &str) -> Result<Placeholder, String> {
+    let (value, cell) = match name.split_once(':') {
+        Some((value, cell)) => {
+            if cell.is_empty() {
+                return Err(format!("missing cell name in placeholder '{{{}}}'", name));
+            }
+            (value, Some(String::from(cell)))
+        }
+        None => (name, None),
+    };
+
+    let value = match value {
+        "ac" => Value::Ac,
+        "adapter" => Value::Adapter,
+        "capacity" => Value::Capacity,
+        "charge" => Value::Charge,
+        "eta" => Value::Eta,
+        "name" => Value::Name,
+        "profile" => Value::Profile,
+        "status" => Value::Status,
+        "time" => Value::Time,
+        "watts" => Value::Watts,
+        "watts_avg" => Value::WattsAverage,
+        "wear" => Value::Wear,
+        _ => {
+            return Err(format!(
+                "unknown placeholder '{{{}}}', select from \
+                 [ac, adapter, capacity, charge, eta, name, profile, status, time, watts, \
+                 watts_avg, wear]",
+                name
+            ))
+        }
+    };
+
+    Ok(Placeholder { value, cell })
+}
+
+/// Color as red, green and blue components
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Color(u8, u8, u8);
+
+impl Color {
+    /// Parses a color written as `#RRGGBB`
+    fn parse(text: &str) -> Result<Color, String> {
+        let hex = match text.strip_prefix('#') {
+            Some(v) if v.len() == 6 && v.chars().all(|c| c.is_ascii_hexdigit()) => v,
+            _ => return Err(format!("invalid color '{}', expected '#RRGGBB'", text)),
+        };
+
+        let component = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).unwrap();
+        Ok(Color(component(0), component(2), component(4)))
+    }
+
+    /// Linearly interpolates between two colors
+    fn lerp(&self, other: &Color, t: f64) -> Color {
+        let mix = |a: u8, b: u8| ((a as f64) + t * ((b as f64) - (a as f64))).round() as u8;
+        Color(
+            mix(self.0, other.0),
+            mix(self.1, other.1),
+            mix(self.2, other.2),
+        )
+    }
+}
+
+impl std::fmt::Display for Color {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "#{:02X}{:02X}{:02X}", self.0, self.1, self.2)
+    }
+}
+
+/// Validated output format
+#[derive(Clone)]
+pub struct Formatter {
+    pub full_text: Template,  // template of the full text
+    pub short_text: Template, // template of the short text
+
+    pub(crate) cell_display: CellDisplay, // display of the individual cells
+    pub(crate) cell_separator: String,    // text between separate cells
+
+    labels: StatusLabels,           // labels for each charge status
+    ac_label: String,               // label shown while plugged in
+    stops: Vec<(f64, Color)>,       // color stops sorted by threshold
+    power_stops: Vec<(f64, Color)>, // power color stops sorted by threshold
+    gradient: bool,                 // interpolate between color stops
+    color_charging: Color,          // color while charging
+    color_default: Color,           // color while neither (dis)charging
+}
+
+impl Formatter {
+    /// Validates output format options
+    ///
+    /// # Arguments
+    ///
+    /// - `format`: Output format options from the configuration file
+    ///
+    /// # Returns
+    ///
+    /// A `Result`:
+    /// - `Ok`: A validated `Formatter`
+    /// - `Err`: A `String` describing the invalid option
+    pub fn new(format: &Format) -> Result<Formatter, String> {
+        let full_text =
+            Template::parse(&format.full_text).map_err(|e| format!("full_text: {}", e))?;
+        let short_text =
+            Template::parse(&format.short_text).map_err(|e| format!("short_text: {}", e))?;
+
+        if format.color_stops.is_empty() {
+            return Err(String::from("color_stops: at least one stop is required"));
+        }
+        let stops = parse_stops(&format.color_stops).map_err(|e| format!("color_stops: {}", e))?;
+        let power_stops = parse_stops(&format.power_color_stops)
+            .map_err(|e| format!("power_color_stops: {}", e))?;
+
+        let color_charging =
+            Color::parse(&format.color_charging).map_err(|e| format!("color_charging: {}", e))?;
+        let color_default =
+            Color::parse(&format.color_default).map_err(|e| format!("color_default: {}", e))?;
+
+        Ok(Formatter {
+            full_text,
+            short_text,
+            cell_display: format.cell_display,
+            cell_separator: format.cell_separator.clone(),
+            labels: format.status_labels.clone(),
+            ac_label: format.ac_label.clone(),
+            stops,
+            power_stops,
+            gradient: format.color_gradient,
+            color_charging,
+            color_default,
+        })
+    }
+
+    /// Returns the label of a charge status
+    pub(crate) fn label(&self, status: ChargeStatus) -> &str {
+        match status {
+            ChargeStatus::Charging => &self.labels.charging,
+            ChargeStatus::Discharging => &self.labels.discharging,
+            ChargeStatus::Full => &self.labels.full,
+            ChargeStatus::Idle => &self.labels.idle,
+            ChargeStatus::Unknown => &self.labels.unknown,
+        }
+    }
+
+    /// Returns the label shown while an external power source is online
+    pub(crate) fn ac_label(&self) -> &str {
+        &self.ac_label
+    }
+
+    /// Returns the color for a charge status and charge percentage
+    pub(crate) fn color(&self, status: ChargeStatus, charge: f64) -> String {
+        match status {
+            ChargeStatus::Charging => self.color_charging.to_string(),
+            ChargeStatus::Discharging => self.charge_color(charge).to_string(),
+            _ => self.color_default.to_string(),
+        }
+    }
+
+    /// Returns the color for a power draw, if colored by power
+    ///
+    /// The power stops only apply while discharging, since the power drawn
+    /// by the system is unknown while charging.
+    ///
+    /// # Arguments
+    ///
+    /// - `status`: Overall charge status
+    /// - `watts`: Power flowing out of the cells (W)
+    pub(crate) fn power_color(&self, status: ChargeStatus, watts: f64) -> Option<String> {
+        if status != ChargeStatus::Discharging || self.power_stops.is_empty() {
+            return None;
+        }
+
+        Some(self.stop_color(&self.power_stops, watts).to_string())
+    }
+
+    /// Returns the color of the stops for a charge percentage
+    fn charge_color(&self, charge: f64) -> Color {
+        self.stop_color(&self.stops, charge)
+    }
+
+    /// Returns the color of a set of stops for a value
+    fn stop_color(&self, stops: &[(f64, Color)], value: f64) -> Color {
+        let upper = stops.iter().position(|(threshold, _)| *threshold > value);
+        match upper {
+            Some(0) => stops[0].1,
+            Some(i) => {
+                let (low, low_color) = stops[i - 1];
+                let (high, high_color) = stops[i];
+                if self.gradient {
+                    low_color.lerp(&high_color, (value - low) / (high - low))
+                } else {
+                    low_color
+                }
+            }
+            None => stops[stops.len() - 1].1,
+        }
+    }
+}
+
+impl Default for Formatter {
+    fn default() -> Self {
+        Formatter::new(&Format::default()).expect("default format is valid")
+    }
+}
+
+/// Parses color stops sorted by threshold
+fn parse_stops(stops: &[ColorStop]) -> Result<Vec<(f64, Color)>, String> {
+    let mut parsed = Vec::new();
+    for stop in stops {
+        parsed.push((stop.threshold, Color::parse(&stop.color)?));
+    }
+    parsed.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+
+    Ok(parsed)
+}
+
+/// Formats a duration as `H:MM:SS`
+pub(crate) fn format_time(seconds: u64) -> String {
+    let hours = seconds / 3600;
+    let minutes = (seconds % 3600) / 60;
+    let seconds = seconds % 60;
+
+    format!("{}:{:02}:{:02}", hours, minutes, seconds)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn placeholder(value: Value, cell: Option<&str>) -> Segment {
+        Segment::Placeholder(Placeholder {
+            value,
+            cell: cell.map(String::from),
+        })
+    }
+
+    #[test]
+    fn test_parse_template() {
+        let template = Template::parse("BAT {charge}% {status:BAT1}[ {time}] \\[x\\]");
+        assert_eq!(
+            template,
+            Ok(Template(vec![
+                Segment::Text(String::from("BAT ")),
+                placeholder(Value::Charge, None),
+                Segment::Text(String::from("% ")),
+                placeholder(Value::Status, Some("BAT1")),
+                Segment::Optional(vec![
+                    Segment::Text(String::from(" ")),
+                    placeholder(Value::Time, None),
+                ]),
+                Segment::Text(String::from(" [x]")),
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_parse_template_errors() {
+        let invalid = [
+            "BAT {charge",
+            "BAT charge}",
+            "BAT {{charge}}",
+            "BAT {volts}",
+            "BAT {charge:}",
+            "BAT [{time}",
+            "BAT {time}]",
+            "BAT [[{time}]]",
+            "BAT \\",
+        ];
+        for text in invalid.iter() {
+            assert!(Template::parse(text).is_err(), "{}", text);
+        }
+    }
+
+    #[test]
+    fn test_render_template() {
+        let template = Template::parse("{charge}%[ {time}][ ({watts} W)]").unwrap();
+        let output = template.render(|p| match p.value {
+            Value::Charge => String::from("42"),
+            Value::Watts => String::from("7.5"),
+            _ => String::new(),
+        });
+        assert_eq!(output, "42% (7.5 W)");
+    }
+
+    #[test]
+    fn test_color_parse() {
+        assert_eq!(Color::parse("#0A0b1C"), Ok(Color(10, 11, 28)));
+        assert!(Color::parse("#FF000").is_err());
+        assert!(Color::parse("FF0000").is_err());
+        assert!(Color::parse("#GG0000").is_err());
+    }
+
+    #[test]
+    fn test_charge_color() {
+        let mut formatter = Formatter::default();
+        assert_eq!(formatter.charge_color(100.0).to_string(), "#00FF00");
+        assert_eq!(formatter.charge_color(85.0).to_string(), "#37FF00");
+        assert_eq!(formatter.charge_color(5.0).to_string(), "#FF0000");
+        assert_eq!(formatter.charge_color(-1.0).to_string(), "#FF0000");
+
+        formatter.gradient = true;
+        assert_eq!(formatter.charge_color(5.0).to_string(), "#FF1C00");
+        assert_eq!(formatter.charge_color(95.0).to_string(), "#00FF00");
+    }
+
+    #[test]
+    fn test_power_color() {
+        let format: Format = serde_yaml::from_str(
+            "power_color_stops:\n  \
+             - { threshold: 0, color: '#00FF00' }\n  \
+             - { threshold: 10, color: '#FFFF00' }\n  \
+             - { threshold: 25, color: '#FF0000' }\n",
+        )
+        .unwrap();
+        let formatter = Formatter::new(&format).unwrap();
+        let color = |watts| formatter.power_color(ChargeStatus::Discharging, watts);
+        assert_eq!(color(5.0).as_deref(), Some("#00FF00"));
+        assert_eq!(color(12.5).as_deref(), Some("#FFFF00"));
+        assert_eq!(color(40.0).as_deref(), Some("#FF0000"));
+        assert_eq!(formatter.power_color(ChargeStatus::Charging, 40.0), None);
+
+        let formatter = Formatter::default();
+        assert_eq!(formatter.power_color(ChargeStatus::Discharging, 40.0), None);
+    }
+
+    #[test]
+    fn test_formatter_invalid_color() {
+        let format = Format {
+            color_charging: String::from("green"),
+            ..Default::default()
+        };
+        assert!(Formatter::new(&format).is_err());
+
+        let format = Format {
+            color_stops: Vec::new(),
+            ..Default::default()
+        };
+        assert!(Formatter::new(&format).is_err());
+
+        let format = Format {
+            power_color_stops: vec![ColorStop {
+                threshold: 10.0,
+                color: String::from("red"),
+            }],
+            ..Default::default()
+        };
+        assert!(Formatter::new(&format).is_err());
+    }
+
+    #[test]
+    fn test_format_time() {
+        assert_eq!(format_time(5373), "1:29:33");
+        assert_eq!(format_time(59), "0:00:59");
+    }
+}
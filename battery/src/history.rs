@@ -0,0 +1,410 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use chrono::TimeZone;
+use log::{error, warn};
+
+use crate::{Batteries, ChargeStatus, Units};
+
+// Constants
+const HEADER: &str =
+    "timestamp,cell,capacity_now,capacity_full,capacity_design,cycle_count,rate,status";
+
+/// Sample of a single cell in the history file
+#[derive(Debug, PartialEq)]
+pub struct Sample {
+    timestamp: i64,           // seconds since the Unix epoch
+    cell: String,             // name of the cell
+    capacity_now: u64,        // present capacity (mWh)
+    capacity_full: u64,       // last 'full' capacity (mWh)
+    capacity_design: u64,     // 'full' design capacity (mWh)
+    cycle_count: Option<u64>, // charge cycles
+    rate: u64,                // present (dis)charge rate (mW)
+    status: String,           // charging status
+}
+
+impl Sample {
+    /// Parses a line of the history file
+    fn parse(line: &str) -> Option<Sample> {
+        let fields: Vec<&str> = line.split(',').collect();
+        if fields.len() != 8 {
+            return None;
+        }
+
+        Some(Sample {
+            timestamp: fields[0].parse().ok()?,
+            cell: String::from(fields[1]),
+            capacity_now: fields[2].parse().ok()?,
+            capacity_full: fields[3].parse().ok()?,
+            capacity_design: fields[4].parse().ok()?,
+            cycle_count: match fields[5] {
+                "" => None,
+                v => Some(v.parse().ok()?),
+            },
+            rate: fields[6].parse().ok()?,
+            status: String::from(fields[7]),
+        })
+    }
+}
+
+impl fmt::Display for Sample {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let cycle_count = self.cycle_count.map(|v| v.to_string());
+        write!(
+            f,
+            "{},{},{},{},{},{},{},{}",
+            self.timestamp,
+            self.cell,
+            self.capacity_now,
+            self.capacity_full,
+            self.capacity_design,
+            cycle_count.as_deref().unwrap_or(""),
+            self.rate,
+            self.status
+        )
+    }
+}
+
+/// Long-term history of the cells stored as CSV
+///
+/// i3blocks runs the block every few seconds, so samples are only appended
+/// once per interval. The modification time of the history file tells when
+/// the last samples were appended, which spares reading the file on every
+/// invocation.
+pub struct History {
+    path: PathBuf, // path to the history file
+    interval: u64, // minimum interval between samples (s)
+}
+
+impl History {
+    /// Creates a history stored in a file
+    ///
+    /// # Arguments
+    ///
+    /// - `path`: Path to the history file
+    /// - `interval`: Minimum interval between samples (s)
+    pub fn new(path: &Path, interval: u64) -> History {
+        History {
+            path: path.to_path_buf(),
+            interval,
+        }
+    }
+
+    /// Appends a sample per cell unless the last samples are too recent
+    ///
+    /// # Arguments
+    ///
+    /// - `batteries`: The present `Batteries`
+    /// - `now`: The present time
+    ///
+    /// # Returns
+    ///
+    /// Whether samples were appended
+    pub fn record(&self, batteries: &Batteries, now: SystemTime) -> bool {
+        let modified = std::fs::metadata(&self.path).and_then(|v| v.modified());
+        if let Ok(modified) = modified {
+            if modified + Duration::from_secs(self.interval) > now {
+                return false;
+            }
+        }
+
+        let timestamp = match now.duration_since(UNIX_EPOCH) {
+            Ok(v) => v.as_secs() as i64,
+            Err(e) => {
+                error!("battery::History::record: {}", e);
+                return false;
+            }
+        };
+
+        let mut file = match std::fs::OpenOptions::new()
+            .append(true)
+            .create(true)
+            .open(&self.path)
+        {
+            Ok(v) => v,
+            Err(e) => {
+                error!("battery::History::record: {}", e);
+                return false;
+            }
+        };
+
+        let mut lines = String::new();
+        if modified.is_err() {
+            lines.push_str(HEADER);
+            lines.push('\n');
+        }
+        // Cells only reporting their charge have no capacities to track, and
+        // cells without a voltage have no energy to compare with other cells
+        for cell in batteries
+            .cells
+            .iter()
+            .filter(|v| !v.percentage_only && v.units == Units::Energy)
+        {
+            let sample = Sample {
+                timestamp,
+                cell: cell.name.clone(),
+                capacity_now: cell.capacity_now,
+                capacity_full: cell.capacity_full,
+                capacity_design: cell.capacity_design,
+                cycle_count: cell.cycle_count,
+                rate: cell.present_rate,
+                status: cell.charge_status.to_string(),
+            };
+            lines.push_str(&sample.to_string());
+            lines.push('\n');
+        }
+
+        // Write all samples at once so that a concurrent invocation can't
+        // interleave lines
+        if let Err(e) = file.write_all(lines.as_bytes()) {
+            error!("battery::History::record: {}", e);
+            return false;
+        }
+
+        true
+    }
+
+    /// Loads all samples of the history file
+    ///
+    /// Malformed lines, e.g. a line truncated by a crash, are skipped.
+    ///
+    /// # Returns
+    ///
+    /// A `Result`:
+    /// - `Ok`: A `Vec` of `Sample`s in the order they were recorded
+    /// - `Err`: A `String` describing the failure to read the file
+    pub fn load(&self) -> Result<Vec<Sample>, String> {
+        let contents = std::fs::read_to_string(&self.path)
+            .map_err(|e| format!("unable to read {}, {}", self.path.display(), e))?;
+
+        let mut samples = Vec::new();
+        for (i, line) in contents.lines().enumerate() {
+            if line == HEADER || line.is_empty() {
+                continue;
+            }
+
+            match Sample::parse(line) {
+                Some(v) => samples.push(v),
+                None => warn!("battery::History::load: skipping malformed line {}", i + 1),
+            }
+        }
+
+        Ok(samples)
+    }
+}
+
+/// Capacity of a cell over a month
+#[derive(Debug, PartialEq)]
+struct MonthSummary {
+    capacity_full: f64,   // average last 'full' capacity (mWh)
+    capacity_design: u64, // 'full' design capacity (mWh)
+}
+
+/// Summary of the history printed by the `history` subcommand
+///
+/// Samples are grouped per cell, by month to show the fade of the cells'
+/// full capacity and by day to show their average discharge rate.
+#[derive(Debug, Default)]
+pub struct Summary {
+    months: BTreeMap<String, BTreeMap<String, MonthSummary>>, // capacity per cell and month
+    days: BTreeMap<String, BTreeMap<String, f64>>, // average discharge rate per cell and day (mW)
+}
+
+impl Summary {
+    /// Summarises samples
+    ///
+    /// # Arguments
+    ///
+    /// - `samples`: Samples of the history file
+    /// - `timezone`: Time zone in which samples are grouped into months and
+    ///   days
+    pub fn new<Tz: TimeZone>(samples: &[Sample], timezone: &Tz) -> Summary
+    where
+        Tz::Offset: fmt::Display,
+    {
+        let mut months: BTreeMap<(String, String), (f64, u64, u64)> = BTreeMap::new();
+        let mut days: BTreeMap<(String, String), (f64, u64)> = BTreeMap::new();
+        let discharging = ChargeStatus::Discharging.to_string();
+
+        for sample in samples {
+            let time = match timezone.timestamp_opt(sample.timestamp, 0).single() {
+                Some(v) => v,
+                None => continue,
+            };
+
+            let month = months
+                .entry((sample.cell.clone(), time.format("%Y-%m").to_string()))
+                .or_insert((0.0, 0, sample.capacity_design));
+            month.0 += sample.capacity_full as f64;
+            month.1 += 1;
+
+            if sample.status == discharging && sample.rate > 0 {
+                let day = days
+                    .entry((sample.cell.clone(), time.format("%Y-%m-%d").to_string()))
+                    .or_insert((0.0, 0));
+                day.0 += sample.rate as f64;
+                day.1 += 1;
+            }
+        }
+
+        let mut summary = Summary::default();
+        for ((cell, month), (sum, count, capacity_design)) in months {
+            summary.months.entry(cell).or_default().insert(
+                month,
+                MonthSummary {
+                    capacity_full: sum / (count as f64),
+                    capacity_design,
+                },
+            );
+        }
+        for ((cell, day), (sum, count)) in days {
+            summary
+                .days
+                .entry(cell)
+                .or_default()
+                .insert(day, sum / (count as f64));
+        }
+
+        summary
+    }
+}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Capacity fade per month")?;
+        for (cell, months) in &self.months {
+            writeln!(f, "  {}", cell)?;
+            let mut previous: Option<f64> = None;
+            for (month, summary) in months {
+                let percent = if summary.capacity_design > 0 {
+                    100.0 * summary.capacity_full / (summary.capacity_design as f64)
+                } else {
+                    0.0
+                };
+                write!(
+                    f,
+                    "    {}  {:.0} mWh  {:.1}%",
+                    month, summary.capacity_full, percent
+                )?;
+                if let Some(previous) = previous {
+                    write!(f, "  {:+.1}%", percent - previous)?;
+                }
+                writeln!(f)?;
+                previous = Some(percent);
+            }
+        }
+
+        writeln!(f, "Average discharge rate per day")?;
+        for (cell, days) in &self.days {
+            writeln!(f, "  {}", cell)?;
+            for (day, rate) in days {
+                writeln!(f, "    {}  {:.1} W", day, rate / 1000.0)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn temp_path(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(name);
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn test_sample_parse() {
+        let line = "1788000000,BAT0,16083,59873,84027,245,29337,charging";
+        let sample = Sample::parse(line);
+        assert!(sample.is_some());
+
+        let sample = sample.unwrap();
+        assert_eq!(sample.cycle_count, Some(245));
+        assert_eq!(sample.to_string(), line);
+
+        let sample = Sample::parse("1788000000,BAT0,16083,59873,84027,,29337,charging");
+        assert_eq!(sample.unwrap().cycle_count, None);
+
+        assert!(Sample::parse("1788000000,BAT0,16083").is_none());
+        assert!(Sample::parse("now,BAT0,16083,59873,84027,245,29337,charging").is_none());
+    }
+
+    #[test]
+    fn test_record() {
+        let path = temp_path("battery-test-history-record.csv");
+        let history = History::new(&path, 600);
+        let batteries = Batteries::new(&PathBuf::from("tests/two-batteries-dis-idl"), false);
+        let batteries = batteries.unwrap();
+
+        let now = SystemTime::now();
+        assert!(history.record(&batteries, now));
+        assert!(!history.record(&batteries, now + Duration::from_secs(5)));
+        assert!(history.record(&batteries, now + Duration::from_secs(3600)));
+
+        let samples = history.load().unwrap();
+        assert_eq!(samples.len(), 4);
+        assert_eq!(samples[0].cell, "BAT0");
+        assert_eq!(samples[1].cell, "BAT1");
+        assert_eq!(samples[2].timestamp - samples[0].timestamp, 3600);
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(contents.lines().next(), Some(HEADER));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_record_charge_units() {
+        let path = temp_path("battery-test-history-charge.csv");
+        let history = History::new(&path, 600);
+
+        // BAT0 reports charge without a voltage, BAT1 energy
+        let path_mixed = PathBuf::from("tests/two-batteries-mixed-no-voltage");
+        let batteries = Batteries::new(&path_mixed, false).unwrap();
+        assert!(history.record(&batteries, SystemTime::now()));
+
+        let samples = history.load().unwrap();
+        assert_eq!(samples.len(), 1);
+        assert_eq!(samples[0].cell, "BAT1");
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_load_malformed() {
+        let history = History::new(&PathBuf::from("tests/history/history.csv"), 600);
+        let samples = history.load().unwrap();
+        assert_eq!(samples.len(), 8);
+
+        let history = History::new(&PathBuf::from("tests/history/missing.csv"), 600);
+        assert!(history.load().is_err());
+    }
+
+    #[test]
+    fn test_summary() {
+        let history = History::new(&PathBuf::from("tests/history/history.csv"), 600);
+        let samples = history.load().unwrap();
+        let summary = Summary::new(&samples, &Utc);
+        assert_eq!(
+            summary.to_string(),
+            "Capacity fade per month\n\
+             \x20 BAT0\n\
+             \x20   2026-07  60000 mWh  75.0%\n\
+             \x20   2026-08  59200 mWh  74.0%  -1.0%\n\
+             \x20 BAT1\n\
+             \x20   2026-08  20000 mWh  100.0%\n\
+             Average discharge rate per day\n\
+             \x20 BAT0\n\
+             \x20   2026-07-01  9.0 W\n\
+             \x20   2026-08-01  12.0 W\n"
+        );
+    }
+}
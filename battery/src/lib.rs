@@ -1,17 +1,130 @@
+use std::collections::BTreeMap;
 use std::fmt;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use log::{error, info, warn};
-use serde::Deserialize;
-use serde_yaml;
+use serde::{Deserialize, Serialize};
 use simplelog::{ConfigBuilder, LevelFilter, WriteLogger};
 
+mod alert;
+mod charge;
+mod device;
+mod error;
+mod estimator;
+mod format;
+mod history;
+mod monitor;
+mod report;
+mod select;
+mod source;
+mod state;
+mod view;
+mod watch;
+
+pub use alert::{fire_alert, AlertLevel, Alerts, DesktopNotifier, Notifier, Thresholds};
+pub use charge::{active_profile, cycle_profile, ChargeProfile};
+pub use device::{Device, DeviceOptions, Devices};
+pub use error::BatteryError;
+pub use estimator::RateEstimator;
+pub use format::{Format, Formatter};
+pub use history::{History, Summary};
+pub use monitor::{run, Event, EventSource, SystemEvents};
+pub use report::Report;
+pub use select::Selection;
+pub use source::PowerSource;
+pub use state::State;
+pub use view::{View, LEFT_BUTTON, RIGHT_BUTTON};
+pub use watch::Watch;
+
+use charge::ChargeControl;
+use device::CapacityLevel;
+use format::{format_time, CellDisplay, Placeholder, Template, Value};
+
 // Constants
-const DEFAULT_ERROR: &str = "BAT Error!\nBAT Error!\n#FF0000";
+const ARGS_ERROR: &str = "BAT Args error!\nBAT Args error!\n#FF0000";
+const POWER_SUPPLY_CLASS: &str = "class/power_supply";
+pub const SYSFS_ROOT: &str = "/sys";
+pub const DEFAULT_ERROR: &str = "BAT Error!\nBAT Error!\n#FF0000";
+pub const USAGE: &str = "usage: battery <config.yaml>\n       \
+                         battery report [--json] [<config.yaml>]\n       \
+                         battery history <config.yaml>\n       \
+                         battery --watch <config.yaml>";
 
 // Traits
 pub trait I3Block {
+    /// Formats the block's present view
     fn format_i3(&self) -> String;
+
+    /// Reacts to a click on the block
+    ///
+    /// # Arguments
+    ///
+    /// - `button`: The i3bar mouse button, e.g. `LEFT_BUTTON`
+    fn click(&mut self, _button: u8) {}
+}
+
+/// Commands of the `battery` binary
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    Block(PathBuf), // display the block with a configuration file
+    // print a battery health report
+    Report {
+        json: bool,              // print the report as JSON
+        config: Option<PathBuf>, // configuration file selecting the batteries
+    },
+    History(PathBuf), // summarise the history of a configuration file
+    Watch(PathBuf),   // print a rolling power table of a configuration file
+}
+
+impl Command {
+    /// Parses command line arguments
+    ///
+    /// # Arguments
+    ///
+    /// - `args`: A slice of command line arguments
+    ///
+    /// # Returns
+    ///
+    /// A `Result`:
+    /// - `Ok`: The `Command` to run
+    /// - `Err`: A `String` with error to be displayed by i3
+    pub fn new(args: &[String]) -> Result<Command, String> {
+        match args.get(1).map(|v| v.as_str()) {
+            Some("report") => {
+                let mut json = false;
+                let mut config = None;
+                for arg in &args[2..] {
+                    match arg.as_str() {
+                        "--json" => json = true,
+                        _ if arg.starts_with('-') || config.is_some() => {
+                            return Err(ARGS_ERROR.into())
+                        }
+                        _ => config = Some(PathBuf::from(arg)),
+                    }
+                }
+                Ok(Command::Report { json, config })
+            }
+            Some("history") => match args.get(2) {
+                Some(path) if args.len() == 3 => Ok(Command::History(PathBuf::from(path))),
+                _ => Err(ARGS_ERROR.into()),
+            },
+            Some("--watch") => match args.get(2) {
+                Some(path) if args.len() == 3 => Ok(Command::Watch(PathBuf::from(path))),
+                _ => Err(ARGS_ERROR.into()),
+            },
+            Some(path) if args.len() == 2 => Ok(Command::Block(PathBuf::from(path))),
+            _ => Err(ARGS_ERROR.into()),
+        }
+    }
+}
+
+/// Kinds of power supplies displayed by the block
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Mode {
+    #[default]
+    Batteries, // system batteries
+    Devices, // peripheral device batteries
 }
 
 /// Container for configuration options
@@ -23,6 +136,78 @@ pub struct Config {
     /// Log battery information
     #[serde(default = "Config::default_bool_false")]
     pub log_batteries: bool,
+
+    /// Root of the sysfs file system
+    #[serde(default = "Config::default_sysfs_root")]
+    pub sysfs_root: PathBuf,
+
+    /// Battery cells to display
+    #[serde(flatten)]
+    pub selection: Selection,
+
+    /// Kind of power supplies to display
+    #[serde(default)]
+    pub mode: Mode,
+
+    /// Keep running and print an update on every change
+    #[serde(default = "Config::default_bool_false")]
+    pub persistent: bool,
+
+    /// Refresh interval of the persistent mode without any change (s)
+    #[serde(default = "Config::default_refresh_interval")]
+    pub refresh_interval: u64,
+
+    /// Device mode options
+    #[serde(default)]
+    pub devices: DeviceOptions,
+
+    /// Path to the file persisting rate history between invocations
+    #[serde(default)]
+    pub state_file_path: Option<PathBuf>,
+
+    /// Path to the CSV file recording the long-term history of the cells
+    #[serde(default)]
+    pub history_file_path: Option<PathBuf>,
+
+    /// Minimum interval between history samples (s)
+    #[serde(default = "Config::default_history_interval")]
+    pub history_interval: u64,
+
+    /// Weight of the newest rate sample in the moving average, in (0, 1]
+    #[serde(default = "Config::default_rate_smoothing")]
+    pub rate_smoothing: f64,
+
+    /// Charge percentage at which a warning alert is raised
+    #[serde(default)]
+    pub warning_threshold: Option<f64>,
+
+    /// Charge percentage at which a critical alert is raised
+    #[serde(default)]
+    pub critical_threshold: Option<f64>,
+
+    /// Shell command run when a critical alert is raised
+    #[serde(default)]
+    pub critical_command: Option<String>,
+
+    /// Send desktop notifications when alerts are raised
+    #[serde(default = "Config::default_bool_true")]
+    pub notifications: bool,
+
+    /// Charge control profiles cycled by clicking the block
+    #[serde(default)]
+    pub charge_profiles: Vec<ChargeProfile>,
+
+    /// Command writing charge control attributes with elevated privileges
+    #[serde(default)]
+    pub charge_helper: Option<String>,
+
+    /// Output format options
+    #[serde(flatten)]
+    format: Format,
+
+    /// Validated output format
+    #[serde(skip)]
+    pub formatter: Formatter,
 }
 
 impl Config {
@@ -35,22 +220,16 @@ impl Config {
     ///
     /// # Arguments
     ///
-    /// - `args`: A slice of command line arguments
+    /// - `path`: Path to the configuration file
     ///
     /// # Returns
     ///
     /// A `Result`:
     /// - `Ok`: A `Config` with parsed configuration options
     /// - `Err`: A `String` with error to be displayed by i3
-    pub fn new(args: &[String]) -> Result<Config, String> {
-        // Check number of arguments
-        if args.len() != 2 {
-            let e = "BAT Args error!\nBAT Args error!\n#FF0000";
-            return Err(e.into());
-        }
-
+    pub fn new(path: &Path) -> Result<Config, String> {
         // Open configuration file
-        let file = match std::fs::File::open(&args[1]) {
+        let file = match std::fs::File::open(path) {
             Ok(v) => v,
             Err(_) => {
                 let e = "BAT File error!\nBAT File error!\n#FF0000";
@@ -59,7 +238,7 @@ impl Config {
         };
 
         // Parse configuration file
-        let config: Config = match serde_yaml::from_reader(file) {
+        let mut config: Config = match serde_yaml::from_reader(file) {
             Ok(v) => v,
             Err(_) => {
                 let e = "BAT Parse error!\nBAT Parse error!\n#FF0000";
@@ -85,373 +264,1006 @@ impl Config {
             .set_time_to_local(true)
             .build();
 
-        if let Err(_) = WriteLogger::init(LevelFilter::Info, logger, file) {
+        if WriteLogger::init(LevelFilter::Info, logger, file).is_err() {
             let e = "BAT Logger error!\nBAT Logger error!\n#FF0000";
             return Err(e.into());
         }
 
+        // Verify output format
+        config.formatter = match Formatter::new(&config.format) {
+            Ok(v) => v,
+            Err(e) => {
+                error!("battery::Config::new: invalid format, {}", e);
+                let e = "BAT Parse error!\nBAT Parse error!\n#FF0000";
+                return Err(e.into());
+            }
+        };
+
+        // Verify rate smoothing
+        if !(config.rate_smoothing > 0.0 && config.rate_smoothing <= 1.0) {
+            error!(
+                "battery::Config::new: invalid rate smoothing {}, select from (0, 1]",
+                config.rate_smoothing
+            );
+            return Err(DEFAULT_ERROR.into());
+        }
+
+        // Verify refresh interval
+        if config.refresh_interval == 0 {
+            error!("battery::Config::new: refresh interval must be positive");
+            return Err(DEFAULT_ERROR.into());
+        }
+
+        // Verify alert thresholds
+        for threshold in [config.warning_threshold, config.critical_threshold]
+            .iter()
+            .flatten()
+        {
+            if !(0.0..=100.0).contains(threshold) {
+                error!(
+                    "battery::Config::new: invalid alert threshold {}, select from [0, 100]",
+                    threshold
+                );
+                return Err(DEFAULT_ERROR.into());
+            }
+        }
+
+        if let (Some(warning), Some(critical)) =
+            (config.warning_threshold, config.critical_threshold)
+        {
+            if critical > warning {
+                error!(
+                    "battery::Config::new: critical threshold {} above warning threshold {}",
+                    critical, warning
+                );
+                return Err(DEFAULT_ERROR.into());
+            }
+        }
+
+        // Verify charge profiles
+        for (i, profile) in config.charge_profiles.iter().enumerate() {
+            if let Err(e) = profile.validate() {
+                error!("battery::Config::new: {}", e);
+                return Err(DEFAULT_ERROR.into());
+            }
+
+            if config.charge_profiles[..i]
+                .iter()
+                .any(|v| v.name == profile.name)
+            {
+                error!(
+                    "battery::Config::new: duplicate charge profile {}",
+                    profile.name
+                );
+                return Err(DEFAULT_ERROR.into());
+            }
+        }
+
+        // Verify device thresholds
+        if let Err(e) = config.devices.validate() {
+            error!("battery::Config::new: {}", e);
+            return Err(DEFAULT_ERROR.into());
+        }
+
+        // Alerts remember the fired level in the state file or in memory
+        if config.state_file_path.is_none() && !config.persistent {
+            if config.critical_command.is_some() {
+                error!(
+                    "battery::Config::new: `critical_command` requires `state_file_path` \
+                      or `persistent`"
+                );
+                return Err(DEFAULT_ERROR.into());
+            }
+
+            if config.notifications
+                && (config.warning_threshold.is_some() || config.critical_threshold.is_some())
+            {
+                warn!(
+                    "battery::Config::new: notifications require `state_file_path` \
+                      or `persistent`, only the urgent flag will be set"
+                );
+            }
+        }
+
         Ok(config)
     }
 
+    /// Returns the alert thresholds
+    pub fn thresholds(&self) -> Thresholds {
+        Thresholds {
+            warning: self.warning_threshold,
+            critical: self.critical_threshold,
+        }
+    }
+
+    /// Returns the path to the `power_supply` class of the sysfs root
+    pub fn power_supply_path(&self) -> PathBuf {
+        self.sysfs_root.join(POWER_SUPPLY_CLASS)
+    }
+
     fn default_bool_false() -> bool {
         false
     }
+
+    fn default_bool_true() -> bool {
+        true
+    }
+
+    fn default_sysfs_root() -> PathBuf {
+        PathBuf::from(SYSFS_ROOT)
+    }
+
+    fn default_rate_smoothing() -> f64 {
+        0.3
+    }
+
+    fn default_history_interval() -> u64 {
+        600
+    }
+
+    fn default_refresh_interval() -> u64 {
+        15
+    }
+}
+
+/// Units of the capacities and rates of a cell
+///
+/// Charge attributes are converted into energy with the cell's voltage, so
+/// only cells without any voltage keep their charge units.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+enum Units {
+    #[default]
+    Energy, // capacities in mWh, rates in mW
+    Charge, // capacities in mAh, rates in mA
+}
+
+impl Units {
+    /// Returns the unit of capacities
+    fn capacity(self) -> &'static str {
+        match self {
+            Units::Energy => "mWh",
+            Units::Charge => "mAh",
+        }
+    }
+
+    /// Returns the unit of rates
+    fn rate(self) -> &'static str {
+        match self {
+            Units::Energy => "mW",
+            Units::Charge => "mA",
+        }
+    }
 }
 
 /// Battery charge states
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Serialize)]
 enum ChargeStatus {
     Charging,
     Discharging,
     Full,
+    Idle,
+    #[default]
     Unknown,
 }
 
-impl Default for ChargeStatus {
-    fn default() -> Self {
-        ChargeStatus::Unknown
-    }
-}
-
 impl fmt::Display for ChargeStatus {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
             ChargeStatus::Charging => write!(f, "charging"),
             ChargeStatus::Discharging => write!(f, "discharging"),
             ChargeStatus::Full => write!(f, "full"),
+            ChargeStatus::Idle => write!(f, "idle"),
             ChargeStatus::Unknown => write!(f, "unknown"),
         }
     }
 }
 
 /// Battery information
+///
+/// Cells expose either `charge_*` attributes (µAh) or `energy_*` attributes
+/// (µWh). Both are normalized into energy units (mWh, mW) so that cells of
+/// either family can be aggregated together, unless a charge cell reports no
+/// voltage, in which case it keeps charge units (mAh, mA).
 #[derive(Default)]
 pub struct Battery {
-    name: String, // name of the cell
+    name: String,  // name of the cell
+    path: PathBuf, // path to the cell's directory
 
     charge_full: Option<u64>,        // last 'full' charge (mAh)
     charge_full_design: Option<u64>, // 'full' design charge (mAh)
     charge_now: Option<u64>,         // present charge (mAh)
     current_now: Option<u64>,        // present current (mA)
+    energy_full: Option<u64>,        // last 'full' energy (mWh)
+    energy_full_design: Option<u64>, // 'full' design energy (mWh)
+    energy_now: Option<u64>,         // present energy (mWh)
+    power_now: Option<u64>,          // present power (mW)
     status: Option<String>,          // charging status
+    voltage_min_design: Option<u64>, // minimum design voltage (mV)
+    voltage_now: Option<u64>,        // present voltage (mV)
+
+    cycle_count: Option<u64>,      // charge cycles
+    manufacturer: Option<String>,  // manufacturer of the cell
+    model_name: Option<String>,    // model of the cell
+    serial_number: Option<String>, // serial number of the cell
+    technology: Option<String>,    // chemistry, e.g. `Li-ion`
+
+    charge_control: ChargeControl, // charge control attributes
 
-    capacity_design: u64,        // 'full' design capacity (mAh)
-    capacity_full: u64,          // last 'full' capacity (mAh)
-    capacity_now: u64,           // present capacity (mAh)
+    capacity: Option<u8>,          // present charge reported by the driver (%)
+    capacity_level: CapacityLevel, // coarse charge level
+    percentage_only: bool,         // charge only known as a percentage or level
+
+    units: Units,                // units of the capacities and rates
+    capacity_design: u64,        // 'full' design capacity (mWh or mAh)
+    capacity_full: u64,          // last 'full' capacity (mWh or mAh)
+    capacity_now: u64,           // present capacity (mWh or mAh)
     charge_status: ChargeStatus, // charging status
-    present_rate: u64,           // present current (mA)
-    time_remaining: u64,         // remaining (dis)charge time (s)
+    present_rate: u64,           // present power (mW or mA)
+    time_remaining: Option<u64>, // remaining (dis)charge time (s)
+
+    power: u64,         // instantaneous power (mW or mA)
+    power_average: u64, // smoothed power (mW or mA)
 }
 
 impl Battery {
-    pub fn new(path: &PathBuf) -> Option<Battery> {
-        let mut battery = Battery::default();
-
+    /// Parses a battery cell
+    ///
+    /// Cells with partially broken readings degrade gracefully, e.g. a cell
+    /// without a (dis)charge rate has no time estimate, and the problems are
+    /// logged as warnings.
+    ///
+    /// # Arguments
+    ///
+    /// - `path`: Path to the cell's directory
+    ///
+    /// # Returns
+    ///
+    /// A `Result`:
+    /// - `Ok`: The parsed `Battery`
+    /// - `Err`: A `BatteryError` describing why the capacities are unknown
+    pub fn new(path: &Path) -> Result<Battery, BatteryError> {
         // Get battery name
-        match path.file_name() {
-            Some(v) => match v.to_str() {
-                Some(name) => battery.name = String::from(name),
-                None => {
-                    error!("battery::Battery::new unable to parse name");
-                    return None;
-                }
-            },
-            None => {
-                error!("battery::Battery::new unable to parse name");
-                return None;
+        let name = match path.file_name().map(|v| v.to_str()) {
+            Some(Some(name)) => String::from(name),
+            _ => {
+                return Err(BatteryError::InvalidValue {
+                    cell: path.display().to_string(),
+                    attribute: "name",
+                    value: path.display().to_string(),
+                })
             }
         };
 
-        // Read battery directory
-        let entries = match path.read_dir() {
-            Ok(v) => v,
-            Err(e) => {
-                error!("battery::Battery::new {}", e);
-                return None;
-            }
+        let mut battery = Battery {
+            name,
+            path: path.to_path_buf(),
+            charge_control: ChargeControl::new(path),
+            ..Default::default()
         };
 
-        // Parse files
-        for entry in entries {
-            if let Ok(entry) = entry {
-                if let Some(entry_name) = entry.file_name().to_str() {
-                    let entry_path = entry.path();
-                    match entry_name {
-                        "charge_full" => {
-                            battery.charge_full = parse_file::<u64>(&entry_path).map(|v| v / 1000)
-                        }
-                        "charge_full_design" => {
-                            battery.charge_full_design =
-                                parse_file::<u64>(&entry_path).map(|v| v / 1000)
-                        }
-                        "charge_now" => {
-                            battery.charge_now = parse_file::<u64>(&entry_path).map(|v| v / 1000)
-                        }
-                        "current_now" => {
-                            battery.current_now = parse_file::<u64>(&entry_path).map(|v| v / 1000)
-                        }
-                        "status" => battery.status = parse_file(&entry_path),
-                        _ => (),
-                    }
-                }
+        // Read attributes
+        let mut attributes = Attributes::read(&battery.name, path)?;
+        battery.charge_full = attributes.micro("charge_full");
+        battery.charge_full_design = attributes.micro("charge_full_design");
+        battery.charge_now = attributes.micro("charge_now");
+        battery.current_now = attributes.micro("current_now");
+        battery.cycle_count = attributes.get("cycle_count");
+        battery.energy_full = attributes.micro("energy_full");
+        battery.energy_full_design = attributes.micro("energy_full_design");
+        battery.energy_now = attributes.micro("energy_now");
+        battery.manufacturer = attributes.get("manufacturer");
+        battery.model_name = attributes.get("model_name");
+        battery.power_now = attributes.micro("power_now");
+        battery.serial_number = attributes.get("serial_number");
+        battery.status = attributes.get("status");
+        battery.technology = attributes.get("technology");
+        battery.voltage_min_design = attributes.micro("voltage_min_design");
+        battery.voltage_now = attributes.micro("voltage_now");
+
+        // Determine voltage used to convert charge into energy
+        let voltage = battery.voltage_min_design.or(battery.voltage_now);
+
+        // Determine battery's capacities
+        //
+        // Minimal drivers, e.g. of virtual machines, report neither family
+        // of attributes but only the charge as a percentage or a level.
+        let energy = battery.energy_full.is_some() || battery.energy_now.is_some();
+        let charge = battery.charge_full.is_some() || battery.charge_now.is_some();
+        if !energy && !charge {
+            battery.capacity = attributes.get::<u8>("capacity").map(|v| v.min(100));
+            battery.capacity_level = attributes
+                .get::<String>("capacity_level")
+                .map(|v| CapacityLevel::parse(&v))
+                .unwrap_or(CapacityLevel::Unknown);
+            if battery.capacity.is_none() && battery.capacity_level == CapacityLevel::Unknown {
+                return Err(attributes.missing("capacity"));
             }
-        }
-
-        // Determine battery's current capacity
-        if battery.charge_full.is_some() {
-            battery.capacity_full = battery.charge_full.unwrap();
-        } else {
-            error!("battery::Battery::new: unable to compute `capacity_full`");
-            return None;
-        }
-
-        // Determine battery's design capacity
-        if battery.charge_full_design.is_some() {
-            battery.capacity_design = battery.charge_full_design.unwrap();
-        } else {
-            error!(
-                "battery::Battery::new:\
-                    unable to compute `capacity_design`"
-            );
-            return None;
-        }
-
-        // Determine battery's present capacity
-        if battery.charge_now.is_some() {
-            battery.capacity_now = battery.charge_now.unwrap();
+            battery.percentage_only = true;
         } else {
-            error!("battery::Battery::new: unable to compute `capacity_now`");
-            return None;
+            // Cells report either family of attributes, so the family of any
+            // present attribute decides which ones are required.
+            let (full, full_design, now, scale) = if energy {
+                (
+                    ("energy_full", battery.energy_full),
+                    ("energy_full_design", battery.energy_full_design),
+                    ("energy_now", battery.energy_now),
+                    Some(1000),
+                )
+            } else {
+                (
+                    ("charge_full", battery.charge_full),
+                    ("charge_full_design", battery.charge_full_design),
+                    ("charge_now", battery.charge_now),
+                    voltage,
+                )
+            };
+            let full_value = full.1.ok_or_else(|| attributes.missing(full.0))?;
+            let now_value = now.1.ok_or_else(|| attributes.missing(now.0))?;
+            let scale = match scale {
+                Some(v) => v,
+                None => {
+                    // Without a voltage, the charges can't be converted
+                    battery.units = Units::Charge;
+                    1000
+                }
+            };
+            let full_design_value = match full_design.1 {
+                Some(v) => v,
+                None => {
+                    let problem = attributes.missing(full_design.0);
+                    attributes.warn(problem);
+                    full_value
+                }
+            };
+            battery.capacity_full = charge_to_energy(full_value, scale);
+            battery.capacity_design = charge_to_energy(full_design_value, scale);
+            battery.capacity_now = charge_to_energy(now_value, scale);
         }
 
         // Determine battery's (dis)charging rate
-        if battery.current_now.is_some() {
-            battery.present_rate = battery.current_now.unwrap();
-        } else {
-            error!("battery::Battery::new unable to compute `current_now`");
-            return None;
+        if let Some(power_now) = battery.power_now {
+            battery.present_rate = power_now;
+        } else if let (Some(current_now), Some(voltage)) = (battery.current_now, voltage) {
+            battery.present_rate = charge_to_energy(current_now, voltage);
+        } else if let (Some(current_now), Units::Charge) = (battery.current_now, battery.units) {
+            battery.present_rate = current_now;
+        } else if !battery.percentage_only {
+            let problem = attributes.missing("power_now");
+            attributes.warn(problem);
         }
 
+        // Determine battery's instantaneous power
+        //
+        // The rate above converts current at the same voltage as the
+        // capacities so that times are consistent, whereas the power drawn
+        // at this instant is the current at the present voltage.
+        battery.power = match (battery.power_now, battery.current_now) {
+            (Some(power_now), _) => power_now,
+            (None, Some(current_now)) => match battery.voltage_now.or(voltage) {
+                Some(voltage) => charge_to_energy(current_now, voltage),
+                None => battery.present_rate,
+            },
+            (None, None) => battery.present_rate,
+        };
+        battery.power_average = battery.power;
+
         // Determine battery's state
-        if let Some(status) = battery.status.clone() {
-            match status.as_str() {
-                "Charging" => {
-                    battery.charge_status = ChargeStatus::Charging;
-                    battery.time_remaining = (3600
-                        * (battery.capacity_full - battery.capacity_now))
-                        / battery.present_rate;
-                }
-                "Discharging" => {
-                    battery.charge_status = ChargeStatus::Discharging;
-                    battery.time_remaining = (3600 * battery.capacity_now) / battery.present_rate;
-                }
-                "Full" => battery.charge_status = ChargeStatus::Full,
-                _ => {}
+        if let Some(status) = battery.status.as_deref() {
+            battery.charge_status = match status {
+                "Charging" => ChargeStatus::Charging,
+                "Discharging" => ChargeStatus::Discharging,
+                "Full" => ChargeStatus::Full,
+                "Not charging" => ChargeStatus::Idle,
+                _ => ChargeStatus::Unknown,
             };
         }
 
-        // Warn and upper bound on invalid battery reading
+        // Upper bound the present capacity on inconsistent readings
         if battery.capacity_now > battery.capacity_full {
+            attributes.warn(BatteryError::InconsistentReading {
+                cell: battery.name.clone(),
+                reason: format!(
+                    "{} {unit} (`capacity_now`) > {} {unit} (`capacity_full`), setting limit",
+                    battery.capacity_now,
+                    battery.capacity_full,
+                    unit = battery.units.capacity()
+                ),
+            });
             battery.capacity_now = battery.capacity_full;
-            warn!(
-                "battery::Battery::new invalid reading for cell {}, \
-                  {} (`capacity_now`) > {} (`capacity_full`), setting limit",
-                battery.name, battery.capacity_now, battery.capacity_full
-            );
         }
 
-        Some(battery)
+        // Determine battery's (dis)charging time
+        battery.estimate_time();
+
+        attributes.log();
+        Ok(battery)
+    }
+
+    /// Estimates the remaining (dis)charge time from the present rate
+    ///
+    /// There is no estimate if the cell is neither charging nor discharging,
+    /// if its rate is zero or if its capacities are unknown.
+    fn estimate_time(&mut self) {
+        if self.percentage_only {
+            self.time_remaining = None;
+            return;
+        }
+
+        self.time_remaining = match self.charge_status {
+            ChargeStatus::Charging => self
+                .capacity_full
+                .saturating_sub(self.capacity_now)
+                .saturating_mul(3600)
+                .checked_div(self.present_rate),
+            ChargeStatus::Discharging => self
+                .capacity_now
+                .saturating_mul(3600)
+                .checked_div(self.present_rate),
+            _ => None,
+        };
+    }
+
+    /// Returns the present charge of the cell (%)
+    ///
+    /// Cells reporting only a capacity level are given a charge
+    /// representative of their level.
+    fn charge_percent(&self) -> Option<f64> {
+        if self.percentage_only {
+            return self
+                .capacity
+                .map(f64::from)
+                .or_else(|| self.capacity_level.charge());
+        }
+
+        percent(self.capacity_now, self.capacity_full)
+    }
+
+    /// Returns whether the cell reports its (dis)charge rate
+    fn has_rate(&self) -> bool {
+        self.power_now.is_some() || self.current_now.is_some()
+    }
+
+    /// Returns the signed power flowing into the cell (mW or mA)
+    ///
+    /// The flow is positive while charging, negative while discharging and
+    /// zero otherwise.
+    fn power_flow(&self) -> i64 {
+        self.signed(self.present_rate)
+    }
+
+    /// Returns a power signed by the direction it flows in (mW or mA)
+    ///
+    /// # Arguments
+    ///
+    /// - `power`: Power flowing in or out of the cell (mW or mA)
+    fn signed(&self, power: u64) -> i64 {
+        match self.charge_status {
+            ChargeStatus::Charging => power as i64,
+            ChargeStatus::Discharging => -(power as i64),
+            _ => 0,
+        }
     }
 }
 
 #[derive(Default)]
 pub struct Batteries {
-    cells: Vec<Battery>, // available individual battery cells
-
+    cells: Vec<Battery>,       // available individual battery cells
+    errors: Vec<BatteryError>, // errors of the unreadable cells
+    sources: Vec<PowerSource>, // available external power sources
+
+    units: Units,                // units of the capacities and rates
+    capacity_design: u64,        // 'full' design capacity (mWh or mAh)
+    capacity_full: u64,          // last 'full' capacity (mWh or mAh)
+    capacity_now: u64,           // present capacity (mWh or mAh)
     capacity_percent: f64,       // current capacity (%)
     charge_percent: f64,         // current charge (%)
+    percentage_only: bool,       // any cell's charge only known as a percentage
     charge_status: ChargeStatus, // overall charging status
-    time_remaining: u64,         // remaining (dis)charge times (s)
+    power_flow: i64,             // net power flowing into the cells (mW or mA)
+    time_remaining: Option<u64>, // remaining (dis)charge time (s)
+
+    power: i64,         // net instantaneous power flowing into the cells (mW or mA)
+    power_average: i64, // net smoothed power flowing into the cells (mW or mA)
+
+    profile: Option<String>, // name of the active charge profile
+    view: View,              // displayed view
+    formatter: Formatter,    // output format
 }
 
 impl Batteries {
-    pub fn new(path: &PathBuf, log: bool) -> Result<Batteries, String> {
+    pub fn new(path: &Path, log: bool) -> Result<Batteries, BatteryError> {
+        Batteries::with_selection(path, &Selection::default(), log)
+    }
+
+    /// Parses the selected battery cells and all external power sources
+    ///
+    /// # Arguments
+    ///
+    /// - `path`: Path to the `power_supply` directory
+    /// - `selection`: Selection of the battery cells by name
+    /// - `log`: Log battery information
+    ///
+    /// # Returns
+    ///
+    /// A `Result`:
+    /// - `Ok`: `Batteries` with at least one selected cell
+    /// - `Err`: A `BatteryError`, the error of the first unreadable cell if
+    ///   no cell could be read
+    pub fn with_selection(
+        path: &Path,
+        selection: &Selection,
+        log: bool,
+    ) -> Result<Batteries, BatteryError> {
         let mut batteries = Batteries::default();
 
         // Read `power_supply` directory
         let entries = match path.read_dir() {
             Ok(v) => v,
-            Err(e) => {
-                error!("battery::Batteries::new: {}", e);
-                return Err(DEFAULT_ERROR.into());
+            Err(error) => {
+                let error = BatteryError::Io {
+                    path: path.to_path_buf(),
+                    error,
+                };
+                error!("battery::Batteries::new: {}", error);
+                return Err(error);
             }
         };
 
-        // Gather all battery cells
-        let mut charge_mask: u8 = 0;
-        for entry in entries {
-            if let Ok(entry) = entry {
-                if let Some(entry_name) = entry.file_name().to_str() {
-                    if !entry_name.starts_with("BAT") {
-                        continue;
+        // Gather all battery cells and external power sources
+        for entry in entries.flatten() {
+            if let Some(entry_name) = entry.file_name().to_str() {
+                // Skip peripheral devices, e.g. wireless mice
+                if device::is_device(&entry.path()) {
+                    continue;
+                }
+
+                if !entry_name.starts_with("BAT") {
+                    if let Some(source) = PowerSource::new(&entry.path()) {
+                        batteries.sources.push(source);
                     }
+                    continue;
+                }
 
-                    // Parse a battery
-                    if let Some(battery) = Battery::new(&entry.path()) {
-                        match battery.charge_status {
-                            ChargeStatus::Charging => charge_mask |= 1,
-                            ChargeStatus::Discharging => charge_mask |= 2,
-                            ChargeStatus::Full => charge_mask |= 4,
-                            _ => charge_mask |= 8,
-                        }
-                        batteries.cells.push(battery);
-                    } else {
-                        error!(
-                            "battery::Batteries::new:\
-                                unable to parse battery {}",
-                            entry_name
-                        );
+                // Skip cells that aren't selected
+                if !selection.matches(entry_name) {
+                    continue;
+                }
+
+                // Parse a battery
+                match Battery::new(&entry.path()) {
+                    Ok(battery) => batteries.cells.push(battery),
+                    Err(e) => {
+                        error!("battery::Batteries::new: skipping cell, {}", e);
+                        batteries.errors.push(e);
                     }
                 }
             }
         }
 
+        // Keep a stable order between invocations
+        batteries.cells.sort_by(|a, b| a.name.cmp(&b.name));
+        batteries.sources.sort_by(|a, b| a.name.cmp(&b.name));
+
         // Check if any cells
-        if batteries.cells.len() == 0 {
+        if batteries.cells.is_empty() {
             error!("battery::Batteries::new: no battery cells");
-            return Err(DEFAULT_ERROR.into());
-        }
-
-        // Determine overall charge status
-        if charge_mask == 4 {
-            // Batteries are all full
-            batteries.charge_status = ChargeStatus::Full;
-        } else if charge_mask == 1 || charge_mask == 5 {
-            // Batteries are all charging or full
-            batteries.charge_status = ChargeStatus::Charging;
-        } else if charge_mask == 2 {
-            // Batteries are all discharging
-            batteries.charge_status = ChargeStatus::Discharging;
+            return Err(batteries
+                .errors
+                .into_iter()
+                .next()
+                .unwrap_or(BatteryError::NoCells));
         }
 
         // Compute overall information
-        let mut capacity_design: u64 = 0;
-        let mut capacity_full: u64 = 0;
-        let mut capacity_now: u64 = 0;
-        for cell in &batteries.cells {
-            capacity_design += cell.capacity_design;
-            capacity_full += cell.capacity_full;
-            capacity_now += cell.capacity_now;
-
-            if batteries.charge_status == ChargeStatus::Charging
-                || batteries.charge_status == ChargeStatus::Discharging
-            {
-                batteries.time_remaining += cell.time_remaining;
-            }
+        batteries.aggregate();
+        for cell in batteries
+            .cells
+            .iter()
+            .filter(|v| v.units != batteries.units)
+        {
+            warn!(
+                "battery::Batteries::new: cell {} reports no voltage to convert its charge \
+                into energy, leaving it out of the totals",
+                cell.name
+            );
+        }
 
-            if log {
+        if log {
+            for cell in &batteries.cells {
                 info!(
                     "battery::Batteries::new: cell {}\n\
-                      \t* capacity design - {} mAh\n\
-                      \t* capacity full - {} mAh\n\
-                      \t* capacity now - {} mAh\n\
+                      \t* capacity design - {} {capacity}\n\
+                      \t* capacity full - {} {capacity}\n\
+                      \t* capacity now - {} {capacity}\n\
+                      \t* power flow - {} {rate}\n\
                       \t* charge status - {}",
                     cell.name,
                     cell.capacity_design,
                     cell.capacity_full,
                     cell.capacity_now,
-                    cell.charge_status.to_string()
+                    cell.power_flow(),
+                    cell.charge_status,
+                    capacity = cell.units.capacity(),
+                    rate = cell.units.rate()
+                );
+            }
+
+            for source in &batteries.sources {
+                info!(
+                    "battery::Batteries::new: power source {}, {}",
+                    source.name, source
                 );
             }
-        }
 
-        if log {
             info!(
                 "battery::Batteries::new: all cells\n\
-                  \t* capacity design - {} mAh\n\
-                  \t* capacity full - {} mAh\n\
-                  \t* capacity now - {} mAh\n\
+                  \t* capacity design - {} {capacity}\n\
+                  \t* capacity full - {} {capacity}\n\
+                  \t* capacity now - {} {capacity}\n\
+                  \t* power flow - {} {rate}\n\
                   \t* charge status - {}",
-                capacity_design,
-                capacity_full,
-                capacity_now,
-                batteries.charge_status.to_string()
+                batteries.capacity_design,
+                batteries.capacity_full,
+                batteries.capacity_now,
+                batteries.power_flow,
+                batteries.charge_status,
+                capacity = batteries.units.capacity(),
+                rate = batteries.units.rate()
             );
         }
 
-        // Compute percentages
-        batteries.capacity_percent = 100.0 * (capacity_full as f64) / (capacity_design as f64);
-        batteries.charge_percent = 100.0 * (capacity_now as f64) / (capacity_full as f64);
-
         Ok(batteries)
     }
-}
 
-impl I3Block for Batteries {
-    fn format_i3(&self) -> String {
-        let status = match self.charge_status {
-            ChargeStatus::Charging => String::from("CHR"),
-            ChargeStatus::Discharging => String::from("DIS"),
-            ChargeStatus::Full => String::from("FUL"),
-            ChargeStatus::Unknown => String::from("UKN"),
-        };
+    /// Replaces the instantaneous rates of the cells with smoothed rates
+    ///
+    /// Each cell's present rate is fed to the `estimator` and the overall
+    /// information is recomputed from the smoothed rates it returns.
+    ///
+    /// # Arguments
+    ///
+    /// - `estimator`: A `RateEstimator` with the rate history of the cells
+    /// - `alpha`: Weight of the newest rate sample in the moving average
+    pub fn smooth(&mut self, estimator: &mut RateEstimator, alpha: f64) {
+        for cell in &mut self.cells {
+            cell.present_rate =
+                estimator.update(&cell.name, cell.charge_status, cell.present_rate, alpha);
+            cell.estimate_time();
+        }
+
+        self.aggregate();
+    }
+
+    /// Smooths the instantaneous power of the cells
+    ///
+    /// Unlike `smooth`, the instantaneous power is kept alongside the
+    /// smoothed power so that both can be displayed.
+    ///
+    /// # Arguments
+    ///
+    /// - `estimator`: A `RateEstimator` with the power history of the cells
+    /// - `alpha`: Weight of the newest power sample in the moving average
+    pub fn smooth_power(&mut self, estimator: &mut RateEstimator, alpha: f64) {
+        for cell in &mut self.cells {
+            cell.power_average =
+                estimator.update(&cell.name, cell.charge_status, cell.power, alpha);
+        }
+
+        self.aggregate();
+    }
+
+    /// Sets the output format
+    ///
+    /// # Arguments
+    ///
+    /// - `formatter`: A validated `Formatter`
+    pub fn set_formatter(&mut self, formatter: Formatter) {
+        self.formatter = formatter;
+    }
+
+    /// Sets the name of the active charge profile
+    ///
+    /// # Arguments
+    ///
+    /// - `profile`: Name of the active `ChargeProfile`, if any
+    pub fn set_profile(&mut self, profile: Option<String>) {
+        self.profile = profile;
+    }
+
+    /// Renders a template, once per cell if cells are displayed separately
+    fn render(&self, template: &Template, separate: bool) -> String {
+        if !separate {
+            return template.render(|p| self.resolve(p, None));
+        }
+
+        let texts: Vec<String> = self
+            .cells
+            .iter()
+            .map(|cell| template.render(|p| self.resolve(p, Some(&cell.name))))
+            .collect();
+        texts.join(&self.formatter.cell_separator)
+    }
+
+    /// Sets the displayed view
+    ///
+    /// # Arguments
+    ///
+    /// - `view`: The `View` to display
+    pub fn set_view(&mut self, view: View) {
+        self.view = view;
+    }
+
+    /// Returns the displayed view
+    pub fn view(&self) -> View {
+        self.view
+    }
+
+    /// Returns the text of a template placeholder
+    ///
+    /// The text is empty if the value is unavailable, e.g. the time while
+    /// neither (dis)charging or a cell that isn't present.
+    ///
+    /// # Arguments
+    ///
+    /// - `placeholder`: The placeholder to substitute
+    /// - `cell`: Cell of placeholders without one, all cells if `None`
+    fn resolve(&self, placeholder: &Placeholder, cell: Option<&str>) -> String {
+        let cell = placeholder.cell.as_deref().or(cell);
+        match placeholder.value {
+            Value::Ac | Value::Adapter => return self.resolve_source(placeholder),
+            Value::Profile => return self.profile.clone().unwrap_or_default(),
+            Value::Name => return cell.map(String::from).unwrap_or_default(),
+            _ => (),
+        }
 
-        let color = match self.charge_status {
-            ChargeStatus::Charging => "#00FF00",
-            ChargeStatus::Discharging => {
-                let charge = self.charge_percent;
-                if charge >= 90.0 {
-                    "#00FF00"
-                } else if charge >= 80.0 {
-                    "#37FF00"
-                } else if charge >= 70.0 {
-                    "#75FF00"
-                } else if charge >= 60.0 {
-                    "#AAFF00"
-                } else if charge >= 50.0 {
-                    "#E1FF00"
-                } else if charge >= 40.0 {
-                    "#FFE100"
-                } else if charge >= 30.0 {
-                    "#FFAA00"
-                } else if charge >= 20.0 {
-                    "#FF7300"
-                } else if charge >= 10.0 {
-                    "#FF3700"
+        // Capacities are unknown for cells only reporting their charge
+        let (units, capacities, charge, status, power, power_average, time) = match cell {
+            Some(name) => match self.cells.iter().find(|cell| cell.name == name) {
+                Some(cell) => (
+                    cell.units,
+                    Some((cell.capacity_design, cell.capacity_full))
+                        .filter(|_| !cell.percentage_only),
+                    cell.charge_percent(),
+                    cell.charge_status,
+                    cell.signed(cell.power),
+                    cell.signed(cell.power_average),
+                    cell.time_remaining,
+                ),
+                None => return String::new(),
+            },
+            None => (
+                self.units,
+                Some((self.capacity_design, self.capacity_full)).filter(|_| !self.percentage_only),
+                if self.percentage_only {
+                    Some(self.charge_percent)
                 } else {
-                    "#FF000"
+                    percent(self.capacity_now, self.capacity_full)
+                },
+                self.charge_status,
+                self.power,
+                self.power_average,
+                self.time_remaining,
+            ),
+        };
+
+        // Power is unknown for cells without a voltage, whose rates are currents
+        let watts = |power: i64| match units {
+            Units::Energy => format!("{:.1}", (power.unsigned_abs() as f64) / 1000.0),
+            Units::Charge => String::new(),
+        };
+
+        match placeholder.value {
+            Value::Capacity => capacities
+                .and_then(|(design, full)| percent(full, design))
+                .map(|v| format!("{:.0}", v))
+                .unwrap_or_default(),
+            Value::Charge => charge.map(|v| format!("{:.0}", v)).unwrap_or_default(),
+            Value::Status => String::from(self.formatter.label(status)),
+            Value::Time => time.map(format_time).unwrap_or_default(),
+            Value::Watts => watts(power),
+            Value::WattsAverage => watts(power_average),
+            Value::Wear => capacities
+                .and_then(|(design, full)| percent(design.saturating_sub(full), design))
+                .map(|v| format!("{:.0}", v))
+                .unwrap_or_default(),
+            Value::Eta => match time {
+                Some(time) => {
+                    let eta = chrono::Local::now() + chrono::Duration::seconds(time as i64);
+                    eta.format("%H:%M").to_string()
                 }
-            }
-            _ => "#FFFFFF",
+                None => String::new(),
+            },
+            _ => unreachable!("{:?} is resolved without a cell", placeholder.value),
+        }
+    }
+
+    /// Returns the text of a power source placeholder
+    ///
+    /// The text is empty if no matching source is online.
+    fn resolve_source(&self, placeholder: &Placeholder) -> String {
+        let online: Vec<&PowerSource> = self
+            .sources
+            .iter()
+            .filter(|source| source.online())
+            .filter(|source| match &placeholder.cell {
+                Some(name) => &source.name == name,
+                None => true,
+            })
+            .collect();
+
+        if online.is_empty() {
+            return String::new();
+        }
+
+        match placeholder.value {
+            Value::Ac => self.formatter.ac_label().into(),
+            _ => match online
+                .iter()
+                .filter_map(|source| source.watts())
+                .sum::<f64>()
+            {
+                watts if watts > 0.0 => format!("{:.0}", watts),
+                _ => String::new(),
+            },
+        }
+    }
+
+    /// Computes the overall information from the individual cells
+    fn aggregate(&mut self) {
+        // Charge cells without a voltage can only be summed among themselves,
+        // so they're left out next to cells in energy units
+        let rated: Vec<&Battery> = self
+            .cells
+            .iter()
+            .filter(|cell| !cell.percentage_only)
+            .collect();
+        self.units = if !rated.is_empty() && rated.iter().all(|v| v.units == Units::Charge) {
+            Units::Charge
+        } else {
+            Units::Energy
         };
 
-        let time = match self.charge_status {
-            ChargeStatus::Charging | ChargeStatus::Discharging => {
-                let mut time = self.time_remaining;
-                let hours = time / 3600;
+        self.capacity_design = 0;
+        self.capacity_full = 0;
+        self.capacity_now = 0;
+        self.power_flow = 0;
+        self.power = 0;
+        self.power_average = 0;
+        let units = self.units;
+        for cell in self.cells.iter().filter(|cell| cell.units == units) {
+            self.power += cell.signed(cell.power);
+            self.power_average += cell.signed(cell.power_average);
+            self.capacity_design += cell.capacity_design;
+            self.capacity_full += cell.capacity_full;
+            self.capacity_now += cell.capacity_now;
+            self.power_flow += cell.power_flow();
+        }
 
-                time = time - (hours * 3600);
-                let minutes = time / 60;
+        // Determine overall charge status
+        let external_power = self.sources.iter().any(|source| source.online());
+        self.charge_status = aggregate_status(&self.cells, self.power_flow, external_power);
+
+        // Determine overall (dis)charge time
+        //
+        // Cells either share the load or drain one after another, but in
+        // both cases the net power flow has to move the combined energy, so
+        // the estimate is the total energy left over the net rate.
+        let rate = self.power_flow.unsigned_abs();
+        self.time_remaining = match self.charge_status {
+            ChargeStatus::Charging => self
+                .capacity_full
+                .saturating_sub(self.capacity_now)
+                .saturating_mul(3600)
+                .checked_div(rate),
+            ChargeStatus::Discharging => self.capacity_now.saturating_mul(3600).checked_div(rate),
+            _ => None,
+        };
 
-                time = time - (minutes * 60);
-                let seconds = time;
+        // Compute percentages
+        //
+        // Capacities of cells only reporting their charge are unknown, so
+        // their charges can only be averaged without weighing by capacity.
+        self.capacity_percent = percent(self.capacity_full, self.capacity_design).unwrap_or(0.0);
+        self.charge_percent = percent(self.capacity_now, self.capacity_full).unwrap_or(0.0);
+        self.percentage_only = self.cells.iter().any(|cell| cell.percentage_only);
+        if self.percentage_only {
+            let charges: Vec<f64> = self
+                .cells
+                .iter()
+                .filter_map(|v| v.charge_percent())
+                .collect();
+            self.charge_percent = charges.iter().sum::<f64>() / (charges.len().max(1) as f64);
+            self.time_remaining = None;
+        }
+    }
+}
 
-                format!(" {}:{:02}:{:02}", hours, minutes, seconds)
+impl I3Block for Batteries {
+    fn format_i3(&self) -> String {
+        let (full_text, short_text) = match self.view.templates() {
+            Some((full_text, short_text)) => {
+                let separate = self.view == View::Cells;
+                (
+                    self.render(&full_text, separate),
+                    self.render(&short_text, separate),
+                )
+            }
+            None => {
+                let separate = self.formatter.cell_display == CellDisplay::Separate;
+                (
+                    self.render(&self.formatter.full_text, separate),
+                    self.render(&self.formatter.short_text, separate),
+                )
             }
-            _ => String::from(""),
         };
-
-        let full_text = format!(
-            "BAT {:.0}% ({:.0}%) {}{}",
-            self.charge_percent, self.capacity_percent, status, time
-        );
-        let short_text = format!("BAT {:.0}%{}", self.charge_percent, time);
+        let watts = (self.power_average.unsigned_abs() as f64) / 1000.0;
+        let power_color = match self.units {
+            Units::Energy => self.formatter.power_color(self.charge_status, watts),
+            Units::Charge => None,
+        };
+        let color = match power_color {
+            Some(v) => v,
+            None => self
+                .formatter
+                .color(self.charge_status, self.charge_percent),
+        };
 
         format!("{}\n{}\n{}", full_text, short_text, color)
     }
+
+    /// Cycles the views on left click
+    fn click(&mut self, button: u8) {
+        if button == LEFT_BUTTON {
+            self.view = self.view.next();
+        }
+    }
+}
+
+/// Derives the overall charge status of a set of cells
+///
+/// Any (dis)charging cell makes the net power flow decide the status, with a
+/// perfectly balanced flow being reported as idle unless a (dis)charging cell
+/// doesn't report its rate at all, in which case only its status is known.
+/// Otherwise the cells are full if all of them are, unknown if any of them is
+/// without external power and idle if they are holding their charge without
+/// being full, which is the case of cells in an unknown state while plugged
+/// in.
+fn aggregate_status(cells: &[Battery], power_flow: i64, external_power: bool) -> ChargeStatus {
+    let active = cells.iter().any(|cell| {
+        cell.charge_status == ChargeStatus::Charging
+            || cell.charge_status == ChargeStatus::Discharging
+    });
+
+    if active {
+        let unrated = |status| {
+            cells
+                .iter()
+                .any(|cell| !cell.has_rate() && cell.charge_status == status)
+        };
+        match power_flow {
+            v if v > 0 => ChargeStatus::Charging,
+            v if v < 0 => ChargeStatus::Discharging,
+            _ if unrated(ChargeStatus::Discharging) => ChargeStatus::Discharging,
+            _ if unrated(ChargeStatus::Charging) => ChargeStatus::Charging,
+            _ => ChargeStatus::Idle,
+        }
+    } else if cells
+        .iter()
+        .all(|cell| cell.charge_status == ChargeStatus::Full)
+    {
+        ChargeStatus::Full
+    } else if !external_power
+        && cells
+            .iter()
+            .any(|cell| cell.charge_status == ChargeStatus::Unknown)
+    {
+        ChargeStatus::Unknown
+    } else {
+        ChargeStatus::Idle
+    }
 }
 
-fn parse_file<T>(path: &PathBuf) -> Option<T>
+fn parse_file<T>(path: &Path) -> Option<T>
 where
     T: std::str::FromStr + std::fmt::Debug,
 {
@@ -460,9 +1272,109 @@ where
         Err(_) => return None,
     };
 
-    match contents.trim().parse::<T>() {
-        Ok(v) => Some(v),
-        Err(_) => None,
+    contents.trim().parse::<T>().ok()
+}
+
+/// Returns a part relative to a whole (%), if the whole isn't zero
+fn percent(part: u64, whole: u64) -> Option<f64> {
+    match whole {
+        0 => None,
+        _ => Some(100.0 * (part as f64) / (whole as f64)),
+    }
+}
+
+/// Converts a charge (mAh) or current (mA) into energy (mWh) or power (mW)
+fn charge_to_energy(charge: u64, voltage: u64) -> u64 {
+    charge.saturating_mul(voltage) / 1000
+}
+
+/// Attributes of a battery cell read from its sysfs directory
+///
+/// Problems with individual attributes are kept so that a missing attribute
+/// can be reported with the reason it is missing, e.g. garbage contents.
+struct Attributes {
+    cell: String,                                // name of the cell
+    values: BTreeMap<String, String>,            // trimmed contents per attribute
+    problems: Vec<(&'static str, BatteryError)>, // problems per attribute
+    warnings: Vec<BatteryError>,                 // problems the cell degrades with
+}
+
+impl Attributes {
+    /// Reads the attribute files of a cell's directory
+    fn read(cell: &str, path: &Path) -> Result<Attributes, BatteryError> {
+        let entries = path.read_dir().map_err(|error| BatteryError::Io {
+            path: path.to_path_buf(),
+            error,
+        })?;
+
+        let mut values = BTreeMap::new();
+        for entry in entries.flatten() {
+            if let (Some(name), Ok(kind)) = (entry.file_name().to_str(), entry.file_type()) {
+                if kind.is_dir() {
+                    continue;
+                }
+
+                // Some attributes can't be read depending on the state of
+                // the cell, e.g. `ENODATA`, which only matters if they are
+                // required
+                if let Ok(v) = std::fs::read_to_string(entry.path()) {
+                    values.insert(String::from(name), String::from(v.trim()));
+                }
+            }
+        }
+
+        Ok(Attributes {
+            cell: String::from(cell),
+            values,
+            problems: Vec::new(),
+            warnings: Vec::new(),
+        })
+    }
+
+    /// Returns the parsed value of an attribute, if present and valid
+    fn get<T: std::str::FromStr>(&mut self, attribute: &'static str) -> Option<T> {
+        let value = self.values.get(attribute)?;
+        match value.parse::<T>() {
+            Ok(v) => Some(v),
+            Err(_) => {
+                let problem = BatteryError::InvalidValue {
+                    cell: self.cell.clone(),
+                    attribute,
+                    value: value.clone(),
+                };
+                self.problems.push((attribute, problem));
+                None
+            }
+        }
+    }
+
+    /// Returns the value of an attribute in micro units as milli units
+    fn micro(&mut self, attribute: &'static str) -> Option<u64> {
+        self.get::<u64>(attribute).map(|v| v / 1000)
+    }
+
+    /// Returns the error of a required attribute without a valid value
+    fn missing(&mut self, attribute: &'static str) -> BatteryError {
+        match self.problems.iter().position(|(v, _)| *v == attribute) {
+            Some(i) => self.problems.remove(i).1,
+            None => BatteryError::MissingAttribute {
+                cell: self.cell.clone(),
+                attribute,
+            },
+        }
+    }
+
+    /// Records a problem the cell degrades with
+    fn warn(&mut self, problem: BatteryError) {
+        self.warnings.push(problem);
+    }
+
+    /// Logs the problems of the attributes
+    fn log(self) {
+        let problems = self.problems.into_iter().map(|(_, v)| v);
+        for problem in problems.chain(self.warnings) {
+            warn!("battery::Battery::new: {}", problem);
+        }
     }
 }
 
@@ -479,34 +1391,89 @@ mod tests {
         capacity_percent: f64,
         charge_percent: f64,
         charge_status: ChargeStatus,
-        time_remaining: u64,
+        time_remaining: Option<u64>,
     }
 
     fn validate_batteries(bt: &BatteriesTest) {
         let batteries = Batteries::new(&bt.path, false);
-        assert_eq!(batteries.is_ok(), true);
+        assert!(batteries.is_ok());
 
         let batteries = batteries.unwrap();
         assert_eq!(batteries.cells.len(), bt.num_cells);
-        relative_eq!(
+        assert!(
+            relative_eq!(
+                batteries.capacity_percent,
+                bt.capacity_percent,
+                epsilon = EPSILON
+            ),
+            "capacity {} != {}",
             batteries.capacity_percent,
-            bt.capacity_percent,
-            epsilon = EPSILON
+            bt.capacity_percent
         );
-        relative_eq!(
+        assert!(
+            relative_eq!(
+                batteries.charge_percent,
+                bt.charge_percent,
+                epsilon = EPSILON
+            ),
+            "charge {} != {}",
             batteries.charge_percent,
-            bt.charge_percent,
-            epsilon = EPSILON
+            bt.charge_percent
         );
         assert_eq!(batteries.charge_status, bt.charge_status);
         assert_eq!(batteries.time_remaining, bt.time_remaining);
     }
 
+    #[test]
+    fn test_command() {
+        let args = |v: &[&str]| -> Vec<String> { v.iter().map(|v| String::from(*v)).collect() };
+
+        assert_eq!(
+            Command::new(&args(&["battery", "battery.yaml"])),
+            Ok(Command::Block(PathBuf::from("battery.yaml")))
+        );
+        assert_eq!(
+            Command::new(&args(&["battery", "report"])),
+            Ok(Command::Report {
+                json: false,
+                config: None
+            })
+        );
+        assert_eq!(
+            Command::new(&args(&["battery", "report", "--json"])),
+            Ok(Command::Report {
+                json: true,
+                config: None
+            })
+        );
+        assert_eq!(
+            Command::new(&args(&["battery", "report", "--json", "battery.yaml"])),
+            Ok(Command::Report {
+                json: true,
+                config: Some(PathBuf::from("battery.yaml"))
+            })
+        );
+        assert_eq!(
+            Command::new(&args(&["battery", "history", "battery.yaml"])),
+            Ok(Command::History(PathBuf::from("battery.yaml")))
+        );
+        assert_eq!(
+            Command::new(&args(&["battery", "--watch", "battery.yaml"])),
+            Ok(Command::Watch(PathBuf::from("battery.yaml")))
+        );
+        assert!(Command::new(&args(&["battery"])).is_err());
+        assert!(Command::new(&args(&["battery", "history"])).is_err());
+        assert!(Command::new(&args(&["battery", "--watch"])).is_err());
+        assert!(Command::new(&args(&["battery", "report", "--xml"])).is_err());
+        assert!(Command::new(&args(&["battery", "report", "a.yaml", "b.yaml"])).is_err());
+        assert!(Command::new(&args(&["battery", "a.yaml", "b.yaml"])).is_err());
+    }
+
     #[test]
     fn test_parse_file_u64() {
         let path = PathBuf::from("tests/one-battery/BAT0/charge_now");
         let out = parse_file::<u64>(&path);
-        assert_eq!(out.is_some(), true);
+        assert!(out.is_some());
         assert_eq!(out.unwrap(), 1449000);
     }
 
@@ -514,7 +1481,7 @@ mod tests {
     fn test_parse_file_string() {
         let path = PathBuf::from("tests/one-battery/BAT0/status");
         let out = parse_file::<String>(&path);
-        assert_eq!(out.is_some(), true);
+        assert!(out.is_some());
         assert_eq!(out.unwrap(), String::from("Charging"));
     }
 
@@ -522,15 +1489,47 @@ mod tests {
     fn test_battery() {
         let path = PathBuf::from("tests/one-battery/BAT0");
         let battery = Battery::new(&path);
-        assert_eq!(battery.is_some(), true);
+        assert!(battery.is_ok());
 
         let battery = battery.unwrap();
+        assert_eq!(battery.units, Units::Charge);
         assert_eq!(battery.capacity_design, 7570);
         assert_eq!(battery.capacity_full, 5394);
         assert_eq!(battery.capacity_now, 1449);
         assert_eq!(battery.charge_status, ChargeStatus::Charging);
         assert_eq!(battery.present_rate, 2643);
-        assert_eq!(battery.time_remaining, 5373);
+        assert_eq!(battery.time_remaining, Some(5373));
+    }
+
+    #[test]
+    fn test_battery_energy() {
+        let path = PathBuf::from("tests/one-battery-energy/BAT0");
+        let battery = Battery::new(&path);
+        assert!(battery.is_ok());
+
+        let battery = battery.unwrap();
+        assert_eq!(battery.capacity_design, 62160);
+        assert_eq!(battery.capacity_full, 57020);
+        assert_eq!(battery.capacity_now, 41380);
+        assert_eq!(battery.charge_status, ChargeStatus::Discharging);
+        assert_eq!(battery.present_rate, 9460);
+        assert_eq!(battery.time_remaining, Some(15747));
+    }
+
+    #[test]
+    fn test_battery_energy_current() {
+        let path = PathBuf::from("tests/one-battery-energy-current/BAT0");
+        let battery = Battery::new(&path);
+        assert!(battery.is_ok());
+
+        let battery = battery.unwrap();
+        assert_eq!(battery.capacity_design, 62160);
+        assert_eq!(battery.capacity_full, 57020);
+        assert_eq!(battery.capacity_now, 41380);
+        assert_eq!(battery.charge_status, ChargeStatus::Charging);
+        assert_eq!(battery.present_rate, 9457);
+        assert_eq!(battery.time_remaining, Some(5953));
+        assert_eq!(battery.power, 10246);
     }
 
     #[test]
@@ -541,7 +1540,7 @@ mod tests {
             capacity_percent: 100.0 * (5394.0 / 7570.0),
             charge_percent: 100.0 * (1449.0 / 5394.0),
             charge_status: ChargeStatus::Charging,
-            time_remaining: 5373,
+            time_remaining: Some(5373),
         };
         validate_batteries(&bt);
     }
@@ -554,7 +1553,7 @@ mod tests {
             capacity_percent: 100.0 * (11499.0 / 16320.0),
             charge_percent: 100.0 * (4640.0 / 11499.0),
             charge_status: ChargeStatus::Charging,
-            time_remaining: 13867,
+            time_remaining: Some(6367),
         };
         validate_batteries(&bt);
     }
@@ -566,8 +1565,8 @@ mod tests {
             num_cells: 2,
             capacity_percent: 100.0 * (11499.0 / 16320.0),
             charge_percent: 100.0 * (4640.0 / 11499.0),
-            charge_status: ChargeStatus::Unknown,
-            time_remaining: 0,
+            charge_status: ChargeStatus::Charging,
+            time_remaining: Some(17537),
         };
         validate_batteries(&bt);
     }
@@ -580,7 +1579,7 @@ mod tests {
             capacity_percent: 100.0 * (11499.0 / 16320.0),
             charge_percent: 100.0 * (7554.0 / 11499.0),
             charge_status: ChargeStatus::Charging,
-            time_remaining: 5373,
+            time_remaining: Some(5373),
         };
         validate_batteries(&bt);
     }
@@ -593,7 +1592,35 @@ mod tests {
             capacity_percent: 100.0 * (11499.0 / 16320.0),
             charge_percent: 100.0 * (4640.0 / 11499.0),
             charge_status: ChargeStatus::Discharging,
-            time_remaining: 11274,
+            time_remaining: Some(4307),
+        };
+        validate_batteries(&bt);
+    }
+
+    #[test]
+    fn test_two_batteries_mixed_dis_dis() {
+        let bt = BatteriesTest {
+            path: PathBuf::from("tests/two-batteries-mixed-dis-dis"),
+            num_cells: 2,
+            capacity_percent: 100.0 * (83353.0 / 107567.0),
+            charge_percent: 100.0 * (34303.0 / 83353.0),
+            charge_status: ChargeStatus::Discharging,
+            time_remaining: Some(3691),
+        };
+        validate_batteries(&bt);
+    }
+
+    #[test]
+    fn test_two_batteries_mixed_no_voltage() {
+        // The charge cell can't be converted into energy, so the totals are
+        // those of the energy cell alone
+        let bt = BatteriesTest {
+            path: PathBuf::from("tests/two-batteries-mixed-no-voltage"),
+            num_cells: 2,
+            capacity_percent: 100.0 * (57020.0 / 62160.0),
+            charge_percent: 100.0 * (41380.0 / 57020.0),
+            charge_status: ChargeStatus::Discharging,
+            time_remaining: Some(15747),
         };
         validate_batteries(&bt);
     }
@@ -605,8 +1632,34 @@ mod tests {
             num_cells: 2,
             capacity_percent: 100.0 * (11499.0 / 16320.0),
             charge_percent: 100.0 * (7554.0 / 11499.0),
-            charge_status: ChargeStatus::Unknown,
-            time_remaining: 0,
+            charge_status: ChargeStatus::Discharging,
+            time_remaining: Some(10289),
+        };
+        validate_batteries(&bt);
+    }
+
+    #[test]
+    fn test_two_batteries_dis_idl() {
+        let bt = BatteriesTest {
+            path: PathBuf::from("tests/two-batteries-dis-idl"),
+            num_cells: 2,
+            capacity_percent: 100.0 * (127638.0 / 181152.0),
+            charge_percent: 100.0 * (51503.0 / 127638.0),
+            charge_status: ChargeStatus::Discharging,
+            time_remaining: Some(6320),
+        };
+        validate_batteries(&bt);
+    }
+
+    #[test]
+    fn test_two_batteries_idl_ful() {
+        let bt = BatteriesTest {
+            path: PathBuf::from("tests/two-batteries-idl-ful"),
+            num_cells: 2,
+            capacity_percent: 100.0 * (127638.0 / 181152.0),
+            charge_percent: 100.0 * (83848.0 / 127638.0),
+            charge_status: ChargeStatus::Idle,
+            time_remaining: None,
         };
         validate_batteries(&bt);
     }
@@ -619,7 +1672,7 @@ mod tests {
             capacity_percent: 100.0 * (11499.0 / 16320.0),
             charge_percent: 100.0,
             charge_status: ChargeStatus::Full,
-            time_remaining: 0,
+            time_remaining: None,
         };
         validate_batteries(&bt);
     }
@@ -630,9 +1683,304 @@ mod tests {
 
         let path = PathBuf::from("tests/one-battery");
         let batteries = Batteries::new(&path, false);
-        assert_eq!(batteries.is_ok(), true);
+        assert!(batteries.is_ok());
+
+        let batteries = batteries.unwrap();
+        assert_eq!(output, batteries.format_i3());
+    }
+
+    #[test]
+    fn test_two_batteries_ful_ful_format_i3() {
+        let output = String::from("BAT 100% (70%) FUL\nBAT 100%\n#FFFFFF");
+
+        let path = PathBuf::from("tests/two-batteries-ful-ful");
+        let batteries = Batteries::new(&path, false);
+        assert!(batteries.is_ok());
 
         let batteries = batteries.unwrap();
         assert_eq!(output, batteries.format_i3());
     }
+
+    #[test]
+    fn test_two_batteries_chr_dis_custom_format_i3() {
+        let output = String::from("27% 52%\n+ 40% 4:52:17\n#00FF00");
+
+        // The cells report no voltage, so their power is unknown
+        let format: Format = serde_yaml::from_str(
+            "full_text: '{charge:BAT0}% {charge:BAT1}%[ {watts} W][ {status:BAT2}]'\n\
+             short_text: '{status} {charge}%[ {time}]'\n\
+             status_labels:\n  charging: '+'\n",
+        )
+        .unwrap();
+
+        let path = PathBuf::from("tests/two-batteries-chr-dis");
+        let batteries = Batteries::new(&path, false);
+        assert!(batteries.is_ok());
+
+        let mut batteries = batteries.unwrap();
+        batteries.set_formatter(Formatter::new(&format).unwrap());
+        assert_eq!(output, batteries.format_i3());
+    }
+
+    #[test]
+    fn test_aggregate_status_external_power() {
+        let cells = [Battery::default()];
+        assert_eq!(aggregate_status(&cells, 0, false), ChargeStatus::Unknown);
+        assert_eq!(aggregate_status(&cells, 0, true), ChargeStatus::Idle);
+    }
+
+    fn format_sources(path: &str) -> String {
+        let format: Format = serde_yaml::from_str(
+            "full_text: '[{ac} ]{status}[ {adapter} W]'\n\
+             short_text: '[{ac:AC}]'\n\
+             ac_label: 'AC'\n",
+        )
+        .unwrap();
+
+        let path = PathBuf::from(path);
+        let batteries = Batteries::new(&path, false);
+        assert!(batteries.is_ok());
+
+        let mut batteries = batteries.unwrap();
+        batteries.set_formatter(Formatter::new(&format).unwrap());
+        batteries.format_i3()
+    }
+
+    #[test]
+    fn test_one_battery_ac() {
+        let output = format_sources("tests/one-battery-ac");
+        assert_eq!(output, "AC IDL\nAC\n#FFFFFF");
+    }
+
+    #[test]
+    fn test_one_battery_usb_pd() {
+        let output = format_sources("tests/one-battery-usb-pd");
+        assert_eq!(output, "AC CHR 65 W\n\n#00FF00");
+    }
+
+    #[test]
+    fn test_one_battery_no_adapter() {
+        let output = format_sources("tests/one-battery-no-adapter");
+        assert_eq!(output, "DIS\n\n#FF7300");
+    }
+
+    #[test]
+    fn test_batteries_selection() {
+        let path = PathBuf::from("tests/two-batteries-chr-dis");
+        let selection = Selection::new(vec![String::from("BAT0")], Vec::new());
+        let batteries = Batteries::with_selection(&path, &selection, false).unwrap();
+        assert_eq!(batteries.cells.len(), 1);
+        assert_eq!(batteries.cells[0].name, "BAT0");
+        assert_eq!(batteries.charge_status, batteries.cells[0].charge_status);
+
+        let selection = Selection::new(Vec::new(), vec![String::from("BAT*")]);
+        assert!(Batteries::with_selection(&path, &selection, false).is_err());
+    }
+
+    #[test]
+    fn test_format_separate_cells() {
+        let format: Format = serde_yaml::from_str(
+            "full_text: '{name} {charge}%[ {status}]'\n\
+             short_text: '{charge}%'\n\
+             cell_display: separate\n\
+             cell_separator: ' | '\n",
+        )
+        .unwrap();
+
+        let path = PathBuf::from("tests/two-batteries-chr-dis");
+        let mut batteries = Batteries::new(&path, false).unwrap();
+        batteries.set_formatter(Formatter::new(&format).unwrap());
+        assert_eq!(
+            batteries.format_i3(),
+            "BAT0 27% CHR | BAT1 52% DIS\n27% | 52%\n#00FF00"
+        );
+    }
+
+    #[test]
+    fn test_smooth_power() {
+        let mut estimator = RateEstimator::default();
+        let template = Template::parse("{watts} W {watts_avg} W").unwrap();
+
+        let mut batteries = Batteries::new(&PathBuf::from("tests/smoothing/0"), false).unwrap();
+        batteries.smooth_power(&mut estimator, 0.5);
+        assert_eq!(batteries.render(&template, false), "29.3 W 29.3 W");
+
+        let mut batteries = Batteries::new(&PathBuf::from("tests/smoothing/1"), false).unwrap();
+        batteries.smooth_power(&mut estimator, 0.5);
+        assert_eq!(batteries.render(&template, false), "13.3 W 21.3 W");
+    }
+
+    #[test]
+    fn test_click_views() {
+        let path = PathBuf::from("tests/two-batteries-chr-dis");
+        let mut batteries = Batteries::new(&path, false).unwrap();
+        batteries.set_formatter(Formatter::new(&Format::default()).unwrap());
+
+        batteries.click(RIGHT_BUTTON);
+        assert_eq!(batteries.view(), View::Summary);
+
+        batteries.click(LEFT_BUTTON);
+        assert_eq!(batteries.view(), View::Cells);
+        assert_eq!(
+            batteries.format_i3(),
+            "BAT0 27% CHR BAT1 52% DIS\nBAT0 27% BAT1 52%\n#00FF00"
+        );
+
+        batteries.click(LEFT_BUTTON);
+        assert_eq!(batteries.format_i3(), "BAT CHR\n\n#00FF00");
+
+        batteries.click(LEFT_BUTTON);
+        assert_eq!(
+            batteries.format_i3(),
+            "BAT health 70% wear 30%\nBAT 70%\n#00FF00"
+        );
+
+        // The time of day depends on the present time
+        batteries.click(LEFT_BUTTON);
+        let output = batteries.format_i3();
+        assert!(output.starts_with("BAT 40% CHR until "), "{}", output);
+
+        batteries.click(LEFT_BUTTON);
+        assert_eq!(batteries.view(), View::Summary);
+    }
+
+    #[test]
+    fn test_config_sysfs_root() {
+        let config: Config = serde_yaml::from_str(
+            "log_file_path: /tmp/battery.log\n\
+             sysfs_root: /host/sys\n\
+             include: [BAT0]\n",
+        )
+        .unwrap();
+        assert_eq!(
+            config.power_supply_path(),
+            PathBuf::from("/host/sys/class/power_supply")
+        );
+        assert!(config.selection.matches("BAT0"));
+        assert!(!config.selection.matches("BAT1"));
+
+        let config: Config = serde_yaml::from_str("log_file_path: /tmp/battery.log\n").unwrap();
+        assert_eq!(
+            config.power_supply_path(),
+            PathBuf::from("/sys/class/power_supply")
+        );
+    }
+
+    #[test]
+    fn test_batteries_exclude_devices() {
+        let path = PathBuf::from("tests/devices");
+        let batteries = Batteries::new(&path, false);
+        assert!(batteries.is_ok());
+
+        let batteries = batteries.unwrap();
+        assert_eq!(batteries.cells.len(), 1);
+        assert_eq!(batteries.cells[0].name, "BAT0");
+        assert_eq!(batteries.sources.len(), 1);
+    }
+
+    #[test]
+    fn test_battery_zero_current() {
+        let path = PathBuf::from("tests/one-battery-zero-current/BAT0");
+        let battery = Battery::new(&path).unwrap();
+        assert_eq!(battery.charge_status, ChargeStatus::Discharging);
+        assert_eq!(battery.present_rate, 0);
+        assert_eq!(battery.time_remaining, None);
+
+        let path = PathBuf::from("tests/one-battery-zero-current");
+        let batteries = Batteries::new(&path, false).unwrap();
+        assert_eq!(batteries.time_remaining, None);
+        assert_eq!(batteries.format_i3(), "BAT 27% (71%) IDL\nBAT 27%\n#FFFFFF");
+    }
+
+    #[test]
+    fn test_battery_overfull() {
+        let path = PathBuf::from("tests/one-battery-overfull/BAT0");
+        let battery = Battery::new(&path).unwrap();
+        assert_eq!(battery.capacity_now, battery.capacity_full);
+        assert_eq!(battery.time_remaining, Some(0));
+    }
+
+    #[test]
+    fn test_batteries_missing_files() {
+        let path = PathBuf::from("tests/two-batteries-missing-files");
+        let batteries = Batteries::new(&path, false).unwrap();
+        assert_eq!(batteries.cells.len(), 1);
+
+        // Without a design capacity or a rate, the cell degrades
+        let cell = &batteries.cells[0];
+        assert_eq!(cell.name, "BAT0");
+        assert_eq!(cell.capacity_design, cell.capacity_full);
+        assert_eq!(cell.time_remaining, None);
+
+        // Without a present capacity, the cell is unreadable
+        assert_eq!(batteries.errors.len(), 1);
+        assert!(matches!(
+            batteries.errors[0],
+            BatteryError::MissingAttribute {
+                attribute: "charge_now",
+                ..
+            }
+        ));
+
+        let selection = Selection::new(vec![String::from("BAT1")], Vec::new());
+        let batteries = Batteries::with_selection(&path, &selection, false);
+        assert!(matches!(
+            batteries,
+            Err(BatteryError::MissingAttribute {
+                attribute: "charge_now",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_batteries_garbage() {
+        let path = PathBuf::from("tests/two-batteries-garbage");
+        let batteries = Batteries::new(&path, false).unwrap();
+        assert_eq!(batteries.cells.len(), 1);
+        assert_eq!(batteries.cells[0].name, "BAT1");
+        assert_eq!(batteries.cells[0].cycle_count, None);
+
+        assert_eq!(batteries.errors.len(), 1);
+        assert_eq!(
+            batteries.errors[0].to_string(),
+            "cell BAT0 has an invalid `energy_now` of 'garbage'"
+        );
+    }
+
+    #[test]
+    fn test_battery_capacity_only() {
+        let path = PathBuf::from("tests/qemu/BAT0");
+        let battery = Battery::new(&path).unwrap();
+        assert!(battery.percentage_only);
+        assert_eq!(battery.charge_percent(), Some(87.0));
+        assert_eq!(battery.time_remaining, None);
+
+        let path = PathBuf::from("tests/qemu");
+        let mut batteries = Batteries::new(&path, false).unwrap();
+        assert_eq!(batteries.charge_status, ChargeStatus::Discharging);
+        assert_eq!(batteries.format_i3(), "BAT 87% DIS\nBAT 87%\n#37FF00");
+
+        batteries.set_view(View::Health);
+        assert_eq!(batteries.format_i3(), "BAT\nBAT\n#37FF00");
+    }
+
+    #[test]
+    fn test_battery_capacity_level_only() {
+        let path = PathBuf::from("tests/virtualbox/BAT0");
+        let battery = Battery::new(&path).unwrap();
+        assert_eq!(battery.capacity, None);
+        assert_eq!(battery.charge_percent(), Some(100.0));
+
+        let path = PathBuf::from("tests/virtualbox");
+        let batteries = Batteries::new(&path, false).unwrap();
+        assert_eq!(batteries.format_i3(), "BAT 100% FUL\nBAT 100%\n#FFFFFF");
+    }
+
+    #[test]
+    fn test_batteries_missing_directory() {
+        let path = PathBuf::from("tests/missing");
+        let batteries = Batteries::new(&path, false);
+        assert!(matches!(batteries, Err(BatteryError::Io { .. })));
+    }
 }
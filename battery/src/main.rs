@@ -1,24 +1,288 @@
-use battery;
 use battery::I3Block;
 
+// Constants
+const URGENT_EXIT_CODE: i32 = 33;
+const WATCH_INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
+
 fn main() {
-    // Parse config file
+    // Parse arguments
     let args: Vec<String> = std::env::args().collect();
-    let config = match battery::Config::new(&args) {
-        Ok(v) => v,
+    let config_path = match battery::Command::new(&args) {
+        Ok(battery::Command::Block(v)) => v,
+        Ok(battery::Command::Report { json, config }) => report(json, config.as_deref()),
+        Ok(battery::Command::History(v)) => history(&v),
+        Ok(battery::Command::Watch(v)) => watch(&v),
         Err(e) => {
             println!("{}", e);
+            eprintln!("{}", battery::USAGE);
             std::process::exit(0);
         }
     };
 
-    // Parse batteries
-    let path = std::path::PathBuf::from("/sys/class/power_supply");
-    match battery::Batteries::new(&path, config.log_batteries) {
-        Ok(batteries) => println!("{}", batteries.format_i3()),
+    // Parse config file
+    let config = match battery::Config::new(&config_path) {
+        Ok(v) => v,
         Err(e) => {
             println!("{}", e);
             std::process::exit(0);
         }
+    };
+
+    // Keep running in persistent mode
+    if config.persistent {
+        // Without a state file the view and alerts are only kept in memory
+        let mut view = battery::View::default();
+        let mut alerts = battery::Alerts::default();
+        let mut events = battery::SystemEvents::new();
+        let reason = battery::run(
+            &mut events,
+            std::time::Duration::from_secs(config.refresh_interval),
+            |click| update(&config, click, &mut view, Some(&mut alerts)),
+            &mut std::io::stdout(),
+        );
+        log::error!("battery::main: persistent mode stopped, {}", reason);
+        std::process::exit(1);
+    }
+
+    let click = std::env::var("BLOCK_BUTTON")
+        .ok()
+        .and_then(|v| v.parse().ok());
+    let (text, urgent) = update(&config, click, &mut battery::View::default(), None);
+    println!("{}", text);
+
+    // Set the urgent flag through the i3blocks exit code
+    if urgent {
+        std::process::exit(URGENT_EXIT_CODE);
+    }
+}
+
+/// Reads the power supplies and formats the block
+///
+/// # Arguments
+///
+/// - `config`: The parsed `Config`
+/// - `click`: The mouse button the block was clicked with, if any
+/// - `view`: The displayed view, used when no state file is configured
+/// - `alerts`: The fired alerts, used when no state file is configured
+///
+/// # Returns
+///
+/// The block's output and whether the urgent flag is set
+fn update(
+    config: &battery::Config,
+    click: Option<u8>,
+    view: &mut battery::View,
+    alerts: Option<&mut battery::Alerts>,
+) -> (String, bool) {
+    let path = config.power_supply_path();
+
+    // List peripheral devices in device mode
+    if config.mode == battery::Mode::Devices {
+        let mut devices = match battery::Devices::new(&path, config.log_batteries) {
+            Ok(v) => v,
+            Err(e) => return (e, false),
+        };
+
+        devices.set_options(config.devices.clone());
+        devices.set_formatter(config.formatter.clone());
+        return (devices.format_i3(), devices.any_low());
+    }
+
+    // Parse batteries
+    let parse =
+        || battery::Batteries::with_selection(&path, &config.selection, config.log_batteries);
+    let mut batteries = match parse() {
+        Ok(v) => v,
+        Err(_) => return (battery::DEFAULT_ERROR.into(), false),
+    };
+
+    // Cycle charge profiles on right click
+    if click == Some(battery::RIGHT_BUTTON) && !config.charge_profiles.is_empty() {
+        match battery::cycle_profile(
+            &config.charge_profiles,
+            &batteries,
+            config.charge_helper.as_deref(),
+        ) {
+            Ok(_) => {
+                batteries = match parse() {
+                    Ok(v) => v,
+                    Err(_) => return (battery::DEFAULT_ERROR.into(), false),
+                }
+            }
+            Err(e) => log::error!("battery::main: {}", e),
+        }
+    }
+    let profile = battery::active_profile(&config.charge_profiles, &batteries);
+    batteries.set_profile(profile.map(|v| v.name.clone()));
+
+    // Load persisted state
+    let mut state = config
+        .state_file_path
+        .as_ref()
+        .map(|path| battery::State::load(path));
+
+    // Smooth (dis)charge rates with persisted history
+    if let Some(state) = &mut state {
+        batteries.smooth(&mut state.rates, config.rate_smoothing);
+        batteries.smooth_power(&mut state.power, config.rate_smoothing);
+    }
+
+    // Fire alerts on threshold crossings
+    let thresholds = config.thresholds();
+    let alerts = match &mut state {
+        Some(state) => Some(&mut state.alerts),
+        None => alerts,
+    };
+    if let Some(alerts) = alerts {
+        if let Some(level) = alerts.update(&batteries, &thresholds) {
+            let notifier = if config.notifications {
+                match battery::DesktopNotifier::new() {
+                    Ok(v) => Some(v),
+                    Err(e) => {
+                        log::error!("battery::main: {}", e);
+                        None
+                    }
+                }
+            } else {
+                None
+            };
+            battery::fire_alert(
+                level,
+                &batteries,
+                notifier.as_ref().map(|v| v as &dyn battery::Notifier),
+                config.critical_command.as_deref(),
+            );
+        }
+    }
+
+    // Cycle views on click
+    if let Some(state) = &state {
+        *view = state.view;
+    }
+    batteries.set_view(*view);
+    if let Some(button) = click {
+        batteries.click(button);
+        *view = batteries.view();
+    }
+    if let Some(state) = &mut state {
+        state.view = *view;
+    }
+
+    // Save persisted state
+    if let (Some(state), Some(path)) = (&state, &config.state_file_path) {
+        state.save(path);
+    }
+
+    // Record long-term history
+    if let Some(path) = &config.history_file_path {
+        let history = battery::History::new(path, config.history_interval);
+        history.record(&batteries, std::time::SystemTime::now());
+    }
+
+    let urgent = thresholds.level(&batteries) != battery::AlertLevel::None;
+    batteries.set_formatter(config.formatter.clone());
+    (batteries.format_i3(), urgent)
+}
+
+/// Prints a battery health report and exits
+///
+/// The batteries are read from the sysfs root and selection of the
+/// configuration file if given, or all batteries under `/sys` otherwise.
+fn report(json: bool, config_path: Option<&std::path::Path>) -> ! {
+    let (root, selection) = match config_path {
+        Some(path) => match battery::Config::new(path) {
+            Ok(config) => (config.sysfs_root, config.selection),
+            Err(_) => {
+                eprintln!("battery: unable to parse {}", path.display());
+                std::process::exit(1);
+            }
+        },
+        None => (
+            std::path::PathBuf::from(battery::SYSFS_ROOT),
+            battery::Selection::default(),
+        ),
+    };
+    let report = match battery::Report::read(&root, &selection) {
+        Ok(v) => v,
+        Err(e) => {
+            eprintln!(
+                "battery: unable to read batteries from {}, {}",
+                root.display(),
+                e
+            );
+            std::process::exit(1);
+        }
+    };
+
+    if json {
+        match report.to_json() {
+            Ok(v) => println!("{}", v),
+            Err(e) => {
+                eprintln!("battery: {}", e);
+                std::process::exit(1);
+            }
+        }
+    } else {
+        print!("{}", report);
+    }
+
+    std::process::exit(0);
+}
+
+/// Prints a summary of the history file of a configuration and exits
+fn history(config_path: &std::path::Path) -> ! {
+    let config = match battery::Config::new(config_path) {
+        Ok(v) => v,
+        Err(_) => {
+            eprintln!("battery: unable to parse {}", config_path.display());
+            std::process::exit(1);
+        }
+    };
+
+    let path = match &config.history_file_path {
+        Some(v) => v,
+        None => {
+            eprintln!("battery: `history_file_path` isn't set");
+            std::process::exit(1);
+        }
+    };
+
+    match battery::History::new(path, config.history_interval).load() {
+        Ok(samples) => print!("{}", battery::Summary::new(&samples, &chrono::Local)),
+        Err(e) => {
+            eprintln!("battery: {}", e);
+            std::process::exit(1);
+        }
+    }
+
+    std::process::exit(0);
+}
+
+/// Prints a rolling table of the power of the configured batteries
+fn watch(config_path: &std::path::Path) -> ! {
+    let config = match battery::Config::new(config_path) {
+        Ok(v) => v,
+        Err(_) => {
+            eprintln!("battery: unable to parse {}", config_path.display());
+            std::process::exit(1);
+        }
+    };
+
+    let path = config.power_supply_path();
+    let mut watch = battery::Watch::new(config.rate_smoothing);
+    loop {
+        match battery::Batteries::with_selection(&path, &config.selection, false) {
+            Ok(mut batteries) => {
+                let time = chrono::Local::now().time();
+                print!("{}", watch.update(&mut batteries, time));
+            }
+            Err(e) => eprintln!(
+                "battery: unable to read batteries from {}, {}",
+                path.display(),
+                e
+            ),
+        }
+
+        std::thread::sleep(WATCH_INTERVAL);
     }
 }
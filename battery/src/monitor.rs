@@ -0,0 +1,466 @@
+use std::convert::TryFrom;
+use std::io::Write;
+use std::os::unix::io::{AsRawFd, FromRawFd, OwnedFd, RawFd};
+use std::time::{Duration, Instant};
+
+use log::warn;
+
+// Constants
+const CLICK_BUFFER_SIZE: usize = 4096;
+const KERNEL_UEVENT_GROUP: u32 = 1;
+const UEVENT_BUFFER_SIZE: usize = 8192;
+
+/// Events waking up the persistent mode
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Event {
+    Change,    // a power supply changed, e.g. the charger was plugged in
+    Click(u8), // the block was clicked with a mouse button
+    Timeout,   // the refresh interval elapsed
+}
+
+/// Source of events waking up the persistent mode
+pub trait EventSource {
+    /// Waits for the next event
+    ///
+    /// # Arguments
+    ///
+    /// - `timeout`: Maximum time to wait for a change
+    ///
+    /// # Returns
+    ///
+    /// A `Result`:
+    /// - `Ok`: The `Event` that ended the wait
+    /// - `Err`: A `String` describing why no more events can be received
+    fn wait(&mut self, timeout: Duration) -> Result<Event, String>;
+}
+
+/// Event source of the persistent mode
+///
+/// The kernel broadcasts a uevent over a netlink socket whenever a power
+/// supply changes, e.g. when the charger is plugged in or a battery changes
+/// status. i3blocks writes a JSON object per line to the standard input of
+/// persistent blocks whenever they are clicked.
+pub struct SystemEvents {
+    uevents: Option<OwnedFd>, // netlink socket subscribed to kernel uevents
+    clicks: Option<RawFd>,    // input of click events
+    pending: Vec<u8>,         // click events read but not yet returned
+}
+
+impl Default for SystemEvents {
+    fn default() -> SystemEvents {
+        SystemEvents::new()
+    }
+}
+
+impl SystemEvents {
+    /// Subscribes to kernel uevents and click events on the standard input
+    ///
+    /// The source falls back to waking up once per refresh interval if the
+    /// netlink socket can't be opened, e.g. in a container.
+    pub fn new() -> SystemEvents {
+        let uevents = match open_uevent_socket() {
+            Ok(v) => Some(v),
+            Err(e) => {
+                warn!("battery::SystemEvents::new: {}, falling back to timer", e);
+                None
+            }
+        };
+
+        SystemEvents {
+            uevents,
+            clicks: Some(std::io::stdin().as_raw_fd()),
+            pending: Vec::new(),
+        }
+    }
+
+    /// Returns the button of the next complete click event already read
+    fn pending_click(&mut self) -> Option<u8> {
+        while let Some(end) = self.pending.iter().position(|byte| *byte == b'\n') {
+            let line: Vec<u8> = self.pending.drain(..=end).collect();
+            if let Some(button) = parse_click(&line) {
+                return Some(button);
+            }
+        }
+
+        None
+    }
+
+    /// Receives a uevent, returning whether it concerns a power supply
+    fn receive_uevent(socket: &OwnedFd) -> Result<bool, String> {
+        let mut buffer = [0u8; UEVENT_BUFFER_SIZE];
+
+        // SAFETY: `buffer` is valid for writes of its full length
+        let length = unsafe {
+            libc::recv(
+                socket.as_raw_fd(),
+                buffer.as_mut_ptr() as *mut libc::c_void,
+                buffer.len(),
+                0,
+            )
+        };
+        if length < 0 {
+            let e = std::io::Error::last_os_error();
+            if e.raw_os_error() == Some(libc::ENOBUFS) {
+                // Uevents were dropped, re-read to catch up on them
+                return Ok(true);
+            } else if e.kind() == std::io::ErrorKind::Interrupted {
+                return Ok(false);
+            }
+            return Err(format!("unable to receive uevent, {}", e));
+        }
+
+        Ok(is_power_supply_uevent(&buffer[..length as usize]))
+    }
+
+    /// Reads click events, returning whether the input is still open
+    fn read_clicks(&mut self, fd: RawFd) -> bool {
+        let mut buffer = [0u8; CLICK_BUFFER_SIZE];
+
+        // SAFETY: `buffer` is valid for writes of its full length
+        let length =
+            unsafe { libc::read(fd, buffer.as_mut_ptr() as *mut libc::c_void, buffer.len()) };
+        if length < 0 {
+            return std::io::Error::last_os_error().kind() == std::io::ErrorKind::Interrupted;
+        } else if length == 0 {
+            return false;
+        }
+
+        self.pending.extend_from_slice(&buffer[..length as usize]);
+        true
+    }
+}
+
+impl EventSource for SystemEvents {
+    fn wait(&mut self, timeout: Duration) -> Result<Event, String> {
+        let deadline = Instant::now() + timeout;
+
+        loop {
+            if let Some(button) = self.pending_click() {
+                return Ok(Event::Click(button));
+            }
+
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining == Duration::from_secs(0) {
+                return Ok(Event::Timeout);
+            }
+
+            let mut poll_fds: Vec<libc::pollfd> = self
+                .uevents
+                .iter()
+                .map(|socket| socket.as_raw_fd())
+                .chain(self.clicks)
+                .map(|fd| libc::pollfd {
+                    fd,
+                    events: libc::POLLIN,
+                    revents: 0,
+                })
+                .collect();
+            if poll_fds.is_empty() {
+                std::thread::sleep(remaining);
+                return Ok(Event::Timeout);
+            }
+            let timeout_ms = remaining.as_millis().clamp(1, i32::MAX as u128) as libc::c_int;
+
+            // SAFETY: `poll_fds` is a valid array of `pollfd` of the given length
+            let ready = unsafe {
+                libc::poll(
+                    poll_fds.as_mut_ptr(),
+                    poll_fds.len() as libc::nfds_t,
+                    timeout_ms,
+                )
+            };
+            if ready < 0 {
+                let e = std::io::Error::last_os_error();
+                if e.kind() == std::io::ErrorKind::Interrupted {
+                    continue;
+                }
+                return Err(format!("unable to poll events, {}", e));
+            } else if ready == 0 {
+                return Ok(Event::Timeout);
+            }
+
+            let mut change = false;
+            for poll_fd in poll_fds.iter().filter(|v| v.revents != 0) {
+                if Some(poll_fd.fd) == self.clicks {
+                    if !self.read_clicks(poll_fd.fd) {
+                        // i3blocks closed the input, stop listening to it
+                        self.clicks = None;
+                    }
+                } else if let Some(socket) = &self.uevents {
+                    change |= SystemEvents::receive_uevent(socket)?;
+                }
+            }
+
+            if change {
+                return Ok(Event::Change);
+            }
+        }
+    }
+}
+
+/// Opens a netlink socket subscribed to kernel uevents
+fn open_uevent_socket() -> Result<OwnedFd, String> {
+    // SAFETY: `socket` has no memory safety preconditions and the file
+    // descriptor it returns is owned by `OwnedFd` on success
+    let socket = unsafe {
+        let fd = libc::socket(
+            libc::AF_NETLINK,
+            libc::SOCK_DGRAM | libc::SOCK_CLOEXEC,
+            libc::NETLINK_KOBJECT_UEVENT,
+        );
+        if fd < 0 {
+            return Err(format!(
+                "unable to open netlink socket, {}",
+                std::io::Error::last_os_error()
+            ));
+        }
+        OwnedFd::from_raw_fd(fd)
+    };
+
+    // SAFETY: `sockaddr_nl` is plain data for which zero is valid, and
+    // `bind` is given its exact size
+    let result = unsafe {
+        let mut address: libc::sockaddr_nl = std::mem::zeroed();
+        address.nl_family = libc::AF_NETLINK as libc::sa_family_t;
+        address.nl_groups = KERNEL_UEVENT_GROUP;
+        libc::bind(
+            socket.as_raw_fd(),
+            &address as *const libc::sockaddr_nl as *const libc::sockaddr,
+            std::mem::size_of::<libc::sockaddr_nl>() as libc::socklen_t,
+        )
+    };
+    if result < 0 {
+        return Err(format!(
+            "unable to bind netlink socket, {}",
+            std::io::Error::last_os_error()
+        ));
+    }
+
+    Ok(socket)
+}
+
+/// Returns the button of an i3blocks click event, e.g. `{"button":1,...}`
+pub(crate) fn parse_click(line: &[u8]) -> Option<u8> {
+    let event: serde_json::Value = serde_json::from_slice(line).ok()?;
+    event["button"].as_u64().and_then(|v| u8::try_from(v).ok())
+}
+
+/// Returns whether a kernel uevent concerns a power supply
+///
+/// Kernel uevents are a header, e.g. `change@/devices/.../BAT0`, followed
+/// by `KEY=value` fields, all terminated by NUL characters.
+pub(crate) fn is_power_supply_uevent(message: &[u8]) -> bool {
+    message
+        .split(|byte| *byte == 0)
+        .any(|field| field == b"SUBSYSTEM=power_supply")
+}
+
+/// Runs the block persistently
+///
+/// The block is updated immediately, then on every event. i3blocks reads one
+/// JSON object per line from persistent blocks, so each update is printed
+/// as a line of JSON, and only if it differs from the previous one.
+///
+/// # Arguments
+///
+/// - `events`: The `EventSource` waking up the block
+/// - `interval`: Refresh interval without any change
+/// - `update`: Returns the block's output in the i3blocks line format and
+///   whether the urgent flag is set, given the clicked button if any
+/// - `output`: Writer of the JSON lines, usually the standard output
+///
+/// # Returns
+///
+/// A `String` describing why the block stopped, either the event source
+/// failing or the output being closed
+pub fn run<F>(
+    events: &mut dyn EventSource,
+    interval: Duration,
+    mut update: F,
+    output: &mut dyn Write,
+) -> String
+where
+    F: FnMut(Option<u8>) -> (String, bool),
+{
+    let mut previous = String::new();
+    let mut click = None;
+
+    loop {
+        let (text, urgent) = update(click);
+        let line = to_json(&text, urgent);
+        if line != previous {
+            if let Err(e) = writeln!(output, "{}", line).and_then(|_| output.flush()) {
+                return format!("unable to write output, {}", e);
+            }
+            previous = line;
+        }
+
+        click = match events.wait(interval) {
+            Ok(Event::Click(button)) => Some(button),
+            Ok(Event::Change) | Ok(Event::Timeout) => None,
+            Err(e) => return e,
+        };
+    }
+}
+
+/// Converts the i3blocks line format into a JSON object
+fn to_json(text: &str, urgent: bool) -> String {
+    let mut lines = text.lines();
+    let full_text = lines.next().unwrap_or("");
+    let short_text = lines.next().unwrap_or("");
+
+    let mut object = serde_json::json!({
+        "full_text": full_text,
+        "short_text": short_text,
+    });
+    if let Some(color) = lines.next() {
+        object["color"] = color.into();
+    }
+    if urgent {
+        object["urgent"] = true.into();
+    }
+
+    match serde_json::to_string(&object) {
+        Ok(v) => v,
+        Err(e) => {
+            warn!("battery::monitor::to_json: {}", e);
+            String::from("{\"full_text\":\"BAT Error!\",\"color\":\"#FF0000\"}")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::VecDeque;
+
+    /// Event source replaying scripted events
+    struct ScriptedEvents(VecDeque<Event>);
+
+    impl EventSource for ScriptedEvents {
+        fn wait(&mut self, _timeout: Duration) -> Result<Event, String> {
+            self.0
+                .pop_front()
+                .ok_or_else(|| String::from("no more events"))
+        }
+    }
+
+    #[test]
+    fn test_is_power_supply_uevent() {
+        let message = b"change@/devices/LNXSYSTM:00/PNP0C0A:00/power_supply/BAT0\0\
+                        ACTION=change\0\
+                        DEVPATH=/devices/LNXSYSTM:00/PNP0C0A:00/power_supply/BAT0\0\
+                        SUBSYSTEM=power_supply\0\
+                        POWER_SUPPLY_NAME=BAT0\0";
+        assert!(is_power_supply_uevent(message));
+
+        let message = b"add@/devices/virtual/net/tun0\0ACTION=add\0SUBSYSTEM=net\0";
+        assert!(!is_power_supply_uevent(message));
+    }
+
+    #[test]
+    fn test_parse_click() {
+        let line = b"{\"name\":\"battery\",\"instance\":\"\",\"button\":1,\"x\":1840,\"y\":10}\n";
+        assert_eq!(parse_click(line), Some(1));
+        assert_eq!(parse_click(b"{\"button\":3}"), Some(3));
+        assert_eq!(parse_click(b"{\"name\":\"battery\"}"), None);
+        assert_eq!(parse_click(b"1"), None);
+        assert_eq!(parse_click(b"{\"button\":"), None);
+    }
+
+    #[test]
+    fn test_pending_click() {
+        let mut events = SystemEvents {
+            uevents: None,
+            clicks: None,
+            pending: b"{\"button\":1}\ninvalid\n{\"button\":3}\n{\"but".to_vec(),
+        };
+        assert_eq!(events.pending_click(), Some(1));
+        assert_eq!(events.pending_click(), Some(3));
+        assert_eq!(events.pending_click(), None);
+        assert_eq!(events.pending, b"{\"but".to_vec());
+    }
+
+    #[test]
+    fn test_to_json() {
+        assert_eq!(
+            to_json("BAT 27%\nBAT\n#00FF00", false),
+            "{\"color\":\"#00FF00\",\"full_text\":\"BAT 27%\",\"short_text\":\"BAT\"}"
+        );
+        assert_eq!(
+            to_json("BAT 5%\nBAT\n#FF0000", true),
+            "{\"color\":\"#FF0000\",\"full_text\":\"BAT 5%\",\"short_text\":\"BAT\",\
+              \"urgent\":true}"
+        );
+        assert_eq!(
+            to_json("", false),
+            "{\"full_text\":\"\",\"short_text\":\"\"}"
+        );
+    }
+
+    #[test]
+    fn test_run() {
+        let mut events = ScriptedEvents(
+            vec![Event::Change, Event::Click(1), Event::Change]
+                .into_iter()
+                .collect(),
+        );
+        let mut updates = vec![
+            ("BAT 40%\n\n#FFFFFF", false),
+            ("BAT 40%\n\n#FFFFFF", false),
+            ("BAT 41%\n\n#00FF00", false),
+            ("BAT 5%\n\n#FF0000", true),
+        ]
+        .into_iter();
+        let mut output = Vec::new();
+        let mut clicks = Vec::new();
+
+        let reason = run(
+            &mut events,
+            Duration::from_secs(15),
+            |click| {
+                clicks.push(click);
+                let (text, urgent) = updates.next().unwrap();
+                (String::from(text), urgent)
+            },
+            &mut output,
+        );
+        assert_eq!(reason, "no more events");
+        assert_eq!(clicks, vec![None, None, Some(1), None]);
+
+        let output = String::from_utf8(output).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].contains("\"full_text\":\"BAT 40%\""));
+        assert!(lines[1].contains("\"full_text\":\"BAT 41%\""));
+        assert!(lines[2].contains("\"urgent\":true"));
+    }
+
+    #[test]
+    fn test_run_with_batteries() {
+        use crate::{Batteries, I3Block};
+        use std::path::PathBuf;
+
+        let mut events = ScriptedEvents(vec![Event::Change].into_iter().collect());
+        let mut snapshots = vec!["tests/two-batteries-dis-dis", "tests/two-batteries-chr-chr"]
+            .into_iter()
+            .map(PathBuf::from);
+        let mut output = Vec::new();
+
+        run(
+            &mut events,
+            Duration::from_secs(15),
+            |_| {
+                let path = snapshots.next().unwrap();
+                (Batteries::new(&path, false).unwrap().format_i3(), false)
+            },
+            &mut output,
+        );
+
+        let output = String::from_utf8(output).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].contains("DIS"));
+        assert!(lines[1].contains("CHR"));
+    }
+}
@@ -0,0 +1,321 @@
+use std::fmt;
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::{Batteries, Battery, BatteryError, Selection, POWER_SUPPLY_CLASS};
+
+/// Health report of a single cell
+#[derive(Debug, Serialize)]
+pub struct CellReport {
+    name: String,                  // name of the cell
+    manufacturer: Option<String>,  // manufacturer of the cell
+    model_name: Option<String>,    // model of the cell
+    serial_number: Option<String>, // serial number of the cell
+    technology: Option<String>,    // chemistry, e.g. `Li-ion`
+    cycle_count: Option<u64>,      // charge cycles
+    status: String,                // charging status
+
+    capacity_unit: &'static str, // unit of the capacities, `mWh` or `mAh`
+    capacity_design: Option<u64>, // 'full' design capacity
+    capacity_full: Option<u64>,  // last 'full' capacity
+    capacity_now: Option<u64>,   // present capacity
+    wear_percent: Option<f64>,   // capacity lost relative to design (%)
+
+    voltage_now: Option<u64>,        // present voltage (mV)
+    voltage_min_design: Option<u64>, // minimum design voltage (mV)
+}
+
+impl CellReport {
+    fn new(cell: &Battery) -> CellReport {
+        // Capacities are unknown for cells only reporting their charge
+        let known = |v| Some(v).filter(|_| !cell.percentage_only);
+        CellReport {
+            name: cell.name.clone(),
+            manufacturer: cell.manufacturer.clone(),
+            model_name: cell.model_name.clone(),
+            serial_number: cell.serial_number.clone(),
+            technology: cell.technology.clone(),
+            cycle_count: cell.cycle_count,
+            status: cell.charge_status.to_string(),
+            capacity_unit: cell.units.capacity(),
+            capacity_design: known(cell.capacity_design),
+            capacity_full: known(cell.capacity_full),
+            capacity_now: known(cell.capacity_now),
+            wear_percent: known(cell.capacity_design).map(|v| wear(v, cell.capacity_full)),
+            voltage_now: cell.voltage_now,
+            voltage_min_design: cell.voltage_min_design,
+        }
+    }
+}
+
+/// Health report of all cells combined
+#[derive(Debug, Serialize)]
+pub struct TotalReport {
+    status: String, // overall charging status
+
+    capacity_unit: &'static str, // unit of the capacities, `mWh` or `mAh`
+    capacity_design: Option<u64>, // 'full' design capacity
+    capacity_full: Option<u64>,  // last 'full' capacity
+    capacity_now: Option<u64>,   // present capacity
+    charge_percent: f64,         // present charge (%)
+    wear_percent: Option<f64>,   // capacity lost relative to design (%)
+}
+
+/// Battery health report
+///
+/// The report is printed by the `report` subcommand, either as
+/// human-readable text through `Display` or as JSON.
+#[derive(Debug, Serialize)]
+pub struct Report {
+    cells: Vec<CellReport>, // reports of the individual cells
+    errors: Vec<String>,    // errors of the unreadable cells
+    total: TotalReport,     // report of all cells combined
+}
+
+impl Report {
+    /// Builds the report of a set of batteries
+    ///
+    /// # Arguments
+    ///
+    /// - `batteries`: The present `Batteries`
+    pub fn new(batteries: &Batteries) -> Report {
+        let known = |v| Some(v).filter(|_| !batteries.percentage_only);
+        Report {
+            cells: batteries.cells.iter().map(CellReport::new).collect(),
+            errors: batteries.errors.iter().map(|e| e.to_string()).collect(),
+            total: TotalReport {
+                status: batteries.charge_status.to_string(),
+                capacity_unit: batteries.units.capacity(),
+                capacity_design: known(batteries.capacity_design),
+                capacity_full: known(batteries.capacity_full),
+                capacity_now: known(batteries.capacity_now),
+                charge_percent: batteries.charge_percent,
+                wear_percent: known(batteries.capacity_design)
+                    .map(|v| wear(v, batteries.capacity_full)),
+            },
+        }
+    }
+
+    /// Reads the batteries under a sysfs root and builds their report
+    ///
+    /// # Arguments
+    ///
+    /// - `sysfs_root`: Root of the sysfs file system, e.g. `/sys`
+    /// - `selection`: Selection of the reported cells
+    ///
+    /// # Returns
+    ///
+    /// A `Result`:
+    /// - `Ok`: The `Report` of the selected batteries
+    /// - `Err`: A `BatteryError` if no battery could be read
+    pub fn read(sysfs_root: &Path, selection: &Selection) -> Result<Report, BatteryError> {
+        let path = sysfs_root.join(POWER_SUPPLY_CLASS);
+        Batteries::with_selection(&path, selection, false).map(|v| Report::new(&v))
+    }
+
+    /// Serializes the report as pretty-printed JSON
+    ///
+    /// # Returns
+    ///
+    /// A `Result`:
+    /// - `Ok`: A `String` with the JSON report
+    /// - `Err`: A `String` describing the serialization failure
+    pub fn to_json(&self) -> Result<String, String> {
+        serde_json::to_string_pretty(self).map_err(|e| e.to_string())
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for cell in &self.cells {
+            writeln!(f, "{}", cell.name)?;
+            write_field(f, "manufacturer", &cell.manufacturer)?;
+            write_field(f, "model", &cell.model_name)?;
+            write_field(f, "serial number", &cell.serial_number)?;
+            write_field(f, "technology", &cell.technology)?;
+            write_field(f, "cycle count", &cell.cycle_count)?;
+            write_field(f, "status", &Some(&cell.status))?;
+            write_capacities(
+                f,
+                cell.capacity_unit,
+                cell.capacity_design,
+                cell.capacity_full,
+                cell.capacity_now,
+                cell.wear_percent,
+            )?;
+            let voltage = match (cell.voltage_now, cell.voltage_min_design) {
+                (Some(now), Some(design)) => Some(format!("{} mV (design {} mV)", now, design)),
+                (Some(now), None) => Some(format!("{} mV", now)),
+                (None, Some(design)) => Some(format!("unknown (design {} mV)", design)),
+                (None, None) => None,
+            };
+            write_field(f, "voltage", &voltage)?;
+            writeln!(f)?;
+        }
+
+        if !self.errors.is_empty() {
+            writeln!(f, "Unreadable cells")?;
+            for error in &self.errors {
+                writeln!(f, "  {}", error)?;
+            }
+            writeln!(f)?;
+        }
+
+        writeln!(f, "All cells")?;
+        write_field(f, "status", &Some(&self.total.status))?;
+        write_capacities(
+            f,
+            self.total.capacity_unit,
+            self.total.capacity_design,
+            self.total.capacity_full,
+            self.total.capacity_now,
+            self.total.wear_percent,
+        )?;
+        write_field(
+            f,
+            "charge",
+            &Some(format!("{:.1}%", self.total.charge_percent)),
+        )
+    }
+}
+
+/// Writes an aligned field of the text report, `unknown` if unavailable
+fn write_field<T: fmt::Display>(
+    f: &mut fmt::Formatter,
+    name: &str,
+    value: &Option<T>,
+) -> fmt::Result {
+    match value {
+        Some(v) => writeln!(f, "  {:<16}{}", name, v),
+        None => writeln!(f, "  {:<16}unknown", name),
+    }
+}
+
+/// Writes the capacity fields of the text report
+fn write_capacities(
+    f: &mut fmt::Formatter,
+    unit: &str,
+    design: Option<u64>,
+    full: Option<u64>,
+    now: Option<u64>,
+    wear: Option<f64>,
+) -> fmt::Result {
+    let capacity = |v: Option<u64>| v.map(|v| format!("{} {}", v, unit));
+    write_field(f, "design capacity", &capacity(design))?;
+    write_field(f, "full capacity", &capacity(full))?;
+    write_field(f, "capacity now", &capacity(now))?;
+    write_field(f, "wear", &wear.map(|v| format!("{:.1}%", v)))
+}
+
+/// Returns the capacity lost relative to the design capacity (%)
+fn wear(design: u64, full: u64) -> f64 {
+    if design == 0 {
+        return 0.0;
+    }
+
+    100.0 * (design.saturating_sub(full) as f64) / (design as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn report(path: &str) -> Report {
+        let batteries = Batteries::new(&PathBuf::from(path), false).unwrap();
+        Report::new(&batteries)
+    }
+
+    #[test]
+    fn test_wear() {
+        assert_eq!(wear(50000, 40000), 20.0);
+        assert_eq!(wear(50000, 52000), 0.0);
+        assert_eq!(wear(0, 40000), 0.0);
+    }
+
+    #[test]
+    fn test_report_errors() {
+        let text = report("tests/two-batteries-garbage").to_string();
+        assert!(text.contains(
+            "Unreadable cells\n  cell BAT0 has an invalid `energy_now` of 'garbage'\n\n"
+        ));
+    }
+
+    #[test]
+    fn test_report_capacity_only() {
+        let text = report("tests/qemu").to_string();
+        assert!(text.contains(
+            "\x20 design capacity unknown\n\
+             \x20 full capacity   unknown\n\
+             \x20 capacity now    unknown\n\
+             \x20 wear            unknown\n\
+             \x20 charge          87.0%\n"
+        ));
+    }
+
+    #[test]
+    fn test_report_text() {
+        let report = report("tests/one-battery-report");
+        assert_eq!(
+            report.to_string(),
+            "BAT0\n\
+             \x20 manufacturer    SMP\n\
+             \x20 model           5B10W13930\n\
+             \x20 serial number   1234\n\
+             \x20 technology      Li-poly\n\
+             \x20 cycle count     245\n\
+             \x20 status          charging\n\
+             \x20 design capacity 84027 mWh\n\
+             \x20 full capacity   59873 mWh\n\
+             \x20 capacity now    16083 mWh\n\
+             \x20 wear            28.7%\n\
+             \x20 voltage         12012 mV (design 11100 mV)\n\
+             \n\
+             All cells\n\
+             \x20 status          charging\n\
+             \x20 design capacity 84027 mWh\n\
+             \x20 full capacity   59873 mWh\n\
+             \x20 capacity now    16083 mWh\n\
+             \x20 wear            28.7%\n\
+             \x20 charge          26.9%\n"
+        );
+    }
+
+    #[test]
+    fn test_report_text_unknown() {
+        let text = report("tests/two-batteries-chr-dis").to_string();
+        assert!(text.starts_with("BAT0\n  manufacturer    unknown\n"));
+        assert!(text.contains("BAT1\n"));
+    }
+
+    #[test]
+    fn test_report_sysfs_root() {
+        let root = PathBuf::from("tests/sysfs");
+        let report = Report::read(&root, &Selection::default()).unwrap();
+        let names: Vec<_> = report.cells.iter().map(|v| v.name.as_str()).collect();
+        assert_eq!(names, ["BAT0", "BAT1"]);
+
+        let selection = Selection::new(vec!["BAT1".into()], Vec::new());
+        let report = Report::read(&root, &selection).unwrap();
+        assert_eq!(report.cells.len(), 1);
+        assert_eq!(report.cells[0].name, "BAT1");
+
+        assert!(Report::read(&PathBuf::from("tests/missing"), &selection).is_err());
+    }
+
+    #[test]
+    fn test_report_json() {
+        let json = report("tests/one-battery-report").to_json().unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+
+        let cell = &value["cells"][0];
+        assert_eq!(cell["name"], "BAT0");
+        assert_eq!(cell["model_name"], "5B10W13930");
+        assert_eq!(cell["cycle_count"], 245);
+        assert_eq!(cell["capacity_design"], 84027);
+        assert_eq!(cell["voltage_now"], 12012);
+        assert_eq!(value["total"]["status"], "charging");
+        assert_eq!(value["total"]["capacity_full"], 59873);
+    }
+}
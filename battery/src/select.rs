@@ -0,0 +1,108 @@
+use serde::Deserialize;
+
+/// Selection of battery cells by name
+///
+/// Names are matched against glob patterns where `*` matches any sequence
+/// of characters and `?` matches any single character, e.g. `BAT*`.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct Selection {
+    include: Vec<String>, // patterns of cells to include, all if empty
+    exclude: Vec<String>, // patterns of cells to exclude
+}
+
+impl Selection {
+    /// Creates a selection
+    ///
+    /// # Arguments
+    ///
+    /// - `include`: Patterns of cells to include, all cells if empty
+    /// - `exclude`: Patterns of cells to exclude, even if included
+    pub fn new(include: Vec<String>, exclude: Vec<String>) -> Selection {
+        Selection { include, exclude }
+    }
+
+    /// Returns whether a cell is selected
+    pub fn matches(&self, name: &str) -> bool {
+        let included =
+            self.include.is_empty() || self.include.iter().any(|pattern| glob_match(pattern, name));
+        let excluded = self.exclude.iter().any(|pattern| glob_match(pattern, name));
+
+        included && !excluded
+    }
+}
+
+/// Matches a name against a glob pattern
+fn glob_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+
+    // Position after the last `*` and the name position it was tried at
+    let mut backtrack: Option<(usize, usize)> = None;
+    let (mut p, mut n) = (0, 0);
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p + 1, n));
+                p += 1;
+            }
+            Some('?') => {
+                p += 1;
+                n += 1;
+            }
+            Some(c) if *c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match backtrack {
+                // Let the last `*` swallow one more character
+                Some((star_p, star_n)) => {
+                    backtrack = Some((star_p, star_n + 1));
+                    p = star_p;
+                    n = star_n + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("BAT0", "BAT0"));
+        assert!(!glob_match("BAT0", "BAT1"));
+        assert!(glob_match("BAT*", "BAT0"));
+        assert!(glob_match("BAT*", "BAT"));
+        assert!(glob_match("B?T1", "BAT1"));
+        assert!(!glob_match("B?T1", "BT1"));
+        assert!(glob_match("*0", "BAT0"));
+        assert!(glob_match("*A*0", "BAT0"));
+        assert!(!glob_match("*A*1", "BAT0"));
+        assert!(glob_match("*", ""));
+        assert!(!glob_match("", "BAT0"));
+    }
+
+    #[test]
+    fn test_selection() {
+        let all = Selection::default();
+        assert!(all.matches("BAT0"));
+        assert!(all.matches("BAT1"));
+
+        let internal = Selection::new(vec![String::from("BAT0")], Vec::new());
+        assert!(internal.matches("BAT0"));
+        assert!(!internal.matches("BAT1"));
+
+        let undocked = Selection::new(
+            vec![String::from("BAT*")],
+            vec![String::from("BAT2"), String::from("BAT3")],
+        );
+        assert!(undocked.matches("BAT1"));
+        assert!(!undocked.matches("BAT2"));
+    }
+}
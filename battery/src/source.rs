@@ -0,0 +1,153 @@
+use std::path::Path;
+
+use log::error;
+
+use crate::parse_file;
+
+/// Kinds of external power sources
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SourceKind {
+    Mains, // AC adapter
+    Usb,   // USB port, e.g. USB-C power delivery
+}
+
+/// External power source information
+#[derive(Debug)]
+pub struct PowerSource {
+    pub(crate) name: String, // name of the supply
+
+    kind: SourceKind,         // kind of supply
+    online: bool,             // supply is connected
+    usb_type: Option<String>, // active USB type, e.g. `PD`
+    voltage_max: Option<u64>, // maximum voltage (mV)
+    current_max: Option<u64>, // maximum current (mA)
+}
+
+impl PowerSource {
+    /// Parses an external power source
+    ///
+    /// # Arguments
+    ///
+    /// - `path`: Path to the supply's directory
+    ///
+    /// # Returns
+    ///
+    /// An `Option`:
+    /// - `Some`: A `PowerSource` if the supply is of `Mains` or `USB` type
+    /// - `None`: If the supply is of any other type or can't be parsed
+    pub fn new(path: &Path) -> Option<PowerSource> {
+        let kind = match parse_file::<String>(&path.join("type")).as_deref() {
+            Some("Mains") => SourceKind::Mains,
+            Some("USB") => SourceKind::Usb,
+            _ => return None,
+        };
+
+        let name = match path.file_name().and_then(|v| v.to_str()) {
+            Some(v) => String::from(v),
+            None => {
+                error!("battery::PowerSource::new: unable to parse name");
+                return None;
+            }
+        };
+
+        let online = match parse_file::<u8>(&path.join("online")) {
+            Some(v) => v != 0,
+            None => {
+                error!(
+                    "battery::PowerSource::new: unable to parse `online` of supply {}",
+                    name
+                );
+                return None;
+            }
+        };
+
+        // The active USB type is enclosed in brackets, e.g. `C [PD] PD_PPS`
+        let usb_type = parse_file::<String>(&path.join("usb_type")).and_then(|v| {
+            v.split_whitespace()
+                .find(|t| t.starts_with('[') && t.ends_with(']'))
+                .map(|t| String::from(t.trim_matches(|c| c == '[' || c == ']')))
+        });
+
+        Some(PowerSource {
+            name,
+            kind,
+            online,
+            usb_type,
+            voltage_max: parse_file::<u64>(&path.join("voltage_max")).map(|v| v / 1000),
+            current_max: parse_file::<u64>(&path.join("current_max")).map(|v| v / 1000),
+        })
+    }
+
+    /// Returns whether the source is connected
+    pub fn online(&self) -> bool {
+        self.online
+    }
+
+    /// Returns the maximum power the source can deliver (W)
+    ///
+    /// The power is unavailable if the source doesn't report its maximum
+    /// voltage and current, which is common for AC adapters.
+    pub fn watts(&self) -> Option<f64> {
+        match (self.voltage_max, self.current_max) {
+            (Some(voltage), Some(current)) if voltage > 0 && current > 0 => {
+                Some((voltage as f64) * (current as f64) / 1_000_000.0)
+            }
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for PowerSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let kind = match (self.kind, &self.usb_type) {
+            (SourceKind::Mains, _) => String::from("mains"),
+            (SourceKind::Usb, Some(usb_type)) => format!("usb ({})", usb_type),
+            (SourceKind::Usb, None) => String::from("usb"),
+        };
+        let state = if self.online { "online" } else { "offline" };
+
+        match self.watts() {
+            Some(watts) => write!(f, "{}, {}, {:.1} W", kind, state, watts),
+            None => write!(f, "{}, {}", kind, state),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_power_source_mains() {
+        let path = PathBuf::from("tests/one-battery-ac/AC");
+        let source = PowerSource::new(&path);
+        assert!(source.is_some());
+
+        let source = source.unwrap();
+        assert_eq!(source.kind, SourceKind::Mains);
+        assert!(source.online());
+        assert_eq!(source.watts(), None);
+        assert_eq!(source.to_string(), "mains, online");
+    }
+
+    #[test]
+    fn test_power_source_usb_pd() {
+        let path = PathBuf::from("tests/one-battery-usb-pd/ucsi-source-psy-USBC000:001");
+        let source = PowerSource::new(&path);
+        assert!(source.is_some());
+
+        let source = source.unwrap();
+        assert_eq!(source.kind, SourceKind::Usb);
+        assert!(source.online());
+        assert_eq!(source.usb_type.as_deref(), Some("PD"));
+        assert_eq!(source.watts(), Some(65.0));
+        assert_eq!(source.to_string(), "usb (PD), online, 65.0 W");
+    }
+
+    #[test]
+    fn test_power_source_battery() {
+        let path = PathBuf::from("tests/one-battery-ac/BAT0");
+        assert!(PowerSource::new(&path).is_none());
+    }
+}
@@ -0,0 +1,99 @@
+use std::path::Path;
+
+use log::{error, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::{Alerts, RateEstimator, View};
+
+/// State persisted between invocations of the `battery` block
+///
+/// i3blocks runs the block once per interval, so anything that has to be
+/// remembered across intervals is kept in a state file.
+#[derive(Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct State {
+    pub rates: RateEstimator, // rate history of the cells
+    pub power: RateEstimator, // power history of the cells
+    pub alerts: Alerts,       // alerts that already fired
+    pub view: View,           // view selected by clicking the block
+}
+
+impl State {
+    /// Loads the state from a state file
+    ///
+    /// A missing or unreadable state file results in an empty state, since
+    /// the state is only used to refine the block's output.
+    ///
+    /// # Arguments
+    ///
+    /// - `path`: Path to the state file
+    pub fn load(path: &Path) -> State {
+        let file = match std::fs::File::open(path) {
+            Ok(v) => v,
+            Err(_) => return State::default(),
+        };
+
+        match serde_yaml::from_reader(file) {
+            Ok(v) => v,
+            Err(e) => {
+                warn!("battery::State::load: {}, discarding state", e);
+                State::default()
+            }
+        }
+    }
+
+    /// Saves the state to a state file
+    ///
+    /// # Arguments
+    ///
+    /// - `path`: Path to the state file
+    pub fn save(&self, path: &Path) {
+        let file = match std::fs::File::create(path) {
+            Ok(v) => v,
+            Err(e) => {
+                error!("battery::State::save: {}", e);
+                return;
+            }
+        };
+
+        if let Err(e) = serde_yaml::to_writer(file, self) {
+            error!("battery::State::save: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ChargeStatus;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_save_load() {
+        let path = std::env::temp_dir().join("battery-test-state-save-load.yaml");
+
+        let mut state = State::default();
+        state
+            .rates
+            .update("BAT0", ChargeStatus::Discharging, 1000, 0.5);
+        state.save(&path);
+
+        let mut state = State::load(&path);
+        let rate = state
+            .rates
+            .update("BAT0", ChargeStatus::Discharging, 2000, 0.5);
+        assert_eq!(rate, 1500);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_load_invalid() {
+        let path = PathBuf::from("tests/smoothing/0/BAT0/status");
+        let mut state = State::load(&path);
+        let rate = state
+            .rates
+            .update("BAT0", ChargeStatus::Discharging, 1000, 0.5);
+        assert_eq!(rate, 1000);
+    }
+}
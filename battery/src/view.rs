@@ -0,0 +1,89 @@
+use serde::{Deserialize, Serialize};
+
+use crate::format::Template;
+
+// Constants
+pub const LEFT_BUTTON: u8 = 1;
+pub const RIGHT_BUTTON: u8 = 3;
+
+/// Views of the `battery` block cycled by left-clicking it
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum View {
+    #[default]
+    Summary, // all cells combined, as configured by the templates
+    Cells,  // each cell separately
+    Power,  // power flowing in or out of the cells
+    Health, // capacity relative to design
+    Eta,    // time of day the cells will be empty or full
+}
+
+impl View {
+    /// Returns the view following this one
+    pub fn next(self) -> View {
+        match self {
+            View::Summary => View::Cells,
+            View::Cells => View::Power,
+            View::Power => View::Health,
+            View::Health => View::Eta,
+            View::Eta => View::Summary,
+        }
+    }
+
+    /// Returns the full and short text templates of the view
+    ///
+    /// The summary view has no templates of its own since it uses the
+    /// configured ones.
+    pub(crate) fn templates(self) -> Option<(Template, Template)> {
+        let (full_text, short_text) = match self {
+            View::Summary => return None,
+            View::Cells => ("{name} {charge}% {status}", "{name} {charge}%"),
+            View::Power => (
+                "BAT {status}[ {watts} W][ ({adapter} W {ac})]",
+                "[{watts} W]",
+            ),
+            View::Health => ("BAT[ health {capacity}% wear {wear}%]", "BAT[ {capacity}%]"),
+            View::Eta => ("BAT {charge}% {status}[ until {eta}]", "BAT[ until {eta}]"),
+        };
+
+        Some((
+            Template::parse(full_text).expect("view templates are valid"),
+            Template::parse(short_text).expect("view templates are valid"),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_next() {
+        let mut view = View::default();
+        let mut views = Vec::new();
+        for _ in 0..5 {
+            views.push(view);
+            view = view.next();
+        }
+
+        assert_eq!(view, View::Summary);
+        assert_eq!(
+            views,
+            vec![
+                View::Summary,
+                View::Cells,
+                View::Power,
+                View::Health,
+                View::Eta
+            ]
+        );
+    }
+
+    #[test]
+    fn test_templates() {
+        assert!(View::Summary.templates().is_none());
+        for view in [View::Cells, View::Power, View::Health, View::Eta].iter() {
+            assert!(view.templates().is_some());
+        }
+    }
+}
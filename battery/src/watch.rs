@@ -0,0 +1,121 @@
+use chrono::NaiveTime;
+
+use crate::{Batteries, RateEstimator, Units};
+
+// Constants
+const HEADER_ROWS: usize = 20;
+
+/// Rolling table of the power flowing in or out of the cells
+///
+/// The table is printed by the `--watch` debug mode to profile workloads.
+/// Powers are signed, positive while charging and negative while
+/// discharging, and the header is repeated every few rows so that the
+/// columns stay readable while scrolling.
+pub struct Watch {
+    estimator: RateEstimator, // power history of the cells
+    alpha: f64,               // weight of the newest power sample
+    cells: Vec<String>,       // cells of the last header
+    rows: usize,              // rows printed since the last header
+}
+
+impl Watch {
+    /// Creates an empty table
+    ///
+    /// # Arguments
+    ///
+    /// - `alpha`: Weight of the newest power sample in the moving average
+    pub fn new(alpha: f64) -> Watch {
+        Watch {
+            estimator: RateEstimator::default(),
+            alpha,
+            cells: Vec::new(),
+            rows: 0,
+        }
+    }
+
+    /// Adds a row of the present power to the table
+    ///
+    /// # Arguments
+    ///
+    /// - `batteries`: The present `Batteries`, whose power is smoothed
+    /// - `time`: Time of day of the row
+    ///
+    /// # Returns
+    ///
+    /// The lines to print, including a header if one is due
+    pub fn update(&mut self, batteries: &mut Batteries, time: NaiveTime) -> String {
+        batteries.smooth_power(&mut self.estimator, self.alpha);
+
+        let mut lines = String::new();
+        let cells: Vec<String> = batteries.cells.iter().map(|v| v.name.clone()).collect();
+        if cells != self.cells || self.rows == HEADER_ROWS {
+            lines.push_str(&format!("{:<8}", "time"));
+            for cell in &cells {
+                lines.push_str(&format!(" {:>9}", cell));
+            }
+            lines.push_str(&format!(" {:>9} {:>9}  status\n", "total", "average"));
+
+            self.cells = cells;
+            self.rows = 0;
+        }
+
+        // Rates of cells without a voltage are currents, so their power is unknown
+        let watts = |power: i64, units: Units| match units {
+            Units::Energy => format!("{:>9.2}", (power as f64) / 1000.0),
+            Units::Charge => format!("{:>9}", "-"),
+        };
+        lines.push_str(&time.format("%H:%M:%S").to_string());
+        for cell in &batteries.cells {
+            lines.push_str(&format!(" {}", watts(cell.signed(cell.power), cell.units)));
+        }
+        lines.push_str(&format!(
+            " {} {}  {}\n",
+            watts(batteries.power, batteries.units),
+            watts(batteries.power_average, batteries.units),
+            batteries.charge_status
+        ));
+        self.rows += 1;
+
+        lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_update() {
+        let path = PathBuf::from("tests/smoothing");
+        let time = NaiveTime::from_hms_opt(17, 42, 0).unwrap();
+        let mut watch = Watch::new(0.5);
+
+        let mut batteries = Batteries::new(&path.join("0"), false).unwrap();
+        assert_eq!(
+            watch.update(&mut batteries, time),
+            "time          BAT0     total   average  status\n\
+             17:42:00    -29.34    -29.34    -29.34  discharging\n"
+        );
+
+        let mut batteries = Batteries::new(&path.join("1"), false).unwrap();
+        assert_eq!(
+            watch.update(&mut batteries, time),
+            "17:42:00    -13.32    -13.32    -21.33  discharging\n"
+        );
+    }
+
+    #[test]
+    fn test_update_header() {
+        let path = PathBuf::from("tests/two-batteries-chr-dis");
+        let time = NaiveTime::from_hms_opt(17, 42, 0).unwrap();
+        let mut watch = Watch::new(0.5);
+
+        let mut headers = 0;
+        for _ in 0..(2 * HEADER_ROWS + 1) {
+            let mut batteries = Batteries::new(&path, false).unwrap();
+            headers += watch.update(&mut batteries, time).matches("time").count();
+        }
+        assert_eq!(headers, 3);
+    }
+}
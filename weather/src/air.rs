@@ -0,0 +1,250 @@
+use std::fmt;
+
+use log::error;
+use serde::{Deserialize, Serialize};
+
+use crate::aqi::{Aqi, AqiScale, Concentrations};
+use crate::openmeteo::OpenMeteoAirQuality;
+use crate::openweathermap::OpenWeatherMap;
+use crate::report::{self, STALE_COLOR};
+use crate::{Config, GeoLocation, HttpClient, I3Block, DEFAULT_ERROR};
+
+// Constants
+const NO_INDEX_COLOR: &str = "#FFFFFF";
+
+/// Current air quality at a location
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct AirQuality {
+    pub concentrations: Concentrations, // pollutant concentrations (μg/m³)
+    pub pollen: Vec<(String, f64)>,     // pollen counts by plant (grains/m³)
+
+    #[serde(skip)]
+    scale: AqiScale, // index the block is rendered with
+
+    #[serde(skip)]
+    pub age: Option<i64>, // age of a stale report (s)
+}
+
+impl AirQuality {
+    /// Creates a report without pollen counts
+    ///
+    /// # Arguments
+    ///
+    /// - `concentrations`: Pollutant concentrations (μg/m³)
+    pub fn new(concentrations: Concentrations) -> AirQuality {
+        AirQuality {
+            concentrations,
+            pollen: Vec::new(),
+            scale: AqiScale::default(),
+            age: None,
+        }
+    }
+
+    /// Sets the index the block is rendered with
+    pub fn set_scale(&mut self, scale: AqiScale) {
+        self.scale = scale;
+    }
+
+    /// Marks the report as stale
+    ///
+    /// # Arguments
+    ///
+    /// - `age`: Time since the report was fetched in seconds
+    pub fn set_age(&mut self, age: i64) {
+        self.age = Some(age);
+    }
+
+    /// Returns the plant with the highest pollen count, if any pollen is
+    /// in the air
+    fn dominant_pollen(&self) -> Option<&(String, f64)> {
+        self.pollen
+            .iter()
+            .filter(|(_, count)| *count >= 1.0)
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+    }
+}
+
+impl fmt::Display for AirQuality {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let c = &self.concentrations;
+        let value = |v: Option<f64>| match v {
+            Some(v) => format!("{:.1}", v),
+            None => "-".into(),
+        };
+
+        write!(
+            f,
+            "PM2.5: {}, PM10: {}, O3: {}, NO2: {}, SO2: {}, CO: {} (μg/m³)",
+            value(c.pm2_5),
+            value(c.pm10),
+            value(c.o3),
+            value(c.no2),
+            value(c.so2),
+            value(c.co)
+        )?;
+        for (plant, count) in &self.pollen {
+            write!(f, ", {} pollen: {:.0}", plant, count)?;
+        }
+        Ok(())
+    }
+}
+
+impl I3Block for AirQuality {
+    fn format_i3(&self) -> String {
+        let label = match self.scale {
+            AqiScale::Us => "AQI",
+            AqiScale::European => "EAQI",
+        };
+
+        let (mut full_text, short_text, mut color) =
+            match Aqi::compute(self.scale, &self.concentrations) {
+                Some(aqi) => (
+                    format!(
+                        "{} {} {}, {}",
+                        label,
+                        aqi.value,
+                        aqi.category(),
+                        aqi.dominant
+                    ),
+                    format!("{} {}", label, aqi.value),
+                    aqi.color().to_string(),
+                ),
+                None => (
+                    format!("{} -", label),
+                    format!("{} -", label),
+                    NO_INDEX_COLOR.to_string(),
+                ),
+            };
+        if let Some((plant, count)) = self.dominant_pollen() {
+            full_text = format!("{}, {} pollen {:.0}", full_text, plant, count);
+        }
+        if let Some(age) = self.age {
+            full_text = format!("{} · {}", full_text, report::format_age(age));
+            color = STALE_COLOR.into();
+        }
+
+        format!("{}\n{}\n{}", full_text, short_text, color)
+    }
+}
+
+/// Source of current air quality reports
+pub trait AirQualityProvider {
+    /// Fetches the current air quality at a location
+    ///
+    /// # Arguments
+    ///
+    /// - `client`: Client the request is sent with
+    /// - `location`: Location to report the air quality of
+    ///
+    /// # Returns
+    ///
+    /// The air quality, or an i3blocks error string
+    fn air_quality(
+        &self,
+        client: &dyn HttpClient,
+        location: &GeoLocation,
+    ) -> Result<AirQuality, String>;
+}
+
+/// Air quality sources selectable in the configuration
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AirQualitySource {
+    OpenWeatherMap, // air pollution API, requires an API key
+    #[default]
+    OpenMeteo, // air quality API with European pollen counts, keyless
+}
+
+/// Air quality settings of the configuration
+#[derive(Default, Deserialize)]
+#[serde(default)]
+pub struct AirQualityConfig {
+    pub source: AirQualitySource, // air quality source
+    pub scale: AqiScale,          // index the block displays
+}
+
+impl AirQualitySource {
+    /// Creates the configured air quality provider
+    ///
+    /// # Arguments
+    ///
+    /// - `config`: Parsed configuration
+    ///
+    /// # Returns
+    ///
+    /// The provider, or an i3blocks error string if its settings are missing
+    pub fn build(config: &Config) -> Result<Box<dyn AirQualityProvider>, String> {
+        let endpoints = &config.endpoints;
+        match config.air_quality.source {
+            AirQualitySource::OpenWeatherMap => match &config.open_weather_api_key {
+                Some(key) => Ok(Box::new(OpenWeatherMap::new(
+                    &endpoints.openweathermap,
+                    key,
+                ))),
+                None => {
+                    error!(
+                        "weather::AirQualitySource::build: `open_weather_api_key` is required \
+                        by the openweathermap air quality source"
+                    );
+                    Err(DEFAULT_ERROR.into())
+                }
+            },
+            AirQualitySource::OpenMeteo => Ok(Box::new(OpenMeteoAirQuality::new(
+                &endpoints.openmeteo_air_quality,
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn air_quality() -> AirQuality {
+        AirQuality::new(Concentrations {
+            pm2_5: Some(12.0),
+            pm10: Some(30.0),
+            o3: Some(120.0),
+            no2: Some(20.0),
+            so2: None,
+            co: Some(300.0),
+        })
+    }
+
+    #[test]
+    fn test_format_us() {
+        let mut report = air_quality();
+        report.pollen = vec![("birch".into(), 0.4), ("grass".into(), 23.6)];
+
+        assert_eq!(
+            report.format_i3(),
+            "AQI 71 moderate, O3, grass pollen 24\nAQI 71\n#FFFF00"
+        );
+    }
+
+    #[test]
+    fn test_format_european() {
+        let mut report = air_quality();
+        report.set_scale(AqiScale::European);
+        report.pollen = vec![("birch".into(), 0.4)];
+
+        assert_eq!(report.format_i3(), "EAQI 3 moderate, O3\nEAQI 3\n#F0E641");
+    }
+
+    #[test]
+    fn test_format_stale() {
+        let mut report = air_quality();
+        report.set_age(2 * 60 * 60);
+
+        assert_eq!(
+            report.format_i3(),
+            "AQI 71 moderate, O3 · 2h ago\nAQI 71\n#808080"
+        );
+    }
+
+    #[test]
+    fn test_format_no_index() {
+        let report = AirQuality::new(Concentrations::default());
+        assert_eq!(report.format_i3(), "AQI -\nAQI -\n#FFFFFF");
+    }
+}
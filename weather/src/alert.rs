@@ -0,0 +1,278 @@
+use std::fmt;
+
+use chrono::{DateTime, Utc};
+use log::error;
+use serde::{Deserialize, Serialize};
+
+use crate::meteoalarm::MeteoAlarm;
+use crate::nws::Nws;
+use crate::openweathermap::OpenWeatherMap;
+use crate::{Config, GeoLocation, HttpClient, DEFAULT_ERROR};
+
+// Constants
+const DEFAULT_LABEL: &str = "⚠";
+
+/// Government weather alert, e.g. a storm or heat warning
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Alert {
+    pub id: String,                   // identifier given by the issuer
+    pub event: String,                // short name, e.g. `Wind Advisory`
+    pub sender: Option<String>,       // issuing agency
+    pub description: String,          // full text
+    pub start: Option<DateTime<Utc>>, // time the alert takes effect
+    pub end: Option<DateTime<Utc>>,   // time the alert expires, `None` until cancelled
+}
+
+impl Alert {
+    /// Returns whether the alert hasn't expired yet
+    pub fn is_active(&self, now: DateTime<Utc>) -> bool {
+        self.end.is_none_or(|v| v > now)
+    }
+
+    /// Returns whether two alerts are the same warning
+    ///
+    /// Issuers repeat a warning for every area and language it covers, and
+    /// update it under a new identifier.
+    fn duplicates(&self, other: &Alert) -> bool {
+        self.id == other.id
+            || (self.event.eq_ignore_ascii_case(&other.event)
+                && self.start == other.start
+                && self.end == other.end)
+    }
+
+    /// Drops expired and repeated alerts
+    ///
+    /// # Arguments
+    ///
+    /// - `alerts`: Alerts in the order they were issued
+    /// - `now`: Present time
+    ///
+    /// # Returns
+    ///
+    /// The active alerts, sorted by start time
+    pub fn prune(alerts: Vec<Alert>, now: DateTime<Utc>) -> Vec<Alert> {
+        let mut pruned: Vec<Alert> = Vec::new();
+        for alert in alerts {
+            if alert.is_active(now) && !pruned.iter().any(|v| v.duplicates(&alert)) {
+                pruned.push(alert);
+            }
+        }
+        pruned.sort_by_key(|v| v.start);
+
+        pruned
+    }
+
+    /// Formats the label of the selected alert
+    ///
+    /// # Arguments
+    ///
+    /// - `alerts`: Active alerts
+    /// - `index`: Index of the selected alert, wrapped around the alerts
+    /// - `label`: Label marking an alert
+    ///
+    /// # Returns
+    ///
+    /// The full and short texts appended to the block, if any alert is active
+    pub fn label(alerts: &[Alert], index: usize, label: &str) -> Option<(String, String)> {
+        let alert = alerts.get(index % alerts.len().max(1))?;
+        let full_text = match alerts.len() {
+            1 => format!("{} {}", label, alert.event),
+            n => format!("{} {} ({}/{})", label, alert.event, index % n + 1, n),
+        };
+
+        Some((full_text, label.into()))
+    }
+}
+
+impl fmt::Display for Alert {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let time = |v: Option<DateTime<Utc>>| match v {
+            Some(v) => v.to_rfc3339(),
+            None => "-".into(),
+        };
+        let description: Vec<&str> = self.description.split_whitespace().collect();
+
+        write!(
+            f,
+            "{} ({} to {})",
+            self.event,
+            time(self.start),
+            time(self.end)
+        )?;
+        if let Some(sender) = &self.sender {
+            write!(f, " by {}", sender)?;
+        }
+        write!(f, ": {}", description.join(" "))
+    }
+}
+
+/// Source of government weather alerts
+pub trait AlertProvider {
+    /// Fetches the alerts issued for a location
+    ///
+    /// # Arguments
+    ///
+    /// - `client`: Client the request is sent with
+    /// - `location`: Location the alerts are issued for
+    ///
+    /// # Returns
+    ///
+    /// The alerts, possibly expired or repeated, or an i3blocks error string
+    fn alerts(&self, client: &dyn HttpClient, location: &GeoLocation)
+        -> Result<Vec<Alert>, String>;
+}
+
+/// Alert sources selectable in the configuration
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AlertSource {
+    OpenWeatherMap, // One Call API 3.0, requires an API key with a subscription
+    Nws,            // US National Weather Service, keyless
+    MeteoAlarm,     // European MeteoAlarm feeds, keyless
+}
+
+/// Alert settings of the configuration
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct AlertConfig {
+    pub source: Option<AlertSource>, // alert source, alerts are disabled without one
+    pub country: Option<String>,     // MeteoAlarm feed, e.g. `germany`
+    pub region: Option<String>,      // MeteoAlarm area description, e.g. `Berlin`
+    pub label: String,               // label marking an alert
+}
+
+impl Default for AlertConfig {
+    fn default() -> AlertConfig {
+        AlertConfig {
+            source: None,
+            country: None,
+            region: None,
+            label: DEFAULT_LABEL.into(),
+        }
+    }
+}
+
+impl AlertSource {
+    /// Creates the configured alert provider
+    ///
+    /// # Arguments
+    ///
+    /// - `config`: Parsed configuration
+    ///
+    /// # Returns
+    ///
+    /// The provider, `None` if alerts are disabled, or an i3blocks error
+    /// string if its settings are missing
+    pub fn build(config: &Config) -> Result<Option<Box<dyn AlertProvider>>, String> {
+        let endpoints = &config.endpoints;
+        let alerts = &config.alerts;
+        let missing = |setting: &str, source: &str| {
+            error!(
+                "weather::AlertSource::build: `{}` is required by the {} alert source",
+                setting, source
+            );
+            Err(DEFAULT_ERROR.to_string())
+        };
+
+        let provider: Box<dyn AlertProvider> = match alerts.source {
+            None => return Ok(None),
+            Some(AlertSource::OpenWeatherMap) => match &config.open_weather_api_key {
+                Some(key) => Box::new(OpenWeatherMap::new(&endpoints.openweathermap, key)),
+                None => return missing("open_weather_api_key", "openweathermap"),
+            },
+            Some(AlertSource::Nws) => Box::new(Nws::new(&endpoints.nws)),
+            Some(AlertSource::MeteoAlarm) => match (&alerts.country, &alerts.region) {
+                (Some(country), Some(region)) => {
+                    Box::new(MeteoAlarm::new(&endpoints.meteoalarm, country, region))
+                }
+                (None, _) => return missing("alerts.country", "meteoalarm"),
+                (_, None) => return missing("alerts.region", "meteoalarm"),
+            },
+        };
+
+        Ok(Some(provider))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn alert(id: &str, event: &str, start: &str, end: Option<&str>) -> Alert {
+        let time = |v: &str| DateTime::parse_from_rfc3339(v).unwrap().with_timezone(&Utc);
+        Alert {
+            id: id.into(),
+            event: event.into(),
+            sender: None,
+            description: "Gusts up to 90 km/h.\nSecure loose objects.".into(),
+            start: Some(time(start)),
+            end: end.map(time),
+        }
+    }
+
+    #[test]
+    fn test_prune() {
+        let now = "2026-10-18T12:00:00Z".parse().unwrap();
+        let alerts = vec![
+            alert(
+                "a",
+                "Wind warning",
+                "2026-10-18T10:00:00Z",
+                Some("2026-10-18T20:00:00Z"),
+            ),
+            alert(
+                "b",
+                "Heat warning",
+                "2026-10-18T06:00:00Z",
+                Some("2026-10-18T11:59:00Z"),
+            ),
+            alert(
+                "c",
+                "wind warning",
+                "2026-10-18T10:00:00Z",
+                Some("2026-10-18T20:00:00Z"),
+            ),
+            alert(
+                "a",
+                "Wind warning",
+                "2026-10-18T10:00:00Z",
+                Some("2026-10-19T20:00:00Z"),
+            ),
+            alert("d", "Flood watch", "2026-10-18T08:00:00Z", None),
+        ];
+
+        let ids: Vec<String> = Alert::prune(alerts, now)
+            .into_iter()
+            .map(|v| v.id)
+            .collect();
+        assert_eq!(ids, vec!["d", "a"]);
+    }
+
+    #[test]
+    fn test_label() {
+        let alerts = vec![
+            alert("a", "Wind warning", "2026-10-18T10:00:00Z", None),
+            alert("b", "Flood watch", "2026-10-18T11:00:00Z", None),
+        ];
+
+        assert_eq!(Alert::label(&[], 0, "!"), None);
+        assert_eq!(
+            Alert::label(&alerts[..1], 3, "!"),
+            Some(("! Wind warning".into(), "!".into()))
+        );
+        assert_eq!(
+            Alert::label(&alerts, 3, "!"),
+            Some(("! Flood watch (2/2)".into(), "!".into()))
+        );
+    }
+
+    #[test]
+    fn test_display() {
+        let alert = alert("a", "Wind warning", "2026-10-18T10:00:00Z", None);
+        assert_eq!(
+            alert.to_string(),
+            "Wind warning (2026-10-18T10:00:00+00:00 to -): Gusts up to 90 km/h. Secure loose \
+             objects."
+        );
+    }
+}
@@ -0,0 +1,402 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+// Molar volume of an ideal gas at 25 °C and 1 atm (l/mol), converting μg/m³
+// to ppb as `ppb = μg/m³ * MOLAR_VOLUME / molar mass`
+const MOLAR_VOLUME: f64 = 24.45;
+
+// US AQI ranges of the breakpoint rows
+const US_INDEX: [(f64, f64); 6] = [
+    (0.0, 50.0),
+    (51.0, 100.0),
+    (101.0, 150.0),
+    (151.0, 200.0),
+    (201.0, 300.0),
+    (301.0, 500.0),
+];
+
+// US EPA concentration breakpoints of each index range, see
+// https://document.airnow.gov/technical-assistance-document-for-the-reporting-of-daily-air-quailty.pdf
+const US_PM2_5: [(f64, f64); 6] = [
+    (0.0, 9.0),
+    (9.1, 35.4),
+    (35.5, 55.4),
+    (55.5, 125.4),
+    (125.5, 225.4),
+    (225.5, 325.4),
+]; // μg/m³, truncated to 0.1
+const US_PM10: [(f64, f64); 6] = [
+    (0.0, 54.0),
+    (55.0, 154.0),
+    (155.0, 254.0),
+    (255.0, 354.0),
+    (355.0, 424.0),
+    (425.0, 604.0),
+]; // μg/m³, truncated to 1
+const US_O3: [(f64, f64); 6] = [
+    (0.0, 54.0),
+    (55.0, 70.0),
+    (71.0, 85.0),
+    (86.0, 105.0),
+    (106.0, 200.0),
+    (405.0, 604.0),
+]; // ppb, truncated to 1, 8 hour rows and the 1 hour row above 300
+const US_NO2: [(f64, f64); 6] = [
+    (0.0, 53.0),
+    (54.0, 100.0),
+    (101.0, 360.0),
+    (361.0, 649.0),
+    (650.0, 1249.0),
+    (1250.0, 2049.0),
+]; // ppb, truncated to 1
+const US_SO2: [(f64, f64); 6] = [
+    (0.0, 35.0),
+    (36.0, 75.0),
+    (76.0, 185.0),
+    (186.0, 304.0),
+    (305.0, 604.0),
+    (605.0, 1004.0),
+]; // ppb, truncated to 1
+const US_CO: [(f64, f64); 6] = [
+    (0.0, 4.4),
+    (4.5, 9.4),
+    (9.5, 12.4),
+    (12.5, 15.4),
+    (15.5, 30.4),
+    (30.5, 50.4),
+]; // ppm, truncated to 0.1
+
+// European AQI upper bounds of the good to very poor bands (μg/m³), above
+// which a concentration is extremely poor, see
+// https://airindex.eea.europa.eu/AQI/index.html
+const EU_PM2_5: [f64; 5] = [10.0, 20.0, 25.0, 50.0, 75.0];
+const EU_PM10: [f64; 5] = [20.0, 40.0, 50.0, 100.0, 150.0];
+const EU_O3: [f64; 5] = [50.0, 100.0, 130.0, 240.0, 380.0];
+const EU_NO2: [f64; 5] = [40.0, 90.0, 120.0, 230.0, 340.0];
+const EU_SO2: [f64; 5] = [100.0, 200.0, 350.0, 500.0, 750.0];
+
+// Categories and colors of the levels of each scale
+const US_CATEGORIES: [(&str, &str); 6] = [
+    ("good", "#00E400"),
+    ("moderate", "#FFFF00"),
+    ("unhealthy for sensitive groups", "#FF7E00"),
+    ("unhealthy", "#FF0000"),
+    ("very unhealthy", "#8F3F97"),
+    ("hazardous", "#7E0023"),
+];
+const EU_CATEGORIES: [(&str, &str); 6] = [
+    ("good", "#50F0E6"),
+    ("fair", "#50CCAA"),
+    ("moderate", "#F0E641"),
+    ("poor", "#FF5050"),
+    ("very poor", "#960032"),
+    ("extremely poor", "#7D2181"),
+];
+
+/// Pollutants the air quality indices are computed from
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Pollutant {
+    Pm2_5, // fine particulate matter
+    Pm10,  // coarse particulate matter
+    O3,    // ozone
+    No2,   // nitrogen dioxide
+    So2,   // sulphur dioxide
+    Co,    // carbon monoxide
+}
+
+impl fmt::Display for Pollutant {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Pollutant::Pm2_5 => "PM2.5",
+            Pollutant::Pm10 => "PM10",
+            Pollutant::O3 => "O3",
+            Pollutant::No2 => "NO2",
+            Pollutant::So2 => "SO2",
+            Pollutant::Co => "CO",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl Pollutant {
+    /// Returns the molar mass of a gas (g/mol)
+    fn molar_mass(self) -> Option<f64> {
+        match self {
+            Pollutant::O3 => Some(48.00),
+            Pollutant::No2 => Some(46.01),
+            Pollutant::So2 => Some(64.07),
+            Pollutant::Co => Some(28.01),
+            Pollutant::Pm2_5 | Pollutant::Pm10 => None,
+        }
+    }
+}
+
+/// Pollutant concentrations near the ground, all in μg/m³
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct Concentrations {
+    pub pm2_5: Option<f64>,
+    pub pm10: Option<f64>,
+    pub o3: Option<f64>,
+    pub no2: Option<f64>,
+    pub so2: Option<f64>,
+    pub co: Option<f64>,
+}
+
+impl Concentrations {
+    /// Returns the reported concentrations in order of precedence
+    fn all(&self) -> Vec<(Pollutant, f64)> {
+        [
+            (Pollutant::Pm2_5, self.pm2_5),
+            (Pollutant::Pm10, self.pm10),
+            (Pollutant::O3, self.o3),
+            (Pollutant::No2, self.no2),
+            (Pollutant::So2, self.so2),
+            (Pollutant::Co, self.co),
+        ]
+        .iter()
+        .filter_map(|(pollutant, v)| v.filter(|v| *v >= 0.0).map(|v| (*pollutant, v)))
+        .collect()
+    }
+}
+
+/// Air quality indices
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum AqiScale {
+    #[default]
+    Us, // US EPA AQI from 0 to 500
+    European, // European AQI levels from 1 (good) to 6 (extremely poor)
+}
+
+/// Air quality index of a set of concentrations
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Aqi {
+    pub scale: AqiScale,     // index the value is on
+    pub value: u32,          // US AQI, or European level
+    pub level: usize,        // category from 0 (best) to 5 (worst)
+    pub dominant: Pollutant, // pollutant with the worst sub-index
+}
+
+impl Aqi {
+    /// Computes the air quality index of concentrations
+    ///
+    /// The index is the worst of the sub-indices of the pollutants. The
+    /// indices are defined on 1 to 24 hour averages, for which current
+    /// concentrations stand in.
+    ///
+    /// # Arguments
+    ///
+    /// - `scale`: Index to compute
+    /// - `concentrations`: Pollutant concentrations (μg/m³)
+    ///
+    /// # Returns
+    ///
+    /// The index, or `None` if no pollutant of the index was reported
+    pub fn compute(scale: AqiScale, concentrations: &Concentrations) -> Option<Aqi> {
+        let mut worst: Option<Aqi> = None;
+        for (pollutant, concentration) in concentrations.all() {
+            let sub_index = match scale {
+                AqiScale::Us => us_index(pollutant, concentration),
+                AqiScale::European => european_level(pollutant, concentration),
+            };
+            let (value, level) = match sub_index {
+                Some(v) => v,
+                None => continue,
+            };
+
+            if worst.is_none_or(|v| value > v.value) {
+                worst = Some(Aqi {
+                    scale,
+                    value,
+                    level,
+                    dominant: pollutant,
+                });
+            }
+        }
+
+        worst
+    }
+
+    /// Returns the category of the index, e.g. `moderate`
+    pub fn category(&self) -> &'static str {
+        self.categories()[self.level].0
+    }
+
+    /// Returns the color of the category as `#RRGGBB`
+    pub fn color(&self) -> &'static str {
+        self.categories()[self.level].1
+    }
+
+    fn categories(&self) -> &'static [(&'static str, &'static str); 6] {
+        match self.scale {
+            AqiScale::Us => &US_CATEGORIES,
+            AqiScale::European => &EU_CATEGORIES,
+        }
+    }
+}
+
+/// Computes the US AQI of a pollutant
+///
+/// Concentrations between two rows count as the upper end of the lower row,
+/// and concentrations beyond the table as 500.
+///
+/// # Arguments
+///
+/// - `pollutant`: Pollutant measured
+/// - `concentration`: Concentration (μg/m³)
+///
+/// # Returns
+///
+/// The sub-index and its category level
+fn us_index(pollutant: Pollutant, concentration: f64) -> Option<(u32, usize)> {
+    let ppb = |v: f64| v * MOLAR_VOLUME / pollutant.molar_mass().unwrap_or(1.0);
+    let (breakpoints, concentration) = match pollutant {
+        Pollutant::Pm2_5 => (&US_PM2_5, truncate(concentration, 1)),
+        Pollutant::Pm10 => (&US_PM10, truncate(concentration, 0)),
+        Pollutant::O3 => (&US_O3, truncate(ppb(concentration), 0)),
+        Pollutant::No2 => (&US_NO2, truncate(ppb(concentration), 0)),
+        Pollutant::So2 => (&US_SO2, truncate(ppb(concentration), 0)),
+        Pollutant::Co => (&US_CO, truncate(ppb(concentration) / 1000.0, 1)),
+    };
+
+    let row = breakpoints
+        .iter()
+        .rposition(|(low, _)| concentration >= *low)?;
+    let (low, high) = breakpoints[row];
+    let (index_low, index_high) = US_INDEX[row];
+    let index = if concentration > high {
+        index_high
+    } else {
+        index_low + (index_high - index_low) / (high - low) * (concentration - low)
+    };
+
+    Some((index.round() as u32, row))
+}
+
+/// Computes the European AQI level of a pollutant
+///
+/// # Arguments
+///
+/// - `pollutant`: Pollutant measured
+/// - `concentration`: Concentration (μg/m³)
+///
+/// # Returns
+///
+/// The level from 1 to 6 and its category level, `None` for pollutants
+/// outside the index
+fn european_level(pollutant: Pollutant, concentration: f64) -> Option<(u32, usize)> {
+    let bounds = match pollutant {
+        Pollutant::Pm2_5 => &EU_PM2_5,
+        Pollutant::Pm10 => &EU_PM10,
+        Pollutant::O3 => &EU_O3,
+        Pollutant::No2 => &EU_NO2,
+        Pollutant::So2 => &EU_SO2,
+        Pollutant::Co => return None,
+    };
+
+    let level = bounds
+        .iter()
+        .position(|v| concentration <= *v)
+        .unwrap_or(bounds.len());
+    Some((level as u32 + 1, level))
+}
+
+/// Truncates a value to a number of decimals, as the indices prescribe
+fn truncate(value: f64, decimals: i32) -> f64 {
+    let scale = 10f64.powi(decimals);
+    // Offset binary representation errors, e.g. 35.4 * 10 = 353.99999...
+    ((value * scale) + 1e-9).floor() / scale
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn only(pollutant: Pollutant, v: f64) -> Concentrations {
+        let mut c = Concentrations::default();
+        match pollutant {
+            Pollutant::Pm2_5 => c.pm2_5 = Some(v),
+            Pollutant::Pm10 => c.pm10 = Some(v),
+            Pollutant::O3 => c.o3 = Some(v),
+            Pollutant::No2 => c.no2 = Some(v),
+            Pollutant::So2 => c.so2 = Some(v),
+            Pollutant::Co => c.co = Some(v),
+        }
+        c
+    }
+
+    fn us(pollutant: Pollutant, v: f64) -> u32 {
+        Aqi::compute(AqiScale::Us, &only(pollutant, v))
+            .unwrap()
+            .value
+    }
+
+    #[test]
+    fn test_us_breakpoints() {
+        assert_eq!(us(Pollutant::Pm2_5, 0.0), 0);
+        assert_eq!(us(Pollutant::Pm2_5, 9.0), 50);
+        assert_eq!(us(Pollutant::Pm2_5, 9.1), 51);
+        assert_eq!(us(Pollutant::Pm2_5, 35.4), 100);
+        assert_eq!(us(Pollutant::Pm2_5, 35.49), 100);
+        assert_eq!(us(Pollutant::Pm2_5, 35.5), 101);
+        assert_eq!(us(Pollutant::Pm2_5, 500.0), 500);
+        assert_eq!(us(Pollutant::Pm10, 154.9), 100);
+        assert_eq!(us(Pollutant::Pm10, 200.0), 123);
+    }
+
+    #[test]
+    fn test_us_gases() {
+        // 100 μg/m³ of ozone is 50.9 ppb
+        assert_eq!(us(Pollutant::O3, 100.0), 46);
+        // Between the 8 hour and 1 hour rows
+        assert_eq!(us(Pollutant::O3, 500.0), 300);
+        // 100 μg/m³ of NO2 is 53.1 ppb
+        assert_eq!(us(Pollutant::No2, 100.0), 50);
+        // 10000 μg/m³ of CO is 8.7 ppm
+        assert_eq!(us(Pollutant::Co, 10_000.0), 93);
+        assert_eq!(us(Pollutant::So2, 0.64), 0);
+    }
+
+    #[test]
+    fn test_dominant() {
+        let concentrations = Concentrations {
+            pm2_5: Some(12.0),
+            pm10: Some(30.0),
+            o3: Some(120.0),
+            no2: Some(20.0),
+            so2: None,
+            co: Some(300.0),
+        };
+
+        let aqi = Aqi::compute(AqiScale::Us, &concentrations).unwrap();
+        assert_eq!(aqi.value, 71);
+        assert_eq!(aqi.dominant, Pollutant::O3);
+        assert_eq!(aqi.category(), "moderate");
+        assert_eq!(aqi.color(), "#FFFF00");
+
+        let aqi = Aqi::compute(AqiScale::European, &concentrations).unwrap();
+        assert_eq!(aqi.value, 3);
+        assert_eq!(aqi.dominant, Pollutant::O3);
+        assert_eq!(aqi.category(), "moderate");
+    }
+
+    #[test]
+    fn test_european_levels() {
+        let level =
+            |pollutant, v| Aqi::compute(AqiScale::European, &only(pollutant, v)).map(|v| v.value);
+        assert_eq!(level(Pollutant::Pm2_5, 10.0), Some(1));
+        assert_eq!(level(Pollutant::Pm2_5, 10.1), Some(2));
+        assert_eq!(level(Pollutant::Pm10, 151.0), Some(6));
+        assert_eq!(level(Pollutant::So2, 350.0), Some(3));
+        assert_eq!(level(Pollutant::Co, 10_000.0), None);
+    }
+
+    #[test]
+    fn test_empty() {
+        assert_eq!(Aqi::compute(AqiScale::Us, &Concentrations::default()), None);
+        // Negative concentrations are missing values of some models
+        let negative = only(Pollutant::Pm2_5, -1.0);
+        assert_eq!(Aqi::compute(AqiScale::European, &negative), None);
+    }
+}
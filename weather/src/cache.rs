@@ -0,0 +1,522 @@
+use std::path::{Path, PathBuf};
+
+use log::{error, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    AirQuality, AirQualitySource, Alert, AlertSource, GeoLocation, IPv4, Provider, View,
+    WeatherReport,
+};
+
+/// Cache settings of the configuration
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct CacheConfig {
+    pub path: Option<PathBuf>, // cache file, caching is disabled without one
+    pub ip_ttl: i64,           // time the external IP is reused (s)
+    pub report_ttl: i64,       // time a report is served without fetching (s)
+    pub alerts_ttl: i64,       // time alerts are served without fetching (s)
+    pub air_quality_ttl: i64,  // time air quality is served without fetching (s)
+    pub stale_ttl: i64,        // time a stale report is shown when fetching fails (s)
+}
+
+impl Default for CacheConfig {
+    fn default() -> CacheConfig {
+        CacheConfig {
+            path: None,
+            ip_ttl: 15 * 60,
+            report_ttl: 15 * 60,
+            alerts_ttl: 15 * 60,
+            air_quality_ttl: 30 * 60,
+            stale_ttl: 24 * 60 * 60,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+struct Entry<T> {
+    value: T,  // cached value
+    time: i64, // time the value was fetched (Unix time)
+}
+
+impl<T> Entry<T> {
+    fn fresh(&self, now: i64, ttl: i64) -> bool {
+        now - self.time < ttl
+    }
+}
+
+/// Location and source a cached value was fetched for
+///
+/// Values are only reused for the origin they were fetched for, so that
+/// moving to another place or switching providers fetches them again.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Origin<S> {
+    lat: f64,  // latitude of the location (°)
+    lon: f64,  // longitude of the location (°)
+    source: S, // provider the value was fetched from
+}
+
+impl<S> Origin<S> {
+    /// Creates the origin of values fetched for a location
+    ///
+    /// # Arguments
+    ///
+    /// - `location`: Resolved location the values are fetched for
+    /// - `source`: Provider the values are fetched from
+    pub fn new(location: &GeoLocation, source: S) -> Origin<S> {
+        Origin {
+            lat: location.lat,
+            lon: location.lon,
+            source,
+        }
+    }
+}
+
+/// Origin of a report, keyed on its provider and whether a forecast was
+/// requested
+pub type ReportOrigin = Origin<(Provider, bool)>;
+
+/// Origin of alerts, keyed on their source and the MeteoAlarm country and
+/// region
+pub type AlertOrigin = Origin<(AlertSource, Option<String>, Option<String>)>;
+
+/// Last values fetched by the block and the selected view
+///
+/// The cache lets invocations skip requests whose answers are still fresh
+/// and fall back to the last report when fetching fails, e.g. offline or
+/// behind a captive portal. Times are Unix times in seconds.
+#[derive(Default, Deserialize, Serialize)]
+pub struct Cache {
+    #[serde(default)]
+    pub view: View,
+
+    #[serde(default)]
+    pub alert: usize, // index of the displayed alert
+
+    ip: Option<Entry<IPv4>>,
+    location: Option<Entry<(IPv4, GeoLocation)>>,
+    geocoded: Option<Entry<(String, GeoLocation)>>,
+    report: Option<Entry<(ReportOrigin, WeatherReport)>>,
+    alerts: Option<Entry<(AlertOrigin, Vec<Alert>)>>,
+    air_quality: Option<Entry<(Origin<AirQualitySource>, AirQuality)>>,
+}
+
+impl Cache {
+    /// Loads a cache file
+    ///
+    /// # Arguments
+    ///
+    /// - `path`: Path of the cache file
+    ///
+    /// # Returns
+    ///
+    /// The cache, or an empty cache if the file is missing or unreadable
+    pub fn load(path: &Path) -> Cache {
+        let contents = match std::fs::read_to_string(path) {
+            Ok(v) => v,
+            Err(_) => return Cache::default(),
+        };
+
+        match serde_json::from_str(&contents) {
+            Ok(v) => v,
+            Err(e) => {
+                warn!("weather::Cache::load: ignoring {}, {}", path.display(), e);
+                Cache::default()
+            }
+        }
+    }
+
+    /// Saves the cache to a file
+    ///
+    /// # Arguments
+    ///
+    /// - `path`: Path of the cache file
+    pub fn save(&self, path: &Path) {
+        let contents = match serde_json::to_string(self) {
+            Ok(v) => v,
+            Err(e) => {
+                error!("weather::Cache::save: {}", e);
+                return;
+            }
+        };
+
+        if let Err(e) = std::fs::write(path, contents) {
+            error!("weather::Cache::save: {}, {}", path.display(), e);
+        }
+    }
+
+    /// Returns the external IP if it's fresh
+    pub fn ip(&self, now: i64, ttl: i64) -> Option<IPv4> {
+        self.ip
+            .as_ref()
+            .filter(|v| v.fresh(now, ttl))
+            .map(|v| v.value.clone())
+    }
+
+    pub fn set_ip(&mut self, ip: &IPv4, now: i64) {
+        self.ip = Some(Entry {
+            value: ip.clone(),
+            time: now,
+        });
+    }
+
+    /// Returns the location of an IP
+    ///
+    /// Locations don't expire, they're only fetched again once the external
+    /// IP changes.
+    pub fn location(&self, ip: &IPv4) -> Option<GeoLocation> {
+        self.location
+            .as_ref()
+            .filter(|v| v.value.0 == *ip)
+            .map(|v| v.value.1.clone())
+    }
+
+    /// Returns the last location of any IP regardless of its age
+    ///
+    /// The last location stands in while the external IP can't be fetched,
+    /// e.g. offline.
+    pub fn last_location(&self) -> Option<GeoLocation> {
+        self.location.as_ref().map(|v| v.value.1.clone())
+    }
+
+    pub fn set_location(&mut self, ip: &IPv4, location: &GeoLocation, now: i64) {
+        self.location = Some(Entry {
+            value: (ip.clone(), location.clone()),
+            time: now,
+        });
+    }
+
+    /// Returns the coordinates of a geocoded place
+    ///
+    /// Geocoded places don't expire, they're only resolved again once the
+    /// configured place changes.
+    pub fn geocoded(&self, place: &str) -> Option<GeoLocation> {
+        self.geocoded
+            .as_ref()
+            .filter(|v| v.value.0 == place)
+            .map(|v| v.value.1.clone())
+    }
+
+    pub fn set_geocoded(&mut self, place: &str, location: &GeoLocation, now: i64) {
+        self.geocoded = Some(Entry {
+            value: (place.into(), location.clone()),
+            time: now,
+        });
+    }
+
+    /// Returns the last report of an origin if it's fresh
+    pub fn report(&self, origin: &ReportOrigin, now: i64, ttl: i64) -> Option<WeatherReport> {
+        self.report
+            .as_ref()
+            .filter(|v| v.value.0 == *origin && v.fresh(now, ttl))
+            .map(|v| v.value.1.clone())
+    }
+
+    /// Returns the last report of an origin unless it's too old
+    ///
+    /// # Arguments
+    ///
+    /// - `origin`: Location and provider the report was fetched for
+    /// - `now`: Present Unix time
+    /// - `ttl`: Maximum age of the report (s)
+    ///
+    /// # Returns
+    ///
+    /// The report marked as stale with its age, if one was cached
+    pub fn stale_report(&self, origin: &ReportOrigin, now: i64, ttl: i64) -> Option<WeatherReport> {
+        self.report
+            .as_ref()
+            .filter(|v| v.value.0 == *origin && v.fresh(now, ttl))
+            .map(|v| {
+                let mut report = v.value.1.clone();
+                report.set_age(now - v.time);
+                report
+            })
+    }
+
+    pub fn set_report(&mut self, origin: &ReportOrigin, report: &WeatherReport, now: i64) {
+        self.report = Some(Entry {
+            value: (origin.clone(), report.clone()),
+            time: now,
+        });
+    }
+
+    /// Returns the last alerts of an origin if they're fresh
+    pub fn alerts(&self, origin: &AlertOrigin, now: i64, ttl: i64) -> Option<Vec<Alert>> {
+        self.alerts
+            .as_ref()
+            .filter(|v| v.value.0 == *origin && v.fresh(now, ttl))
+            .map(|v| v.value.1.clone())
+    }
+
+    /// Returns the last alerts of an origin regardless of their age
+    ///
+    /// Alerts carry their own end time, so they're still shown when fetching
+    /// fails until they expire.
+    pub fn stale_alerts(&self, origin: &AlertOrigin) -> Vec<Alert> {
+        self.alerts
+            .as_ref()
+            .filter(|v| v.value.0 == *origin)
+            .map(|v| v.value.1.clone())
+            .unwrap_or_default()
+    }
+
+    pub fn set_alerts(&mut self, origin: &AlertOrigin, alerts: &[Alert], now: i64) {
+        self.alerts = Some(Entry {
+            value: (origin.clone(), alerts.to_vec()),
+            time: now,
+        });
+    }
+
+    /// Returns the last air quality of an origin if it's fresh
+    pub fn air_quality(
+        &self,
+        origin: &Origin<AirQualitySource>,
+        now: i64,
+        ttl: i64,
+    ) -> Option<AirQuality> {
+        self.air_quality
+            .as_ref()
+            .filter(|v| v.value.0 == *origin && v.fresh(now, ttl))
+            .map(|v| v.value.1.clone())
+    }
+
+    /// Returns the last air quality of an origin unless it's too old
+    ///
+    /// # Arguments
+    ///
+    /// - `origin`: Location and source the air quality was fetched for
+    /// - `now`: Present Unix time
+    /// - `ttl`: Maximum age of the air quality (s)
+    ///
+    /// # Returns
+    ///
+    /// The air quality marked as stale with its age, if one was cached
+    pub fn stale_air_quality(
+        &self,
+        origin: &Origin<AirQualitySource>,
+        now: i64,
+        ttl: i64,
+    ) -> Option<AirQuality> {
+        self.air_quality
+            .as_ref()
+            .filter(|v| v.value.0 == *origin && v.fresh(now, ttl))
+            .map(|v| {
+                let mut air_quality = v.value.1.clone();
+                air_quality.set_age(now - v.time);
+                air_quality
+            })
+    }
+
+    pub fn set_air_quality(
+        &mut self,
+        origin: &Origin<AirQualitySource>,
+        air_quality: &AirQuality,
+        now: i64,
+    ) {
+        self.air_quality = Some(Entry {
+            value: (origin.clone(), air_quality.clone()),
+            time: now,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Concentrations, Condition};
+
+    fn location(lat: f64) -> GeoLocation {
+        serde_json::from_str(&format!(
+            "{{\"status\": \"success\", \"lat\": {}, \"lon\": 0.0}}",
+            lat
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_ip() {
+        let mut cache = Cache::default();
+        assert_eq!(cache.ip(0, 60), None);
+
+        let ip = IPv4("203.0.113.7".into());
+        cache.set_ip(&ip, 100);
+        assert_eq!(cache.ip(159, 60), Some(ip));
+        assert_eq!(cache.ip(160, 60), None);
+    }
+
+    #[test]
+    fn test_location_ip_change() {
+        let mut cache = Cache::default();
+        let ip = IPv4("203.0.113.7".into());
+        cache.set_location(&ip, &location(37.0), 100);
+
+        assert!(cache.location(&ip).is_some());
+
+        let other = IPv4("198.51.100.1".into());
+        assert!(cache.location(&other).is_none());
+    }
+
+    #[test]
+    fn test_geocoded() {
+        let mut cache = Cache::default();
+        cache.set_geocoded("Paris, FR", &location(48.85), 100);
+
+        assert_eq!(cache.geocoded("Paris, FR").unwrap().lat, 48.85);
+        assert!(cache.geocoded("Paris").is_none());
+    }
+
+    #[test]
+    fn test_last_location() {
+        let mut cache = Cache::default();
+        assert!(cache.last_location().is_none());
+
+        let ip = IPv4("203.0.113.7".into());
+        cache.set_location(&ip, &location(37.0), 100);
+        assert_eq!(cache.last_location().unwrap().lat, 37.0);
+    }
+
+    #[test]
+    fn test_report() {
+        let mut cache = Cache::default();
+        let origin = Origin::new(&location(37.0), (Provider::OpenMeteo, false));
+        cache.set_report(&origin, &WeatherReport::new(12.3, Condition::Rain), 100);
+
+        assert!(cache.report(&origin, 999, 900).is_some());
+        assert!(cache.report(&origin, 1000, 900).is_none());
+
+        let report = cache.stale_report(&origin, 7300, 86400).unwrap();
+        assert_eq!(report.age, Some(7200));
+        assert!(cache.stale_report(&origin, 86500, 86400).is_none());
+    }
+
+    #[test]
+    fn test_report_origin_change() {
+        let paris = Origin::new(&location(48.85), (Provider::OpenMeteo, false));
+        let mut cache = Cache::default();
+        cache.set_report(&paris, &WeatherReport::new(12.3, Condition::Rain), 100);
+
+        // Moving to another place between two runs
+        let tokyo = Origin::new(&location(35.68), (Provider::OpenMeteo, false));
+        assert!(cache.report(&tokyo, 200, 900).is_none());
+        assert!(cache.stale_report(&tokyo, 200, 86400).is_none());
+
+        // Switching providers at the same place
+        let metno = Origin::new(&location(48.85), (Provider::MetNo, false));
+        assert!(cache.report(&metno, 200, 900).is_none());
+        assert!(cache.stale_report(&metno, 200, 86400).is_none());
+
+        cache.set_report(&tokyo, &WeatherReport::new(25.0, Condition::Clear), 300);
+        assert_eq!(
+            cache.report(&tokyo, 400, 900),
+            Some(WeatherReport::new(25.0, Condition::Clear))
+        );
+        assert!(cache.report(&paris, 400, 900).is_none());
+
+        // Adding a forecast view between two runs
+        let forecast = Origin::new(&location(35.68), (Provider::OpenMeteo, true));
+        assert!(cache.report(&forecast, 400, 900).is_none());
+    }
+
+    #[test]
+    fn test_alerts() {
+        let mut cache = Cache::default();
+        let origin = Origin::new(&location(37.0), (AlertSource::Nws, None, None));
+        assert!(cache.stale_alerts(&origin).is_empty());
+
+        let alert = Alert {
+            id: "a".into(),
+            event: "Wind warning".into(),
+            sender: None,
+            description: String::new(),
+            start: None,
+            end: None,
+        };
+        cache.set_alerts(&origin, std::slice::from_ref(&alert), 100);
+
+        assert_eq!(cache.alerts(&origin, 999, 900), Some(vec![alert.clone()]));
+        assert_eq!(cache.alerts(&origin, 1000, 900), None);
+        assert_eq!(cache.stale_alerts(&origin), vec![alert]);
+
+        let other = Origin::new(&location(38.0), (AlertSource::Nws, None, None));
+        assert_eq!(cache.alerts(&other, 999, 900), None);
+        assert!(cache.stale_alerts(&other).is_empty());
+    }
+
+    #[test]
+    fn test_alerts_region_change() {
+        let source = |region: &str| {
+            let region = Some(String::from(region));
+            (
+                AlertSource::MeteoAlarm,
+                Some(String::from("germany")),
+                region,
+            )
+        };
+        let berlin = Origin::new(&location(52.52), source("Berlin"));
+        let mut cache = Cache::default();
+        cache.set_alerts(&berlin, &[], 100);
+        assert_eq!(cache.alerts(&berlin, 200, 900), Some(Vec::new()));
+
+        // Changing the region between two runs at the same coordinates
+        let brandenburg = Origin::new(&location(52.52), source("Brandenburg"));
+        assert_eq!(cache.alerts(&brandenburg, 200, 900), None);
+        assert!(cache.stale_alerts(&brandenburg).is_empty());
+    }
+
+    #[test]
+    fn test_air_quality() {
+        let mut cache = Cache::default();
+        let origin = Origin::new(&location(37.0), AirQualitySource::OpenMeteo);
+        assert!(cache.stale_air_quality(&origin, 0, 86400).is_none());
+
+        let air_quality = AirQuality::new(Concentrations {
+            pm2_5: Some(11.4),
+            ..Default::default()
+        });
+        cache.set_air_quality(&origin, &air_quality, 100);
+
+        assert!(cache.air_quality(&origin, 1899, 1800).is_some());
+        assert!(cache.air_quality(&origin, 1900, 1800).is_none());
+
+        let stale = cache.stale_air_quality(&origin, 3700, 86400).unwrap();
+        assert_eq!(stale.concentrations.pm2_5, Some(11.4));
+        assert_eq!(stale.age, Some(3600));
+        assert!(cache.stale_air_quality(&origin, 86500, 86400).is_none());
+
+        let other = Origin::new(&location(37.0), AirQualitySource::OpenWeatherMap);
+        assert!(cache.air_quality(&other, 1000, 1800).is_none());
+        assert!(cache.stale_air_quality(&other, 1000, 86400).is_none());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let path = std::env::temp_dir().join(format!("weather-cache-{}", std::process::id()));
+        let ip = IPv4("203.0.113.7".into());
+        let origin = Origin::new(&location(37.0), (Provider::OpenWeatherMap, false));
+
+        let mut cache = Cache::default();
+        cache.set_ip(&ip, 100);
+        cache.set_location(&ip, &location(37.0), 100);
+        cache.set_report(&origin, &WeatherReport::new(12.3, Condition::Rain), 100);
+        cache.save(&path);
+
+        let cache = Cache::load(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(cache.ip(100, 60), Some(ip.clone()));
+        assert_eq!(cache.location(&ip).unwrap().lat, 37.0);
+        assert_eq!(
+            cache.report(&origin, 100, 60),
+            Some(WeatherReport::new(12.3, Condition::Rain))
+        );
+    }
+
+    #[test]
+    fn test_load_invalid() {
+        let cache = Cache::load(Path::new("tests/cache/garbage.json"));
+        assert!(cache.report.is_none());
+
+        let cache = Cache::load(Path::new("tests/missing.json"));
+        assert!(cache.ip.is_none());
+    }
+}
@@ -0,0 +1,273 @@
+use chrono::{DateTime, FixedOffset, NaiveDate, TimeZone, Timelike, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::report::convert;
+use crate::Condition;
+
+// Constants
+pub(crate) const HOURLY_HOURS: i64 = 48;
+pub(crate) const DAILY_DAYS: usize = 7;
+
+/// Forecast of a step of the hourly forecast
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Hour {
+    pub time: DateTime<Utc>,        // start of the step
+    pub temperature: f64,           // air temperature (°C)
+    pub condition: Condition,       // prevailing condition
+    pub precipitation: Option<f64>, // precipitation over the step (mm)
+}
+
+/// Forecast of a day
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Day {
+    pub date: NaiveDate,            // local date
+    pub low: f64,                   // lowest air temperature (°C)
+    pub high: f64,                  // highest air temperature (°C)
+    pub condition: Condition,       // prevailing condition
+    pub precipitation: Option<f64>, // total precipitation (mm)
+}
+
+/// Hourly and daily forecasts of a `WeatherProvider`
+///
+/// Hourly steps cover the next 48 hours and may be longer than an hour when
+/// the provider doesn't offer hourly data. Days cover up to the next 7 days.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct Forecast {
+    pub hourly: Vec<Hour>,
+    pub daily: Vec<Day>,
+}
+
+impl Forecast {
+    /// Creates a forecast of hourly steps, deriving the days from them
+    ///
+    /// Used by providers without a daily forecast. A day's condition is the
+    /// one of the step closest to noon.
+    ///
+    /// # Arguments
+    ///
+    /// - `steps`: Steps in chronological order, possibly beyond 48 hours
+    /// - `offset`: Offset of the local time zone, which days are split by
+    pub fn from_steps(steps: Vec<Hour>, offset: FixedOffset) -> Forecast {
+        let mut daily: Vec<(Day, u32)> = Vec::new();
+        for step in &steps {
+            let local = step.time.with_timezone(&offset);
+            let noon = (local.hour() as i64 - 12).unsigned_abs() as u32;
+            match daily.last_mut() {
+                Some((day, closest)) if day.date == local.date_naive() => {
+                    day.low = day.low.min(step.temperature);
+                    day.high = day.high.max(step.temperature);
+                    day.precipitation = match (day.precipitation, step.precipitation) {
+                        (Some(a), Some(b)) => Some(a + b),
+                        (a, b) => a.or(b),
+                    };
+                    if noon < *closest {
+                        day.condition = step.condition;
+                        *closest = noon;
+                    }
+                }
+                _ => daily.push((
+                    Day {
+                        date: local.date_naive(),
+                        low: step.temperature,
+                        high: step.temperature,
+                        condition: step.condition,
+                        precipitation: step.precipitation,
+                    },
+                    noon,
+                )),
+            }
+        }
+
+        let mut forecast = Forecast {
+            hourly: steps,
+            daily: daily.into_iter().map(|(day, _)| day).collect(),
+        };
+        forecast.truncate();
+        forecast
+    }
+
+    /// Drops the steps and days beyond the forecast horizons
+    pub(crate) fn truncate(&mut self) {
+        if let Some(first) = self.hourly.first().map(|v| v.time) {
+            let end = first + chrono::Duration::hours(HOURLY_HOURS);
+            self.hourly.retain(|v| v.time < end);
+        }
+        self.daily.truncate(DAILY_DAYS);
+    }
+
+    /// Returns the step covering a time
+    ///
+    /// # Arguments
+    ///
+    /// - `time`: Time to find the forecast of
+    ///
+    /// # Returns
+    ///
+    /// The last step starting at or before `time`, if the forecast covers it
+    pub fn hour(&self, time: DateTime<Utc>) -> Option<&Hour> {
+        let last = self.hourly.last()?;
+        if time > last.time + chrono::Duration::hours(6) {
+            return None;
+        }
+
+        self.hourly.iter().rev().find(|v| v.time <= time)
+    }
+
+    /// Returns the forecast of a local date
+    pub fn day(&self, date: NaiveDate) -> Option<&Day> {
+        self.daily.iter().find(|v| v.date == date)
+    }
+
+    /// Formats the forecast as a table for the terminal
+    ///
+    /// # Arguments
+    ///
+    /// - `scale`: Temperature scale to display
+    /// - `offset`: Offset of the local time zone
+    ///
+    /// # Returns
+    ///
+    /// The hourly and daily tables
+    pub fn table(&self, scale: char, offset: FixedOffset) -> String {
+        let precipitation = |v: Option<f64>| match v {
+            Some(v) => format!("{:.1} mm", v),
+            None => "-".into(),
+        };
+        let temperature = |v: f64| format!("{:.1}°{}", convert(v, scale), scale);
+
+        let mut lines = format!("{:<12} {:>8} {:>8}  condition\n", "hour", "temp", "precip");
+        for hour in &self.hourly {
+            lines.push_str(&format!(
+                "{:<12} {:>8} {:>8}  {}\n",
+                hour.time.with_timezone(&offset).format("%a %H:%M"),
+                temperature(hour.temperature),
+                precipitation(hour.precipitation),
+                hour.condition
+            ));
+        }
+
+        lines.push_str(&format!(
+            "\n{:<12} {:>8} {:>8} {:>8}  condition\n",
+            "day", "low", "high", "precip"
+        ));
+        for day in &self.daily {
+            lines.push_str(&format!(
+                "{:<12} {:>8} {:>8} {:>8}  {}\n",
+                day.date.format("%a %d %b"),
+                temperature(day.low),
+                temperature(day.high),
+                precipitation(day.precipitation),
+                day.condition
+            ));
+        }
+
+        lines
+    }
+}
+
+/// Converts a Unix time to a `DateTime`
+pub(crate) fn unix(time: i64) -> DateTime<Utc> {
+    Utc.timestamp_opt(time, 0).single().unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn step(time: &str, temperature: f64, condition: Condition) -> Hour {
+        Hour {
+            time: DateTime::parse_from_rfc3339(time)
+                .unwrap()
+                .with_timezone(&Utc),
+            temperature,
+            condition,
+            precipitation: Some(0.5),
+        }
+    }
+
+    #[test]
+    fn test_from_steps() {
+        let offset = FixedOffset::east_opt(2 * 3600).unwrap();
+        let forecast = Forecast::from_steps(
+            vec![
+                step("2026-10-18T06:00:00Z", 9.0, Condition::Fog),
+                step("2026-10-18T09:00:00Z", 14.0, Condition::Clear),
+                step("2026-10-18T12:00:00Z", 15.5, Condition::Cloudy),
+                step("2026-10-18T21:00:00Z", 10.0, Condition::Rain),
+                step("2026-10-18T22:00:00Z", 8.0, Condition::Snow),
+            ],
+            offset,
+        );
+
+        assert_eq!(forecast.hourly.len(), 5);
+        assert_eq!(
+            forecast.daily,
+            vec![
+                Day {
+                    date: NaiveDate::from_ymd_opt(2026, 10, 18).unwrap(),
+                    low: 9.0,
+                    high: 15.5,
+                    condition: Condition::Clear,
+                    precipitation: Some(2.0),
+                },
+                Day {
+                    date: NaiveDate::from_ymd_opt(2026, 10, 19).unwrap(),
+                    low: 8.0,
+                    high: 8.0,
+                    condition: Condition::Snow,
+                    precipitation: Some(0.5),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_truncate() {
+        let steps = (0..60)
+            .map(|v| Hour {
+                time: unix(v * 3600),
+                temperature: 0.0,
+                condition: Condition::Clear,
+                precipitation: None,
+            })
+            .collect();
+        let forecast = Forecast::from_steps(steps, FixedOffset::east_opt(0).unwrap());
+
+        assert_eq!(forecast.hourly.len(), 48);
+        assert_eq!(forecast.daily.len(), 3);
+    }
+
+    #[test]
+    fn test_hour() {
+        let forecast = Forecast {
+            hourly: vec![
+                step("2026-10-18T06:00:00Z", 9.0, Condition::Fog),
+                step("2026-10-18T09:00:00Z", 14.0, Condition::Clear),
+            ],
+            daily: Vec::new(),
+        };
+
+        let hour = |time| forecast.hour(DateTime::parse_from_rfc3339(time).unwrap().into());
+        assert_eq!(hour("2026-10-18T05:59:00Z"), None);
+        assert_eq!(hour("2026-10-18T08:59:00Z").unwrap().temperature, 9.0);
+        assert_eq!(hour("2026-10-18T14:00:00Z").unwrap().temperature, 14.0);
+        assert_eq!(hour("2026-10-18T16:00:00Z"), None);
+    }
+
+    #[test]
+    fn test_table() {
+        let forecast = Forecast::from_steps(
+            vec![step("2026-10-18T12:00:00Z", 15.5, Condition::Cloudy)],
+            FixedOffset::east_opt(0).unwrap(),
+        );
+
+        assert_eq!(
+            forecast.table('C', FixedOffset::east_opt(0).unwrap()),
+            "hour             temp   precip  condition\n\
+             Sun 12:00      15.5°C   0.5 mm  cloudy\n\
+             \n\
+             day               low     high   precip  condition\n\
+             Sun 18 Oct     15.5°C   15.5°C   0.5 mm  cloudy\n"
+        );
+    }
+}
@@ -0,0 +1,571 @@
+use std::collections::HashMap;
+
+use serde::Deserialize;
+
+use crate::IconTheme;
+
+// Constants
+const DEFAULT_FULL_TEXT: &str = "WTR {temp}, {condition}";
+const DEFAULT_SHORT_TEXT: &str = "WTR {temp}";
+const DEFAULT_ICON_THEME: &str = "text";
+const DEFAULT_COLOR: Color = Color(0xFF, 0xFF, 0xFF);
+
+// Upper bounds of the Beaufort scale (m/s)
+const BEAUFORT: [f64; 12] = [
+    0.5, 1.6, 3.4, 5.5, 8.0, 10.8, 13.9, 17.2, 20.8, 24.5, 28.5, 32.7,
+];
+
+// Compass points of wind directions
+const COMPASS: [&str; 16] = [
+    "N", "NNE", "NE", "ENE", "E", "ESE", "SE", "SSE", "S", "SSW", "SW", "WSW", "W", "WNW", "NW",
+    "NNW",
+];
+
+/// Units wind speeds are displayed in
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum WindUnit {
+    #[default]
+    Ms, // metres per second
+    Kmh,      // kilometres per hour
+    Mph,      // miles per hour
+    Knots,    // nautical miles per hour
+    Beaufort, // Beaufort scale
+}
+
+impl WindUnit {
+    /// Formats a wind speed
+    ///
+    /// # Arguments
+    ///
+    /// - `speed`: Wind speed in m/s
+    ///
+    /// # Returns
+    ///
+    /// The speed in the unit, with the unit
+    pub fn format(self, speed: f64) -> String {
+        match self {
+            WindUnit::Ms => format!("{:.1} m/s", speed),
+            WindUnit::Kmh => format!("{:.0} km/h", speed * 3.6),
+            WindUnit::Mph => format!("{:.0} mph", speed * 2.236_936),
+            WindUnit::Knots => format!("{:.0} kn", speed * 1.943_844),
+            WindUnit::Beaufort => format!("{} Bft", beaufort(speed)),
+        }
+    }
+}
+
+/// Units pressures are displayed in
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum PressureUnit {
+    #[default]
+    Hpa, // hectopascals
+    Inhg, // inches of mercury
+}
+
+impl PressureUnit {
+    /// Formats a pressure
+    ///
+    /// # Arguments
+    ///
+    /// - `pressure`: Pressure in hPa
+    ///
+    /// # Returns
+    ///
+    /// The pressure in the unit, with the unit
+    pub fn format(self, pressure: f64) -> String {
+        match self {
+            PressureUnit::Hpa => format!("{:.0} hPa", pressure),
+            PressureUnit::Inhg => format!("{:.2} inHg", pressure * 0.029_53),
+        }
+    }
+}
+
+/// Returns the Beaufort number of a wind speed in m/s
+pub fn beaufort(speed: f64) -> usize {
+    BEAUFORT
+        .iter()
+        .position(|v| speed < *v)
+        .unwrap_or(BEAUFORT.len())
+}
+
+/// Returns the compass point of a wind direction in degrees
+pub fn compass(direction: f64) -> &'static str {
+    let point = (direction.rem_euclid(360.0) / 22.5).round() as usize;
+    COMPASS[point % COMPASS.len()]
+}
+
+/// Temperature above which a color is used
+#[derive(Clone, Deserialize)]
+pub struct ColorStop {
+    threshold: f64, // temperature in the configured scale
+    color: String,  // color as `#RRGGBB`
+}
+
+/// Output format options as written in the configuration file
+#[derive(Clone, Deserialize)]
+#[serde(default)]
+pub struct Format {
+    pub full_text: String,                       // template of the full text
+    pub short_text: String,                      // template of the short text
+    pub wind_unit: WindUnit,                     // unit of wind speeds
+    pub pressure_unit: PressureUnit,             // unit of pressures
+    pub icon_theme: String,                      // name of the icon theme
+    pub icon_themes: HashMap<String, IconTheme>, // themes besides the built-in ones
+    pub color_stops: Vec<ColorStop>,             // colors by temperature
+    pub color_gradient: bool,                    // interpolate between color stops
+}
+
+impl Default for Format {
+    fn default() -> Format {
+        Format {
+            full_text: DEFAULT_FULL_TEXT.into(),
+            short_text: DEFAULT_SHORT_TEXT.into(),
+            wind_unit: WindUnit::default(),
+            pressure_unit: PressureUnit::default(),
+            icon_theme: DEFAULT_ICON_THEME.into(),
+            icon_themes: HashMap::new(),
+            color_stops: Vec::new(),
+            color_gradient: false,
+        }
+    }
+}
+
+/// Values that can be substituted into a template
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Value {
+    Clouds,        // cloud cover (%)
+    Condition,     // prevailing condition
+    FeelsLike,     // apparent temperature
+    Humidity,      // relative humidity (%)
+    Icon,          // icon of the condition
+    Precipitation, // precipitation over the last hour (mm)
+    Pressure,      // sea level pressure
+    Sunrise,       // local time of sunrise
+    Sunset,        // local time of sunset
+    Temperature,   // air temperature
+    Uv,            // UV index
+    Visibility,    // visibility (km)
+    Wind,          // wind speed
+    WindDirection, // compass point the wind blows from
+}
+
+/// Segment of a template
+#[derive(Clone, Debug, PartialEq)]
+enum Segment {
+    Text(String),
+    Placeholder(Value),
+    Optional(Vec<Segment>), // omitted if any of its placeholders is empty
+}
+
+/// Parsed text template
+///
+/// Templates are plain text with placeholders enclosed in braces, e.g.
+/// `WTR {temp}`. Text enclosed in brackets, e.g. `[ feels {feels_like}]`, is
+/// only displayed if all the placeholders within it have a value. Braces,
+/// brackets and backslashes are escaped with a backslash.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Template(Vec<Segment>);
+
+impl Template {
+    /// Parses a template
+    ///
+    /// # Arguments
+    ///
+    /// - `text`: The template text
+    ///
+    /// # Returns
+    ///
+    /// A `Result`:
+    /// - `Ok`: A parsed `Template`
+    /// - `Err`: A `String` describing why the template is invalid
+    pub fn parse(text: &str) -> Result<Template, String> {
+        let mut segments = Vec::new();
+        let mut optional: Option<(usize, Vec<Segment>)> = None;
+        let mut literal = String::new();
+
+        let mut chars = text.char_indices();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '\\' => match chars.next() {
+                    Some((_, c)) => literal.push(c),
+                    None => return Err(format!("dangling escape at {} in '{}'", i, text)),
+                },
+                '{' => {
+                    let mut name = String::new();
+                    let mut closed = false;
+                    for (_, c) in chars.by_ref() {
+                        match c {
+                            '}' => {
+                                closed = true;
+                                break;
+                            }
+                            '{' => return Err(format!("nested '{{' at {} in '{}'", i, text)),
+                            c => name.push(c),
+                        }
+                    }
+                    if !closed {
+                        return Err(format!("unclosed '{{' at {} in '{}'", i, text));
+                    }
+
+                    let value =
+                        parse_value(&name).map_err(|e| format!("{} at {} in '{}'", e, i, text))?;
+
+                    let current = match &mut optional {
+                        Some((_, v)) => v,
+                        None => &mut segments,
+                    };
+                    if !literal.is_empty() {
+                        current.push(Segment::Text(std::mem::take(&mut literal)));
+                    }
+                    current.push(Segment::Placeholder(value));
+                }
+                '}' => return Err(format!("unmatched '}}' at {} in '{}'", i, text)),
+                '[' => {
+                    if optional.is_some() {
+                        return Err(format!("nested '[' at {} in '{}'", i, text));
+                    }
+                    if !literal.is_empty() {
+                        segments.push(Segment::Text(std::mem::take(&mut literal)));
+                    }
+                    optional = Some((i, Vec::new()));
+                }
+                ']' => match optional.take() {
+                    Some((_, mut v)) => {
+                        if !literal.is_empty() {
+                            v.push(Segment::Text(std::mem::take(&mut literal)));
+                        }
+                        segments.push(Segment::Optional(v));
+                    }
+                    None => return Err(format!("unmatched ']' at {} in '{}'", i, text)),
+                },
+                c => literal.push(c),
+            }
+        }
+
+        if let Some((i, _)) = optional {
+            return Err(format!("unclosed '[' at {} in '{}'", i, text));
+        }
+        if !literal.is_empty() {
+            segments.push(Segment::Text(literal));
+        }
+
+        Ok(Template(segments))
+    }
+
+    /// Renders the template
+    ///
+    /// # Arguments
+    ///
+    /// - `resolve`: A function returning the text of a value, `None` if the
+    ///   value is unknown
+    pub fn render<F>(&self, resolve: F) -> String
+    where
+        F: Fn(Value) -> Option<String>,
+    {
+        let mut output = String::new();
+        for segment in &self.0 {
+            match segment {
+                Segment::Text(text) => output.push_str(text),
+                Segment::Placeholder(value) => {
+                    output.push_str(&resolve(*value).unwrap_or_default())
+                }
+                Segment::Optional(segments) => {
+                    let mut section = String::new();
+                    let mut complete = true;
+                    for segment in segments {
+                        match segment {
+                            Segment::Text(text) => section.push_str(text),
+                            Segment::Placeholder(value) => match resolve(*value) {
+                                Some(v) => section.push_str(&v),
+                                None => complete = false,
+                            },
+                            Segment::Optional(_) => (),
+                        }
+                    }
+                    if complete {
+                        output.push_str(&section);
+                    }
+                }
+            }
+        }
+
+        output
+    }
+}
+
+/// Parses the name of a placeholder, e.g. `temp`
+fn parse_value(name: &str) -> Result<Value, String> {
+    let value = match name {
+        "clouds" => Value::Clouds,
+        "condition" => Value::Condition,
+        "feels_like" => Value::FeelsLike,
+        "humidity" => Value::Humidity,
+        "icon" => Value::Icon,
+        "precip" => Value::Precipitation,
+        "pressure" => Value::Pressure,
+        "sunrise" => Value::Sunrise,
+        "sunset" => Value::Sunset,
+        "temp" => Value::Temperature,
+        "uv" => Value::Uv,
+        "visibility" => Value::Visibility,
+        "wind" => Value::Wind,
+        "wind_dir" => Value::WindDirection,
+        _ => {
+            return Err(format!(
+                "unknown placeholder '{{{}}}', select from \
+                 [clouds, condition, feels_like, humidity, icon, precip, pressure, sunrise, \
+                 sunset, temp, uv, visibility, wind, wind_dir]",
+                name
+            ))
+        }
+    };
+
+    Ok(value)
+}
+
+/// Color as red, green and blue components
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Color(u8, u8, u8);
+
+impl Color {
+    /// Parses a color written as `#RRGGBB`
+    fn parse(text: &str) -> Result<Color, String> {
+        let hex = match text.strip_prefix('#') {
+            Some(v) if v.len() == 6 && v.chars().all(|c| c.is_ascii_hexdigit()) => v,
+            _ => return Err(format!("invalid color '{}', expected '#RRGGBB'", text)),
+        };
+
+        let component = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).unwrap();
+        Ok(Color(component(0), component(2), component(4)))
+    }
+
+    /// Linearly interpolates between two colors
+    fn lerp(&self, other: &Color, t: f64) -> Color {
+        let mix = |a: u8, b: u8| ((a as f64) + t * ((b as f64) - (a as f64))).round() as u8;
+        Color(
+            mix(self.0, other.0),
+            mix(self.1, other.1),
+            mix(self.2, other.2),
+        )
+    }
+}
+
+impl std::fmt::Display for Color {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "#{:02X}{:02X}{:02X}", self.0, self.1, self.2)
+    }
+}
+
+/// Validated output format
+#[derive(Clone, Debug, PartialEq)]
+pub struct Formatter {
+    pub full_text: Template,         // template of the full text
+    pub short_text: Template,        // template of the short text
+    pub wind_unit: WindUnit,         // unit of wind speeds
+    pub pressure_unit: PressureUnit, // unit of pressures
+    pub icons: IconTheme,            // icons of the conditions
+
+    stops: Vec<(f64, Color)>, // color stops sorted by temperature
+    gradient: bool,           // interpolate between color stops
+}
+
+impl Formatter {
+    /// Validates output format options
+    ///
+    /// # Arguments
+    ///
+    /// - `format`: Output format options from the configuration file
+    ///
+    /// # Returns
+    ///
+    /// A `Result`:
+    /// - `Ok`: A validated `Formatter`
+    /// - `Err`: A `String` describing the invalid option
+    pub fn new(format: &Format) -> Result<Formatter, String> {
+        let full_text =
+            Template::parse(&format.full_text).map_err(|e| format!("full_text: {}", e))?;
+        let short_text =
+            Template::parse(&format.short_text).map_err(|e| format!("short_text: {}", e))?;
+        let icons = IconTheme::find(&format.icon_theme, &format.icon_themes)
+            .map_err(|e| format!("icon_theme: {}", e))?;
+        let stops = parse_stops(&format.color_stops).map_err(|e| format!("color_stops: {}", e))?;
+
+        Ok(Formatter {
+            full_text,
+            short_text,
+            wind_unit: format.wind_unit,
+            pressure_unit: format.pressure_unit,
+            icons,
+            stops,
+            gradient: format.color_gradient,
+        })
+    }
+
+    /// Returns the color of a temperature
+    ///
+    /// # Arguments
+    ///
+    /// - `temperature`: Temperature in the configured scale
+    ///
+    /// # Returns
+    ///
+    /// The color as `#RRGGBB`, white without color stops
+    pub fn color(&self, temperature: f64) -> String {
+        let stops = &self.stops;
+        let upper = stops
+            .iter()
+            .position(|(threshold, _)| *threshold > temperature);
+        let color = match upper {
+            _ if stops.is_empty() => DEFAULT_COLOR,
+            Some(0) => stops[0].1,
+            Some(i) => {
+                let (low, low_color) = stops[i - 1];
+                let (high, high_color) = stops[i];
+                if self.gradient {
+                    low_color.lerp(&high_color, (temperature - low) / (high - low))
+                } else {
+                    low_color
+                }
+            }
+            None => stops[stops.len() - 1].1,
+        };
+
+        color.to_string()
+    }
+}
+
+impl Default for Formatter {
+    fn default() -> Formatter {
+        Formatter::new(&Format::default()).expect("default format is valid")
+    }
+}
+
+/// Parses and sorts color stops
+fn parse_stops(stops: &[ColorStop]) -> Result<Vec<(f64, Color)>, String> {
+    let mut parsed = Vec::new();
+    for stop in stops {
+        parsed.push((stop.threshold, Color::parse(&stop.color)?));
+    }
+    parsed.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+
+    Ok(parsed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_template() {
+        let template = Template::parse("WTR {temp}[ feels {feels_like}] \\[x\\]");
+        assert_eq!(
+            template,
+            Ok(Template(vec![
+                Segment::Text("WTR ".into()),
+                Segment::Placeholder(Value::Temperature),
+                Segment::Optional(vec![
+                    Segment::Text(" feels ".into()),
+                    Segment::Placeholder(Value::FeelsLike),
+                ]),
+                Segment::Text(" [x]".into()),
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_parse_template_errors() {
+        let invalid = [
+            "WTR {temp",
+            "WTR temp}",
+            "WTR {{temp}}",
+            "WTR {dew_point}",
+            "WTR [{uv}",
+            "WTR {uv}]",
+            "WTR [[{uv}]]",
+            "WTR \\",
+        ];
+        for text in invalid {
+            assert!(Template::parse(text).is_err(), "{}", text);
+        }
+    }
+
+    #[test]
+    fn test_render_template() {
+        let template = Template::parse("{temp}[ uv {uv}][ {wind} {wind_dir}]").unwrap();
+        let output = template.render(|v| match v {
+            Value::Temperature => Some("12.3°C".into()),
+            Value::Wind => Some("4.1 m/s".into()),
+            Value::WindDirection => Some("WSW".into()),
+            _ => None,
+        });
+        assert_eq!(output, "12.3°C 4.1 m/s WSW");
+    }
+
+    #[test]
+    fn test_wind_unit() {
+        assert_eq!(WindUnit::Ms.format(4.1), "4.1 m/s");
+        assert_eq!(WindUnit::Kmh.format(4.1), "15 km/h");
+        assert_eq!(WindUnit::Mph.format(4.1), "9 mph");
+        assert_eq!(WindUnit::Knots.format(4.1), "8 kn");
+        assert_eq!(WindUnit::Beaufort.format(4.1), "3 Bft");
+    }
+
+    #[test]
+    fn test_beaufort() {
+        assert_eq!(beaufort(0.0), 0);
+        assert_eq!(beaufort(0.5), 1);
+        assert_eq!(beaufort(10.7), 5);
+        assert_eq!(beaufort(32.6), 11);
+        assert_eq!(beaufort(40.0), 12);
+    }
+
+    #[test]
+    fn test_pressure_unit() {
+        assert_eq!(PressureUnit::Hpa.format(1012.4), "1012 hPa");
+        assert_eq!(PressureUnit::Inhg.format(1012.4), "29.90 inHg");
+    }
+
+    #[test]
+    fn test_color() {
+        let format: Format = serde_yaml::from_str(
+            "color_stops:\n  - {threshold: 30, color: '#FF0000'}\n  \
+             - {threshold: 0, color: '#0000FF'}\n  - {threshold: 15, color: '#00FF00'}",
+        )
+        .unwrap();
+        let mut formatter = Formatter::new(&format).unwrap();
+        assert_eq!(formatter.color(-5.0), "#0000FF");
+        assert_eq!(formatter.color(20.0), "#00FF00");
+        assert_eq!(formatter.color(35.0), "#FF0000");
+
+        formatter.gradient = true;
+        assert_eq!(formatter.color(7.5), "#008080");
+        assert_eq!(formatter.color(35.0), "#FF0000");
+
+        assert_eq!(Formatter::default().color(20.0), "#FFFFFF");
+    }
+
+    #[test]
+    fn test_formatter_errors() {
+        let format: Format =
+            serde_yaml::from_str("color_stops: [{threshold: 0, color: blue}]").unwrap();
+        assert_eq!(
+            Formatter::new(&format).err().unwrap(),
+            "color_stops: invalid color 'blue', expected '#RRGGBB'"
+        );
+
+        let format: Format = serde_yaml::from_str("icon_theme: ascii").unwrap();
+        assert!(Formatter::new(&format)
+            .err()
+            .unwrap()
+            .starts_with("icon_theme: unknown theme 'ascii'"));
+    }
+
+    #[test]
+    fn test_compass() {
+        assert_eq!(compass(0.0), "N");
+        assert_eq!(compass(250.0), "WSW");
+        assert_eq!(compass(350.0), "N");
+        assert_eq!(compass(-90.0), "W");
+    }
+}
@@ -0,0 +1,88 @@
+use std::fmt;
+use std::time::Duration;
+
+// Constants
+const USER_AGENT: &str = "weather-i3blocks/0.1 https://github.com/mattpotok/blocks";
+
+/// Errors fetching a URL
+#[derive(Debug, PartialEq)]
+pub enum HttpError {
+    Status(u16),     // server answered with a non-success status
+    Timeout,         // server didn't answer in time
+    Request(String), // connection or body couldn't be read
+}
+
+impl fmt::Display for HttpError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            HttpError::Status(401) => {
+                write!(f, "request was unauthorized (401), check the API key")
+            }
+            HttpError::Status(429) => write!(f, "request was rate limited (429)"),
+            HttpError::Status(v) => write!(f, "request failed with status {}", v),
+            HttpError::Timeout => write!(f, "request timed out"),
+            HttpError::Request(v) => write!(f, "{}", v),
+        }
+    }
+}
+
+impl std::error::Error for HttpError {}
+
+/// Client fetching the bodies of URLs
+///
+/// Every request of the block goes through this trait so that the endpoints
+/// can be pointed at a local stub.
+pub trait HttpClient {
+    /// Fetches the body of a URL
+    ///
+    /// # Arguments
+    ///
+    /// - `url`: URL to fetch
+    ///
+    /// # Returns
+    ///
+    /// The response body of a successful request
+    fn get(&self, url: &str) -> Result<String, HttpError>;
+}
+
+/// Blocking `HttpClient` backed by reqwest
+pub struct ReqwestClient {
+    client: reqwest::blocking::Client, // client reused by every request
+}
+
+impl ReqwestClient {
+    /// Creates a client
+    ///
+    /// # Arguments
+    ///
+    /// - `timeout`: Time to wait for a response
+    pub fn new(timeout: Duration) -> Result<ReqwestClient, HttpError> {
+        let client = reqwest::blocking::Client::builder()
+            .user_agent(USER_AGENT)
+            .timeout(timeout)
+            .build()
+            .map_err(|e| HttpError::Request(e.to_string()))?;
+
+        Ok(ReqwestClient { client })
+    }
+}
+
+impl HttpClient for ReqwestClient {
+    fn get(&self, url: &str) -> Result<String, HttpError> {
+        let error = |e: reqwest::Error| {
+            if e.is_timeout() {
+                HttpError::Timeout
+            } else {
+                HttpError::Request(e.to_string())
+            }
+        };
+
+        let resp = self.client.get(url).send().map_err(error)?;
+        let status = resp.status();
+        if !status.is_success() {
+            return Err(HttpError::Status(status.as_u16()));
+        }
+
+        resp.text().map_err(error)
+    }
+}
@@ -0,0 +1,158 @@
+use std::collections::HashMap;
+
+use serde::Deserialize;
+
+use crate::Condition;
+
+// Built-in icon themes
+const THEMES: &str = include_str!("icons.yaml");
+
+/// Icon of a condition, optionally differing between day and night
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(untagged)]
+pub enum Icon {
+    Always(String),
+    DayNight { day: String, night: String },
+}
+
+impl Icon {
+    /// Returns the icon for the day or the night
+    fn get(&self, day: bool) -> &str {
+        match self {
+            Icon::Always(icon) => icon,
+            Icon::DayNight { day: icon, .. } if day => icon,
+            Icon::DayNight { night: icon, .. } => icon,
+        }
+    }
+}
+
+/// Icons of every condition
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct IconTheme {
+    clear: Icon,
+    partly_cloudy: Icon,
+    cloudy: Icon,
+    fog: Icon,
+    haze: Icon,
+    drizzle: Icon,
+    rain: Icon,
+    freezing_rain: Icon,
+    showers: Icon,
+    sleet: Icon,
+    snow: Icon,
+    thunderstorm: Icon,
+    unknown: Icon,
+}
+
+impl IconTheme {
+    /// Returns the icon of a condition
+    ///
+    /// # Arguments
+    ///
+    /// - `condition`: Condition to display
+    /// - `day`: Whether the sun is up
+    pub fn icon(&self, condition: Condition, day: bool) -> &str {
+        let icon = match condition {
+            Condition::Clear => &self.clear,
+            Condition::PartlyCloudy => &self.partly_cloudy,
+            Condition::Cloudy => &self.cloudy,
+            Condition::Fog => &self.fog,
+            Condition::Haze => &self.haze,
+            Condition::Drizzle => &self.drizzle,
+            Condition::Rain => &self.rain,
+            Condition::FreezingRain => &self.freezing_rain,
+            Condition::Showers => &self.showers,
+            Condition::Sleet => &self.sleet,
+            Condition::Snow => &self.snow,
+            Condition::Thunderstorm => &self.thunderstorm,
+            Condition::Unknown => &self.unknown,
+        };
+        icon.get(day)
+    }
+
+    /// Looks up a theme by name
+    ///
+    /// # Arguments
+    ///
+    /// - `name`: Name of the theme
+    /// - `themes`: Themes of the configuration file, which take precedence
+    ///   over the built-in ones
+    ///
+    /// # Returns
+    ///
+    /// A `Result`:
+    /// - `Ok`: The `IconTheme`
+    /// - `Err`: A `String` listing the available themes
+    pub fn find(name: &str, themes: &HashMap<String, IconTheme>) -> Result<IconTheme, String> {
+        if let Some(theme) = themes.get(name) {
+            return Ok(theme.clone());
+        }
+
+        let mut builtin = builtin();
+        if let Some(theme) = builtin.remove(name) {
+            return Ok(theme);
+        }
+
+        let mut names: Vec<&String> = builtin.keys().chain(themes.keys()).collect();
+        names.sort();
+        names.dedup();
+        let names: Vec<&str> = names.iter().map(|v| v.as_str()).collect();
+        Err(format!(
+            "unknown theme '{}', select from [{}]",
+            name,
+            names.join(", ")
+        ))
+    }
+}
+
+/// Returns the built-in icon themes
+fn builtin() -> HashMap<String, IconTheme> {
+    serde_yaml::from_str(THEMES).expect("built-in icon themes are valid")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin() {
+        let themes = builtin();
+        assert_eq!(themes.len(), 3);
+
+        let theme = &themes["text"];
+        assert_eq!(theme.icon(Condition::Clear, true), "SUN");
+        assert_eq!(theme.icon(Condition::Clear, false), "MOON");
+        assert_eq!(theme.icon(Condition::Rain, false), "RAIN");
+        assert_eq!(themes["nerdfont"].icon(Condition::Snow, true), "\u{e31a}");
+    }
+
+    #[test]
+    fn test_find() {
+        let themes: HashMap<String, IconTheme> = serde_yaml::from_str(
+            "text:\n  clear: {day: '*', night: ')'}\n  partly_cloudy: '~'\n  cloudy: '~'\n  \
+             fog: '='\n  haze: '='\n  drizzle: ','\n  rain: ';'\n  freezing_rain: ';'\n  \
+             showers: ';'\n  sleet: ':'\n  snow: '#'\n  thunderstorm: '!'\n  unknown: '?'",
+        )
+        .unwrap();
+
+        let theme = IconTheme::find("text", &themes).unwrap();
+        assert_eq!(theme.icon(Condition::Clear, false), ")");
+        assert_eq!(
+            IconTheme::find("emoji", &themes)
+                .unwrap()
+                .icon(Condition::Clear, true),
+            "☀️"
+        );
+        assert_eq!(
+            IconTheme::find("ascii", &themes).err().unwrap(),
+            "unknown theme 'ascii', select from [emoji, nerdfont, text]"
+        );
+    }
+
+    #[test]
+    fn test_incomplete_theme() {
+        let theme: Result<IconTheme, _> = serde_yaml::from_str("clear: '*'");
+        assert!(theme.is_err());
+    }
+}
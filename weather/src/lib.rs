@@ -1,19 +1,95 @@
 use std::fmt;
+use std::path::{Path, PathBuf};
 
 use log::{error, info};
-use reqwest;
-use serde::Deserialize;
-use serde_yaml;
+use serde::{Deserialize, Serialize};
 use simplelog::{ConfigBuilder, LevelFilter, WriteLogger};
 
+mod air;
+mod alert;
+mod aqi;
+mod cache;
+mod forecast;
+mod format;
+mod http;
+mod icon;
+mod location;
+mod meteoalarm;
+mod metno;
+mod nws;
+mod openmeteo;
+mod openweathermap;
+mod provider;
+mod report;
+mod sun;
+mod view;
+
+pub use air::{AirQuality, AirQualityConfig, AirQualityProvider, AirQualitySource};
+pub use alert::{Alert, AlertConfig, AlertProvider, AlertSource};
+pub use aqi::{Aqi, AqiScale, Concentrations, Pollutant};
+pub use cache::{AlertOrigin, Cache, CacheConfig, Origin, ReportOrigin};
+pub use forecast::{Day, Forecast, Hour};
+pub use format::{ColorStop, Format, Formatter, PressureUnit, WindUnit};
+pub use http::{HttpClient, HttpError, ReqwestClient};
+pub use icon::{Icon, IconTheme};
+pub use location::StaticLocation;
+pub use meteoalarm::MeteoAlarm;
+pub use metno::MetNorway;
+pub use nws::Nws;
+pub use openmeteo::{OpenMeteo, OpenMeteoAirQuality};
+pub use openweathermap::OpenWeatherMap;
+pub use provider::{Provider, WeatherProvider};
+pub use report::{Condition, WeatherReport};
+pub use view::{View, LEFT_BUTTON, RIGHT_BUTTON};
+
 // Constants
-const DEFAULT_ERROR: &str = "WTR Error!\nWTR Error!\n#FF0000";
+pub const DEFAULT_ERROR: &str = "WTR Error!\nWTR Error!\n#FF0000";
+pub const USAGE: &str = "usage: weather <config.yaml>\n       weather forecast <config.yaml>";
+const ARGS_ERROR: &str = "WTR Args error!\nWTR Args error!\n#FF0000";
 
 // Traits
 pub trait I3Block {
     fn format_i3(&self) -> String;
 }
 
+/// Commands of the `weather` binary
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    Block(PathBuf),    // display the block with a configuration file
+    Forecast(PathBuf), // print the forecast of a configuration file
+}
+
+impl Command {
+    /// Parses command line arguments
+    ///
+    /// # Arguments
+    ///
+    /// - `args`: A slice of command line arguments
+    ///
+    /// # Returns
+    ///
+    /// The command to run, or an i3blocks error string
+    pub fn new(args: &[String]) -> Result<Command, String> {
+        match args.get(1).map(|v| v.as_str()) {
+            Some("forecast") => match args.get(2) {
+                Some(path) if args.len() == 3 => Ok(Command::Forecast(PathBuf::from(path))),
+                _ => Err(ARGS_ERROR.into()),
+            },
+            Some(path) if args.len() == 2 => Ok(Command::Block(PathBuf::from(path))),
+            _ => Err(ARGS_ERROR.into()),
+        }
+    }
+}
+
+/// What the block displays, selected in the configuration
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Mode {
+    #[default]
+    Weather, // current weather and forecast views
+    AirQuality, // air quality index, dominant pollutant and pollen
+}
+
 #[derive(Deserialize)]
 pub struct Config {
     #[serde(default = "Config::default_bool_false")]
@@ -25,27 +101,55 @@ pub struct Config {
     #[serde(default = "Config::default_bool_false")]
     pub log_weather_report: bool,
 
-    log_file_path: std::path::PathBuf,
+    log_file_path: PathBuf,
+
+    #[serde(default)]
+    pub mode: Mode,
 
-    pub open_weather_api_key: String,
+    #[serde(default)]
+    pub provider: Provider,
+
+    #[serde(default)]
+    pub open_weather_api_key: Option<String>,
 
     #[serde(default = "Config::default_temperature_scale")]
     pub temperature_scale: char,
+
+    #[serde(default)]
+    pub endpoints: Endpoints,
+
+    #[serde(default = "Config::default_request_timeout")]
+    pub request_timeout: u64,
+
+    #[serde(default)]
+    pub cache: CacheConfig,
+
+    #[serde(default)]
+    pub location: Option<StaticLocation>,
+
+    #[serde(default = "Config::default_views")]
+    pub views: Vec<View>,
+
+    #[serde(default)]
+    pub format: Format,
+
+    #[serde(default)]
+    pub alerts: AlertConfig,
+
+    #[serde(default)]
+    pub air_quality: AirQualityConfig,
+
+    #[serde(skip)]
+    pub formatter: Formatter,
 }
 
 impl Config {
-    pub fn new(args: &[String]) -> Result<Config, String> {
-        // Check number of arguments
-        if args.len() != 2 {
-            let e = "WTR Args error!\nWTR Args error!\n#FF000";
-            return Err(e.into());
-        }
-
+    pub fn new(path: &Path) -> Result<Config, String> {
         // Open configuration file
-        let file = match std::fs::File::open(&args[1]) {
+        let file = match std::fs::File::open(path) {
             Ok(v) => v,
             Err(_) => {
-                let e = "WTR File error!\nWTR File error!\n#FF000";
+                let e = "WTR File error!\nWTR File error!\n#FF0000";
                 return Err(e.into());
             }
         };
@@ -54,7 +158,7 @@ impl Config {
         let mut config: Config = match serde_yaml::from_reader(file) {
             Ok(v) => v,
             Err(_) => {
-                let e = "WTR Parse error!\nWTR Parse error\n#FF0000";
+                let e = "WTR Parse error!\nWTR Parse error!\n#FF0000";
                 return Err(e.into());
             }
         };
@@ -77,8 +181,8 @@ impl Config {
             .set_time_to_local(true)
             .build();
 
-        if let Err(_) = WriteLogger::init(LevelFilter::Info, logger, file) {
-            let e = "WTR Logger error!\nWTR Logger error!\n#FF000";
+        if WriteLogger::init(LevelFilter::Info, logger, file).is_err() {
+            let e = "WTR Logger error!\nWTR Logger error!\n#FF0000";
             return Err(e.into());
         }
 
@@ -94,6 +198,20 @@ impl Config {
             return Err(DEFAULT_ERROR.into());
         }
 
+        // Verify static location
+        if let Some(location) = &config.location {
+            location.validate()?;
+        }
+
+        // Verify output format
+        config.formatter = match Formatter::new(&config.format) {
+            Ok(v) => v,
+            Err(e) => {
+                error!("weather::Config::new: invalid format, {}", e);
+                return Err(DEFAULT_ERROR.into());
+            }
+        };
+
         Ok(config)
     }
 
@@ -104,30 +222,82 @@ impl Config {
     fn default_temperature_scale() -> char {
         'F'
     }
+
+    fn default_request_timeout() -> u64 {
+        10
+    }
+
+    fn default_views() -> Vec<View> {
+        vec![View::Current]
+    }
+
+    /// Returns whether any configured view is rendered from the forecast
+    pub fn needs_forecast(&self) -> bool {
+        self.views.iter().any(|v| v.is_forecast())
+    }
+}
+
+/// Base URLs of the services the block queries
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct Endpoints {
+    pub ip: String,             // external IP lookup
+    pub geolocation: String,    // IP geolocation
+    pub geocoding: String,      // city and postal code geocoding
+    pub openweathermap: String, // OpenWeatherMap API
+    pub openmeteo: String,      // Open-Meteo API
+    pub metno: String,          // MET Norway API
+    pub nws: String,            // US National Weather Service API
+    pub meteoalarm: String,     // MeteoAlarm feeds
+
+    pub openmeteo_air_quality: String, // Open-Meteo air quality API
 }
 
+impl Default for Endpoints {
+    fn default() -> Endpoints {
+        Endpoints {
+            ip: "https://api.ipify.org".into(),
+            geolocation: "http://ip-api.com".into(),
+            geocoding: "https://geocoding-api.open-meteo.com".into(),
+            openweathermap: "https://api.openweathermap.org".into(),
+            openmeteo: "https://api.open-meteo.com".into(),
+            metno: "https://api.met.no".into(),
+            nws: "https://api.weather.gov".into(),
+            meteoalarm: "https://feeds.meteoalarm.org".into(),
+            openmeteo_air_quality: "https://air-quality-api.open-meteo.com".into(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub struct IPv4(String);
 
 impl IPv4 {
-    pub fn new(log: bool) -> Result<IPv4, String> {
+    /// Fetches the external IP
+    ///
+    /// Responses that aren't an IPv4 address, e.g. the login page of a
+    /// captive portal, are rejected.
+    ///
+    /// # Arguments
+    ///
+    /// - `client`: Client the request is sent with
+    /// - `base_url`: Base URL of the IP lookup
+    /// - `log`: Whether to log the IP
+    pub fn new(client: &dyn HttpClient, base_url: &str, log: bool) -> Result<IPv4, String> {
         // Get IP from ipify.org
-        let url = "https://api.ipify.org";
-        let resp = match reqwest::blocking::get(url) {
-            Ok(v) => v,
-            Err(e) => {
-                error!("weather::IPv4::new: {}", e);
-                return Err(DEFAULT_ERROR.into());
-            }
-        };
-
-        // Extract response body
-        match resp.text() {
-            Ok(v) => {
-                if log {
-                    info!("weather::IPv4::new: external IP is {}", v);
+        match client.get(&provider::join(base_url, "/")) {
+            Ok(v) => match v.trim().parse::<std::net::Ipv4Addr>() {
+                Ok(v) => {
+                    if log {
+                        info!("weather::IPv4::new: external IP is {}", v);
+                    }
+                    Ok(IPv4(v.to_string()))
                 }
-                Ok(IPv4(v))
-            }
+                Err(_) => {
+                    error!("weather::IPv4::new: response isn't an IPv4 address");
+                    Err(DEFAULT_ERROR.into())
+                }
+            },
             Err(e) => {
                 error!("weather::IPv4::new: {}", e);
                 Err(DEFAULT_ERROR.into())
@@ -142,10 +312,15 @@ impl fmt::Display for IPv4 {
     }
 }
 
-#[derive(Deserialize)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct GeoLocation {
     status: String,
+
+    // Omitted by failed lookups
+    #[serde(default)]
     lat: f64,
+
+    #[serde(default)]
     lon: f64,
 
     #[serde(default)]
@@ -153,13 +328,52 @@ pub struct GeoLocation {
 }
 
 impl GeoLocation {
-    pub fn new(ip: IPv4, log: bool) -> Result<GeoLocation, String> {
-        // Get Geoleocation
-        let url = format!(
-            "http://ip-api.com/json/{}?fields=status,message,lat,lon",
-            ip
-        );
-        let resp = match reqwest::blocking::get(&url) {
+    /// Creates a location from coordinates
+    ///
+    /// # Arguments
+    ///
+    /// - `lat`: Latitude in [-90, 90]
+    /// - `lon`: Longitude in [-180, 180]
+    ///
+    /// # Returns
+    ///
+    /// The location, or an i3blocks error string if a coordinate is out of
+    /// range
+    pub fn from_coordinates(lat: f64, lon: f64) -> Result<GeoLocation, String> {
+        if !(-90.0..=90.0).contains(&lat) || !(-180.0..=180.0).contains(&lon) {
+            error!(
+                "weather::GeoLocation::from_coordinates: coordinates {}, {} are out of \
+                range, latitude must be in [-90, 90] and longitude in [-180, 180]",
+                lat, lon
+            );
+            return Err(DEFAULT_ERROR.into());
+        }
+
+        Ok(GeoLocation {
+            status: "success".into(),
+            lat,
+            lon,
+            message: String::new(),
+        })
+    }
+
+    /// Fetches the location of an IP
+    ///
+    /// # Arguments
+    ///
+    /// - `client`: Client the request is sent with
+    /// - `base_url`: Base URL of the IP geolocation
+    /// - `ip`: IP to locate
+    /// - `log`: Whether to log the location
+    pub fn new(
+        client: &dyn HttpClient,
+        base_url: &str,
+        ip: IPv4,
+        log: bool,
+    ) -> Result<GeoLocation, String> {
+        // Get Geolocation
+        let path = format!("/json/{}?fields=status,message,lat,lon", ip);
+        let body = match client.get(&provider::join(base_url, &path)) {
             Ok(v) => v,
             Err(e) => {
                 error!("weather::GeoLocation::new: {}", e);
@@ -168,7 +382,7 @@ impl GeoLocation {
         };
 
         // Extract response body
-        let location: GeoLocation = match resp.json() {
+        let location: GeoLocation = match serde_json::from_str(&body) {
             Ok(v) => v,
             Err(e) => {
                 error!("weather::GeoLocation::new: {}", e);
@@ -196,88 +410,26 @@ impl fmt::Display for GeoLocation {
     }
 }
 
-#[derive(Deserialize)]
-pub struct OpenWeatherWeather {
-    main: String,
-}
-
-#[derive(Deserialize)]
-pub struct OpenWeatherMain {
-    temp: f32,
-
-    #[serde(default)]
-    scale: char,
-}
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-#[derive(Deserialize)]
-pub struct OpenWeatherReport {
-    main: OpenWeatherMain,
-    weather: Vec<OpenWeatherWeather>,
-}
-
-impl OpenWeatherReport {
-    pub fn new(
-        location: &GeoLocation,
-        api_key: String,
-        temperature_scale: char,
-        log: bool,
-    ) -> Result<OpenWeatherReport, String> {
-        // Get OpenWeather report
-        let url = format!(
-            "https://api.openweathermap.org/data/2.5/weather?lat={}&lon={}&appid={}",
-            location.lat, location.lon, api_key
-        );
-        let resp = match reqwest::blocking::get(&url) {
-            Ok(v) => v,
-            Err(e) => {
-                error!("weather::OpenWeatherReport::new: {}", e);
-                return Err(DEFAULT_ERROR.into());
-            }
-        };
-
-        // Extract response body
-        let mut report: OpenWeatherReport = match resp.json() {
-            Ok(v) => v,
-            Err(e) => {
-                error!("weather::OpenWeatherReport::new: {}", e);
-                return Err(DEFAULT_ERROR.into());
-            }
-        };
-
-        // Convert temperature
-        report.main.scale = temperature_scale;
-        match report.main.scale {
-            'C' => report.main.temp -= 273.15,
-            'F' => report.main.temp = 1.8 * (report.main.temp - 273.15) + 32.0,
-            _ => (),
-        }
-
-        // Log information
-        if log {
-            info!(
-                "weather::OpenWeatherReport::new: \
-                  current weather is {:.1}°{}, {}",
-                report.main.temp,
-                report.main.scale,
-                report.weather[0].main.to_ascii_lowercase()
-            );
-        }
-
-        Ok(report)
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|v| v.to_string()).collect()
     }
-}
 
-impl I3Block for OpenWeatherReport {
-    fn format_i3(&self) -> String {
-        let full_text = format!(
-            "WTR {:.1}°{}, {}",
-            self.main.temp,
-            self.main.scale,
-            self.weather[0].main.to_ascii_lowercase()
+    #[test]
+    fn test_command() {
+        assert_eq!(
+            Command::new(&args(&["weather", "weather.yaml"])),
+            Ok(Command::Block(PathBuf::from("weather.yaml")))
         );
-        let short_text = format!("WTR {:.1}°{}", self.main.temp, self.main.scale);
-        let color = "#FFFFFF";
-
-        format!("{}\n{}\n{}", full_text, short_text, color)
+        assert_eq!(
+            Command::new(&args(&["weather", "forecast", "weather.yaml"])),
+            Ok(Command::Forecast(PathBuf::from("weather.yaml")))
+        );
+        assert!(Command::new(&args(&["weather"])).is_err());
+        assert!(Command::new(&args(&["weather", "forecast"])).is_err());
+        assert!(Command::new(&args(&["weather", "a.yaml", "b.yaml"])).is_err());
     }
 }
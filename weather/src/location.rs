@@ -0,0 +1,287 @@
+use std::fmt;
+
+use log::{error, info};
+use serde::Deserialize;
+
+use crate::provider;
+use crate::{GeoLocation, HttpClient, DEFAULT_ERROR};
+
+// Constants
+const GEOCODING_RESULTS: &str = "10";
+
+/// Fixed location of the configuration, bypassing IP geolocation
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(untagged)]
+pub enum StaticLocation {
+    Coordinates {
+        latitude: f64,  // latitude (°)
+        longitude: f64, // longitude (°)
+    },
+    City {
+        city: String,            // city name
+        country: Option<String>, // ISO 3166-1 alpha-2 country code
+    },
+    PostalCode {
+        postal_code: String,     // postal code
+        country: Option<String>, // ISO 3166-1 alpha-2 country code
+    },
+}
+
+impl fmt::Display for StaticLocation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (name, country) = match self {
+            StaticLocation::Coordinates {
+                latitude,
+                longitude,
+            } => return write!(f, "{}, {}", latitude, longitude),
+            StaticLocation::City { city, country } => (city, country),
+            StaticLocation::PostalCode {
+                postal_code,
+                country,
+            } => (postal_code, country),
+        };
+
+        match country {
+            Some(country) => write!(f, "{}, {}", name, country),
+            None => write!(f, "{}", name),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct Place {
+    name: String,
+    latitude: f64,
+    longitude: f64,
+
+    #[serde(default)]
+    country_code: String,
+
+    admin1: Option<String>,
+
+    #[serde(default)]
+    postcodes: Vec<String>,
+}
+
+impl fmt::Display for Place {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.admin1 {
+            Some(region) => write!(f, "{} ({}, {})", self.name, region, self.country_code),
+            None => write!(f, "{} ({})", self.name, self.country_code),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct Places {
+    #[serde(default)]
+    results: Vec<Place>,
+}
+
+impl StaticLocation {
+    /// Checks that configured coordinates are in range
+    ///
+    /// # Returns
+    ///
+    /// An i3blocks error string if the coordinates are out of range
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            StaticLocation::Coordinates {
+                latitude,
+                longitude,
+            } => GeoLocation::from_coordinates(*latitude, *longitude).map(|_| ()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Resolves the location to coordinates
+    ///
+    /// Coordinates are used as is, while city names and postal codes are
+    /// geocoded and should be cached by the caller.
+    ///
+    /// # Arguments
+    ///
+    /// - `client`: Client the geocoding request is sent with
+    /// - `base_url`: Base URL of the geocoding API
+    /// - `log`: Whether to log the location
+    ///
+    /// # Returns
+    ///
+    /// The location, or an i3blocks error string
+    pub fn resolve(
+        &self,
+        client: &dyn HttpClient,
+        base_url: &str,
+        log: bool,
+    ) -> Result<GeoLocation, String> {
+        let (name, country, postal) = match self {
+            StaticLocation::Coordinates {
+                latitude,
+                longitude,
+            } => return GeoLocation::from_coordinates(*latitude, *longitude),
+            StaticLocation::City { city, country } => (city, country, false),
+            StaticLocation::PostalCode {
+                postal_code,
+                country,
+            } => (postal_code, country, true),
+        };
+
+        // Search geocoding API
+        let url = match reqwest::Url::parse_with_params(
+            &provider::join(base_url, "/v1/search"),
+            &[
+                ("name", name.as_str()),
+                ("count", GEOCODING_RESULTS),
+                ("format", "json"),
+            ],
+        ) {
+            Ok(v) => v,
+            Err(e) => {
+                error!("weather::StaticLocation::resolve: {}", e);
+                return Err(DEFAULT_ERROR.into());
+            }
+        };
+
+        let body = match client.get(url.as_str()) {
+            Ok(v) => v,
+            Err(e) => {
+                error!("weather::StaticLocation::resolve: {}", e);
+                return Err(DEFAULT_ERROR.into());
+            }
+        };
+
+        // Pick the only matching place
+        let place = match select(&body, name, country.as_deref(), postal) {
+            Ok(v) => v,
+            Err(e) => {
+                error!("weather::StaticLocation::resolve: '{}' {}", self, e);
+                return Err(DEFAULT_ERROR.into());
+            }
+        };
+
+        if log {
+            info!("weather::StaticLocation::resolve: '{}' is {}", self, place);
+        }
+
+        GeoLocation::from_coordinates(place.latitude, place.longitude)
+    }
+}
+
+/// Selects the place a geocoding search refers to
+///
+/// # Arguments
+///
+/// - `body`: Geocoding response body
+/// - `name`: City name or postal code searched for
+/// - `country`: Country code the place must be in, if any
+/// - `postal`: Whether `name` is a postal code
+///
+/// # Returns
+///
+/// The only matching place, or a description of why none or several match
+fn select(body: &str, name: &str, country: Option<&str>, postal: bool) -> Result<Place, String> {
+    let places: Places = serde_json::from_str(body).map_err(|e| e.to_string())?;
+
+    let mut matches: Vec<Place> = places
+        .results
+        .into_iter()
+        .filter(|v| {
+            if postal {
+                v.postcodes.iter().any(|v| v.eq_ignore_ascii_case(name))
+            } else {
+                v.name.eq_ignore_ascii_case(name)
+            }
+        })
+        .filter(|v| country.is_none_or(|c| v.country_code.eq_ignore_ascii_case(c)))
+        .collect();
+
+    match matches.len() {
+        0 => Err("matches no place".into()),
+        1 => Ok(matches.remove(0)),
+        _ => {
+            let candidates: Vec<String> = matches.iter().map(|v| v.to_string()).collect();
+            Err(format!(
+                "is ambiguous between {}, set `country` or coordinates instead",
+                candidates.join(", ")
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture(name: &str) -> String {
+        std::fs::read_to_string(format!("tests/geocoding/{}.json", name)).unwrap()
+    }
+
+    #[test]
+    fn test_select_city() {
+        let body = fixture("paris");
+        let place = select(&body, "paris", Some("fr"), false).unwrap();
+        assert_eq!(place.to_string(), "Paris (Île-de-France, FR)");
+        assert_eq!(place.latitude, 48.85341);
+    }
+
+    #[test]
+    fn test_select_ambiguous() {
+        let body = fixture("paris");
+        assert_eq!(
+            select(&body, "Paris", None, false).err().unwrap(),
+            "is ambiguous between Paris (Île-de-France, FR), Paris (Texas, US), \
+             Paris (Tennessee, US), set `country` or coordinates instead"
+        );
+        assert!(select(&body, "Paris", Some("US"), false).is_err());
+    }
+
+    #[test]
+    fn test_select_postal_code() {
+        let body = fixture("postal-code");
+        let place = select(&body, "10115", Some("DE"), true).unwrap();
+        assert_eq!(place.to_string(), "Berlin Mitte (Berlin, DE)");
+    }
+
+    #[test]
+    fn test_select_none() {
+        assert_eq!(
+            select("{\"generationtime_ms\": 0.4}", "Atlantis", None, false)
+                .err()
+                .unwrap(),
+            "matches no place"
+        );
+    }
+
+    #[test]
+    fn test_validate() {
+        let location = |latitude, longitude| StaticLocation::Coordinates {
+            latitude,
+            longitude,
+        };
+        assert!(location(48.85, 2.35).validate().is_ok());
+        assert!(location(-90.0, 180.0).validate().is_ok());
+        assert!(location(90.5, 0.0).validate().is_err());
+        assert!(location(0.0, -181.0).validate().is_err());
+        assert!(location(f64::NAN, 0.0).validate().is_err());
+    }
+
+    #[test]
+    fn test_deserialize() {
+        let location: StaticLocation = serde_yaml::from_str("city: Paris\ncountry: FR").unwrap();
+        assert_eq!(
+            location,
+            StaticLocation::City {
+                city: "Paris".into(),
+                country: Some("FR".into())
+            }
+        );
+        assert_eq!(location.to_string(), "Paris, FR");
+
+        let location: StaticLocation = serde_yaml::from_str("postal_code: '10115'").unwrap();
+        assert_eq!(location.to_string(), "10115");
+
+        let location: StaticLocation =
+            serde_yaml::from_str("latitude: 48.85\nlongitude: 2.35").unwrap();
+        assert_eq!(location.to_string(), "48.85, 2.35");
+    }
+}
@@ -1,10 +1,299 @@
-use weather;
-use weather::I3Block;
+use std::path::Path;
 
-fn main() {
-    // Parse configuration file
-    let args: Vec<String> = std::env::args().collect();
-    let config = match weather::Config::new(&args) {
+use chrono::{DateTime, Local, Utc};
+use weather::{
+    AirQuality, Alert, Cache, Config, GeoLocation, HttpClient, I3Block, Origin, StaticLocation,
+    WeatherReport,
+};
+
+// Constants
+const URGENT_EXIT_CODE: i32 = 33;
+
+/// Resolves the configured location, reusing a geocoded place
+///
+/// # Arguments
+///
+/// - `config`: Parsed configuration
+/// - `client`: Client requests are sent with
+/// - `cache`: Cache updated with a geocoded place
+/// - `location`: Configured location
+/// - `now`: Present Unix time
+///
+/// # Returns
+///
+/// The location, or an i3blocks error string
+fn resolve(
+    config: &Config,
+    client: &dyn HttpClient,
+    cache: &mut Cache,
+    location: &StaticLocation,
+    now: i64,
+) -> Result<GeoLocation, String> {
+    let geocoding = &config.endpoints.geocoding;
+    if let StaticLocation::Coordinates { .. } = location {
+        return location.resolve(client, geocoding, config.log_geolocation);
+    }
+
+    let place = location.to_string();
+    match cache.geocoded(&place) {
+        Some(v) => Ok(v),
+        None => {
+            let resolved = location.resolve(client, geocoding, config.log_geolocation)?;
+            cache.set_geocoded(&place, &resolved, now);
+            Ok(resolved)
+        }
+    }
+}
+
+/// Locates the external IP, reusing fresh cached values
+///
+/// While the external IP or its location can't be fetched, e.g. offline or
+/// behind a captive portal, the last located position stands in for it.
+///
+/// # Arguments
+///
+/// - `config`: Parsed configuration
+/// - `client`: Client requests are sent with
+/// - `cache`: Cache updated with every fetched value
+/// - `now`: Present Unix time
+///
+/// # Returns
+///
+/// The location, or an i3blocks error string
+fn geolocate(
+    config: &Config,
+    client: &dyn HttpClient,
+    cache: &mut Cache,
+    now: i64,
+) -> Result<GeoLocation, String> {
+    // Fetch external IP
+    let ipv4 = match cache.ip(now, config.cache.ip_ttl) {
+        Some(v) => v,
+        None => {
+            let ipv4 = match weather::IPv4::new(client, &config.endpoints.ip, config.log_ip) {
+                Ok(v) => v,
+                Err(e) => return cache.last_location().ok_or(e),
+            };
+            cache.set_ip(&ipv4, now);
+            ipv4
+        }
+    };
+
+    // Fetch geolocation based on IP
+    match cache.location(&ipv4) {
+        Some(v) => Ok(v),
+        None => {
+            let location = match weather::GeoLocation::new(
+                client,
+                &config.endpoints.geolocation,
+                ipv4.clone(),
+                config.log_geolocation,
+            ) {
+                Ok(v) => v,
+                Err(e) => return cache.last_location().ok_or(e),
+            };
+            cache.set_location(&ipv4, &location, now);
+            Ok(location)
+        }
+    }
+}
+
+/// Locates the block, reusing fresh cached values
+///
+/// # Arguments
+///
+/// - `config`: Parsed configuration
+/// - `client`: Client requests are sent with
+/// - `cache`: Cache updated with every fetched value
+/// - `now`: Present Unix time
+///
+/// # Returns
+///
+/// The configured location, or the location of the external IP
+fn locate(
+    config: &Config,
+    client: &dyn HttpClient,
+    cache: &mut Cache,
+    now: i64,
+) -> Result<GeoLocation, String> {
+    match &config.location {
+        Some(v) => resolve(config, client, cache, v, now),
+        None => geolocate(config, client, cache, now),
+    }
+}
+
+/// Fetches the current weather, reusing a fresh cached report
+///
+/// The forecast is only fetched if a configured view needs it. A report whose
+/// forecast couldn't be fetched is still shown and cached, so the forecast is
+/// only retried once the report expires.
+///
+/// # Arguments
+///
+/// - `config`: Parsed configuration
+/// - `client`: Client requests are sent with
+/// - `cache`: Cache updated with the fetched report
+/// - `location`: Location to report the weather of
+/// - `now`: Present Unix time
+///
+/// # Returns
+///
+/// The current weather, a stale report if fetching fails, or an i3blocks
+/// error string
+fn fetch(
+    config: &Config,
+    client: &dyn HttpClient,
+    cache: &mut Cache,
+    location: &GeoLocation,
+    now: i64,
+) -> Result<WeatherReport, String> {
+    let origin = Origin::new(location, (config.provider, config.needs_forecast()));
+    if let Some(v) = cache.report(&origin, now, config.cache.report_ttl) {
+        return Ok(v);
+    }
+
+    // Fetch weather report
+    let fetched = weather::Provider::build(config).and_then(|provider| {
+        let mut report = provider.current(client, location)?;
+        if config.needs_forecast() {
+            report.forecast = provider.forecast(client, location).ok();
+        }
+        Ok(report)
+    });
+    match fetched {
+        Ok(v) => {
+            cache.set_report(&origin, &v, now);
+            Ok(v)
+        }
+        Err(e) => cache
+            .stale_report(&origin, now, config.cache.stale_ttl)
+            .ok_or(e),
+    }
+}
+
+/// Fetches the current air quality, reusing a fresh cached value
+///
+/// # Arguments
+///
+/// - `config`: Parsed configuration
+/// - `client`: Client requests are sent with
+/// - `cache`: Cache updated with the fetched air quality
+/// - `location`: Location to report the air quality of
+/// - `now`: Present Unix time
+///
+/// # Returns
+///
+/// The air quality, a stale one if fetching fails, or an i3blocks error
+/// string
+fn air_quality(
+    config: &Config,
+    client: &dyn HttpClient,
+    cache: &mut Cache,
+    location: &GeoLocation,
+    now: i64,
+) -> Result<AirQuality, String> {
+    let origin = Origin::new(location, config.air_quality.source);
+    if let Some(v) = cache.air_quality(&origin, now, config.cache.air_quality_ttl) {
+        return Ok(v);
+    }
+
+    let fetched = weather::AirQualitySource::build(config)
+        .and_then(|provider| provider.air_quality(client, location));
+    match fetched {
+        Ok(v) => {
+            cache.set_air_quality(&origin, &v, now);
+            Ok(v)
+        }
+        Err(e) => cache
+            .stale_air_quality(&origin, now, config.cache.stale_ttl)
+            .ok_or(e),
+    }
+}
+
+/// Displays the air quality block
+fn air_quality_block(config: &Config, client: &dyn HttpClient) -> ! {
+    let now = Utc::now().timestamp();
+    let path = config.cache.path.as_ref();
+    let mut cache = path.map(|v| Cache::load(v)).unwrap_or_default();
+
+    let air_quality = locate(config, client, &mut cache, now)
+        .and_then(|location| air_quality(config, client, &mut cache, &location, now));
+    if let Some(path) = path {
+        cache.save(path);
+    }
+
+    match air_quality {
+        Ok(mut air_quality) => {
+            air_quality.set_scale(config.air_quality.scale);
+            if config.log_weather_report {
+                log::info!("weather::main: air quality is {}", air_quality);
+            }
+            println!("{}", air_quality.format_i3());
+        }
+        Err(e) => println!("{}", e),
+    }
+    std::process::exit(0);
+}
+
+/// Fetches the active alerts, reusing fresh cached values
+///
+/// Newly issued alerts are logged in full. When fetching fails, the cached
+/// alerts are kept until they expire.
+///
+/// # Arguments
+///
+/// - `config`: Parsed configuration
+/// - `client`: Client requests are sent with
+/// - `cache`: Cache updated with the fetched alerts
+/// - `location`: Location to report the alerts of
+/// - `now`: Present Unix time
+///
+/// # Returns
+///
+/// The active alerts, none if alerts are disabled
+fn alerts(
+    config: &Config,
+    client: &dyn HttpClient,
+    cache: &mut Cache,
+    location: &GeoLocation,
+    now: i64,
+) -> Vec<Alert> {
+    let (source, provider) = match (config.alerts.source, weather::AlertSource::build(config)) {
+        (Some(source), Ok(Some(provider))) => (source, provider),
+        _ => return Vec::new(),
+    };
+    // MeteoAlarm alerts depend on the configured feed and area
+    let area = (config.alerts.country.clone(), config.alerts.region.clone());
+    let origin = Origin::new(location, (source, area.0, area.1));
+    let time = DateTime::from_timestamp(now, 0).unwrap_or_default();
+
+    if let Some(v) = cache.alerts(&origin, now, config.cache.alerts_ttl) {
+        return Alert::prune(v, time);
+    }
+
+    let alerts = match provider.alerts(client, location) {
+        Ok(v) => v,
+        Err(_) => return Alert::prune(cache.stale_alerts(&origin), time),
+    };
+
+    let known = cache.stale_alerts(&origin);
+    cache.set_alerts(&origin, &alerts, now);
+    let alerts = Alert::prune(alerts, time);
+    for alert in alerts
+        .iter()
+        .filter(|v| !known.iter().any(|k| k.id == v.id))
+    {
+        log::warn!("weather::main: alert {}", alert);
+    }
+
+    alerts
+}
+
+/// Parses the configuration file and creates the HTTP client
+///
+/// Prints the error and exits if either fails.
+fn setup(path: &Path) -> (Config, weather::ReqwestClient) {
+    let config = match Config::new(path) {
         Ok(v) => v,
         Err(e) => {
             println!("{}", e);
@@ -12,32 +301,111 @@ fn main() {
         }
     };
 
-    // Fetch external IP
-    let ipv4 = match weather::IPv4::new(config.log_ip) {
+    let timeout = std::time::Duration::from_secs(config.request_timeout);
+    let client = match weather::ReqwestClient::new(timeout) {
         Ok(v) => v,
         Err(e) => {
-            println!("{}", e);
+            log::error!("weather::main: {}", e);
+            println!("{}", weather::DEFAULT_ERROR);
             std::process::exit(0);
         }
     };
 
-    // Fetch geolocation based on IP
-    let location = match weather::GeoLocation::new(ipv4, config.log_geolocation) {
-        Ok(v) => v,
+    (config, client)
+}
+
+/// Prints the hourly and daily forecasts to the terminal
+fn forecast(path: &Path) -> ! {
+    let (config, client) = setup(path);
+    let now = Utc::now().timestamp();
+    let path = config.cache.path.as_ref();
+    let mut cache = path.map(|v| Cache::load(v)).unwrap_or_default();
+
+    let forecast = locate(&config, &client, &mut cache, now)
+        .and_then(|location| weather::Provider::build(&config)?.forecast(&client, &location));
+    if let Some(path) = path {
+        cache.save(path);
+    }
+
+    match forecast {
+        Ok(v) => {
+            let offset = *Local::now().offset();
+            print!("{}", v.table(config.temperature_scale, offset));
+            std::process::exit(0);
+        }
         Err(e) => {
             println!("{}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn main() {
+    // Parse arguments
+    let args: Vec<String> = std::env::args().collect();
+    let config_path = match weather::Command::new(&args) {
+        Ok(weather::Command::Block(v)) => v,
+        Ok(weather::Command::Forecast(v)) => forecast(&v),
+        Err(e) => {
+            println!("{}", e);
+            eprintln!("{}", weather::USAGE);
             std::process::exit(0);
         }
     };
 
-    // Fetch weather report
-    match weather::OpenWeatherReport::new(
-        &location,
-        config.open_weather_api_key,
-        config.temperature_scale,
-        config.log_weather_report,
-    ) {
-        Ok(report) => println!("{}", report.format_i3()),
+    let (config, client) = setup(&config_path);
+    if config.mode == weather::Mode::AirQuality {
+        air_quality_block(&config, &client);
+    }
+
+    let now = Utc::now().timestamp();
+    let path = config.cache.path.as_ref();
+    let mut cache = path.map(|v| Cache::load(v)).unwrap_or_default();
+
+    // Cycle the view on left click
+    let click: Option<u8> = std::env::var("BLOCK_BUTTON")
+        .ok()
+        .and_then(|v| v.parse().ok());
+    if click == Some(weather::LEFT_BUTTON) || !config.views.contains(&cache.view) {
+        cache.view = cache.view.next(&config.views);
+    }
+
+    // Locate the block, the cached values are only reused for its location
+    let location = locate(&config, &client, &mut cache, now);
+
+    // Cycle the displayed alert on right click
+    let alerts = match &location {
+        Ok(v) => alerts(&config, &client, &mut cache, v, now),
+        Err(_) => Vec::new(),
+    };
+    if click == Some(weather::RIGHT_BUTTON) {
+        cache.alert += 1;
+    }
+    cache.alert %= alerts.len().max(1);
+
+    // Serve a fresh report, or fetch one and fall back to a stale report
+    let report = location.and_then(|v| fetch(&config, &client, &mut cache, &v, now));
+    if let Some(path) = path {
+        cache.save(path);
+    }
+
+    match report {
+        Ok(mut report) => {
+            let local = Local::now();
+            report.set_scale(config.temperature_scale);
+            report.set_formatter(config.formatter.clone());
+            report.set_view(cache.view, local.with_timezone(local.offset()));
+            report.set_alert(Alert::label(&alerts, cache.alert, &config.alerts.label));
+            if config.log_weather_report {
+                log::info!("weather::main: current weather is {}", report);
+            }
+            println!("{}", report.format_i3());
+
+            // Set the urgent flag through the i3blocks exit code
+            if !alerts.is_empty() {
+                std::process::exit(URGENT_EXIT_CODE);
+            }
+        }
         Err(e) => {
             println!("{}", e);
             std::process::exit(0);
@@ -0,0 +1,179 @@
+use chrono::{DateTime, Utc};
+use log::error;
+use serde::Deserialize;
+
+use crate::alert::{Alert, AlertProvider};
+use crate::provider;
+use crate::{GeoLocation, HttpClient, DEFAULT_ERROR};
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Area {
+    area_desc: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Info {
+    #[serde(default)]
+    language: String,
+
+    event: String,
+    sender_name: Option<String>,
+
+    #[serde(default)]
+    description: String,
+
+    onset: Option<DateTime<Utc>>,
+    effective: Option<DateTime<Utc>>,
+    expires: Option<DateTime<Utc>>,
+
+    #[serde(default)]
+    area: Vec<Area>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CapAlert {
+    identifier: String,
+    status: String,
+    msg_type: String,
+
+    #[serde(default)]
+    info: Vec<Info>,
+}
+
+#[derive(Deserialize)]
+struct Warning {
+    alert: CapAlert,
+}
+
+#[derive(Deserialize)]
+struct Response {
+    #[serde(default)]
+    warnings: Vec<Warning>,
+}
+
+/// MeteoAlarm warnings of a European country, which require no API key
+///
+/// Warnings are issued for the regions of a country rather than coordinates,
+/// so the region is configured by name.
+pub struct MeteoAlarm {
+    base_url: String, // base URL of the feeds
+    country: String,  // feed name, e.g. `germany`
+    region: String,   // area description, e.g. `Berlin`
+}
+
+impl MeteoAlarm {
+    /// Creates the provider
+    ///
+    /// # Arguments
+    ///
+    /// - `base_url`: Base URL of the feeds
+    /// - `country`: Feed name of the country, e.g. `germany`
+    /// - `region`: Area description of the region, e.g. `Berlin`
+    pub fn new(base_url: &str, country: &str, region: &str) -> MeteoAlarm {
+        MeteoAlarm {
+            base_url: base_url.into(),
+            country: country.to_lowercase(),
+            region: region.into(),
+        }
+    }
+
+    fn url(&self) -> String {
+        let path = format!("/api/v1/warnings/feeds-{}", self.country);
+        provider::join(&self.base_url, &path)
+    }
+}
+
+/// Parses the warnings feed of a country
+///
+/// Each warning has an info block per language, of which the English one is
+/// preferred. Test messages and cancellations are skipped.
+///
+/// # Arguments
+///
+/// - `body`: JSON response body
+/// - `region`: Area description the warnings must cover
+///
+/// # Returns
+///
+/// The alerts of the region, or a description of why the body is invalid
+pub(crate) fn parse(body: &str, region: &str) -> Result<Vec<Alert>, String> {
+    let resp: Response = serde_json::from_str(body).map_err(|e| e.to_string())?;
+
+    let alerts = resp
+        .warnings
+        .into_iter()
+        .map(|v| v.alert)
+        .filter(|v| v.status == "Actual" && v.msg_type != "Cancel")
+        .filter_map(|alert| {
+            let mut infos: Vec<Info> = alert
+                .info
+                .into_iter()
+                .filter(|v| {
+                    v.area
+                        .iter()
+                        .any(|v| v.area_desc.eq_ignore_ascii_case(region))
+                })
+                .collect();
+            if infos.is_empty() {
+                return None;
+            }
+
+            let i = infos
+                .iter()
+                .position(|v| v.language.starts_with("en"))
+                .unwrap_or(0);
+            let info = infos.swap_remove(i);
+            Some(Alert {
+                id: alert.identifier,
+                event: info.event,
+                sender: info.sender_name,
+                description: info.description,
+                start: info.onset.or(info.effective),
+                end: info.expires,
+            })
+        })
+        .collect();
+
+    Ok(alerts)
+}
+
+impl AlertProvider for MeteoAlarm {
+    fn alerts(
+        &self,
+        client: &dyn HttpClient,
+        _location: &GeoLocation,
+    ) -> Result<Vec<Alert>, String> {
+        client
+            .get(&self.url())
+            .map_err(|e| e.to_string())
+            .and_then(|body| parse(&body, &self.region))
+            .map_err(|e| {
+                error!("weather::MeteoAlarm::alerts: {}", e);
+                DEFAULT_ERROR.into()
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let body = std::fs::read_to_string("tests/meteoalarm/feed.json").unwrap();
+        let alerts = parse(&body, "berlin").unwrap();
+
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].event, "Strong wind gusts");
+        assert_eq!(alerts[0].sender.as_deref(), Some("Deutscher Wetterdienst"));
+        assert_eq!(
+            alerts[0].end.unwrap().to_rfc3339(),
+            "2026-10-18T20:00:00+00:00"
+        );
+
+        assert!(parse(&body, "Hamburg").unwrap().is_empty());
+    }
+}
@@ -0,0 +1,302 @@
+use log::error;
+use serde::Deserialize;
+
+use chrono::{DateTime, FixedOffset, Local, Offset, Utc};
+
+use crate::forecast::Hour;
+use crate::provider::{self, WeatherProvider};
+use crate::sun;
+use crate::{Condition, Forecast, GeoLocation, HttpClient, WeatherReport, DEFAULT_ERROR};
+
+// Symbol codes without their `_day`, `_night` or `_polartwilight` variant,
+// matched in order, see https://api.met.no/weatherapi/weathericon/2.0/
+const CONDITIONS: &[(&str, Condition)] = &[
+    ("thunder", Condition::Thunderstorm),
+    ("sleet", Condition::Sleet),
+    ("snow", Condition::Snow),
+    ("rainshowers", Condition::Showers),
+    ("rain", Condition::Rain),
+    ("fog", Condition::Fog),
+    ("partlycloudy", Condition::PartlyCloudy),
+    ("fair", Condition::PartlyCloudy),
+    ("cloudy", Condition::Cloudy),
+    ("clearsky", Condition::Clear),
+];
+
+#[derive(Deserialize)]
+struct InstantDetails {
+    air_temperature: f64,
+    air_pressure_at_sea_level: Option<f64>,
+    cloud_area_fraction: Option<f64>,
+    relative_humidity: Option<f64>,
+    wind_from_direction: Option<f64>,
+    wind_speed: Option<f64>,
+}
+
+#[derive(Deserialize)]
+struct Instant {
+    details: InstantDetails,
+}
+
+#[derive(Deserialize)]
+struct Summary {
+    symbol_code: String,
+}
+
+#[derive(Deserialize)]
+struct PeriodDetails {
+    precipitation_amount: Option<f64>,
+}
+
+#[derive(Deserialize)]
+struct Period {
+    summary: Option<Summary>,
+    details: Option<PeriodDetails>,
+}
+
+#[derive(Deserialize)]
+struct Data {
+    instant: Instant,
+    next_1_hours: Option<Period>,
+    next_6_hours: Option<Period>,
+}
+
+#[derive(Deserialize)]
+struct Step {
+    time: DateTime<Utc>,
+    data: Data,
+}
+
+#[derive(Deserialize)]
+struct Properties {
+    timeseries: Vec<Step>,
+}
+
+#[derive(Deserialize)]
+struct Response {
+    properties: Properties,
+}
+
+/// MET Norway locationforecast, which requires no API key
+pub struct MetNorway {
+    base_url: String, // base URL of the API
+}
+
+impl MetNorway {
+    /// Creates the provider
+    ///
+    /// # Arguments
+    ///
+    /// - `base_url`: Base URL of the API
+    pub fn new(base_url: &str) -> MetNorway {
+        MetNorway {
+            base_url: base_url.into(),
+        }
+    }
+
+    fn url(&self, location: &GeoLocation) -> String {
+        // The terms of service ask for at most four decimals
+        let path = format!(
+            "/weatherapi/locationforecast/2.0/compact?lat={:.4}&lon={:.4}",
+            location.lat, location.lon
+        );
+        provider::join(&self.base_url, &path)
+    }
+}
+
+/// Maps a symbol code onto a `Condition`
+fn condition(symbol: &str) -> Condition {
+    let symbol = symbol.split('_').next().unwrap_or_default();
+    CONDITIONS
+        .iter()
+        .find(|(name, _)| symbol.contains(name))
+        .map(|(_, condition)| *condition)
+        .unwrap_or(Condition::Unknown)
+}
+
+/// Returns the condition of a period, if it has a summary
+fn period_condition(period: Option<&Period>) -> Option<Condition> {
+    period
+        .and_then(|v| v.summary.as_ref())
+        .map(|v| condition(&v.symbol_code))
+}
+
+/// Parses a compact locationforecast response
+///
+/// The API doesn't report sunrise and sunset, which are computed from the
+/// location instead.
+///
+/// # Arguments
+///
+/// - `body`: Response body
+/// - `location`: Location the forecast is of
+///
+/// # Returns
+///
+/// The neutral report of the first step, or a description of why the body
+/// is invalid
+pub(crate) fn parse(body: &str, location: &GeoLocation) -> Result<WeatherReport, String> {
+    let resp: Response = serde_json::from_str(body).map_err(|e| e.to_string())?;
+    let (time, step) = match resp.properties.timeseries.into_iter().next() {
+        Some(v) => (v.time, v.data),
+        None => return Err("forecast has no timeseries".into()),
+    };
+
+    let instant = step.instant.details;
+    let next_hour = step.next_1_hours;
+
+    let mut report = WeatherReport::new(
+        instant.air_temperature,
+        period_condition(next_hour.as_ref()).unwrap_or(Condition::Unknown),
+    );
+    report.humidity = instant.relative_humidity;
+    report.wind_speed = instant.wind_speed;
+    report.wind_direction = instant.wind_from_direction;
+    report.pressure = instant.air_pressure_at_sea_level;
+    report.clouds = instant.cloud_area_fraction;
+    report.precipitation = next_hour
+        .and_then(|v| v.details)
+        .and_then(|v| v.precipitation_amount);
+
+    if let Some((sunrise, sunset)) = sun::sun_times(location.lat, location.lon, time.date_naive()) {
+        report.sunrise = Some(sunrise);
+        report.sunset = Some(sunset);
+    }
+
+    Ok(report)
+}
+
+/// Parses the forecast of a compact locationforecast response
+///
+/// Steps are hourly for about two days and 6 hourly afterwards, and the days
+/// are derived from them.
+///
+/// # Arguments
+///
+/// - `body`: Response body
+/// - `offset`: Offset of the local time zone, which days are split by
+///
+/// # Returns
+///
+/// The forecast, or a description of why the body is invalid
+pub(crate) fn parse_forecast(body: &str, offset: FixedOffset) -> Result<Forecast, String> {
+    let resp: Response = serde_json::from_str(body).map_err(|e| e.to_string())?;
+
+    let steps = resp
+        .properties
+        .timeseries
+        .into_iter()
+        .map(|step| {
+            // Prefer the shortest period a step summarises
+            let data = step.data;
+            let period = data.next_1_hours.or(data.next_6_hours);
+            Hour {
+                time: step.time,
+                temperature: data.instant.details.air_temperature,
+                condition: period_condition(period.as_ref()).unwrap_or(Condition::Unknown),
+                precipitation: period
+                    .and_then(|v| v.details)
+                    .and_then(|v| v.precipitation_amount),
+            }
+        })
+        .collect();
+
+    Ok(Forecast::from_steps(steps, offset))
+}
+
+impl WeatherProvider for MetNorway {
+    fn current(
+        &self,
+        client: &dyn HttpClient,
+        location: &GeoLocation,
+    ) -> Result<WeatherReport, String> {
+        client
+            .get(&self.url(location))
+            .map_err(|e| e.to_string())
+            .and_then(|body| parse(&body, location))
+            .map_err(|e| {
+                error!("weather::MetNorway::current: {}", e);
+                DEFAULT_ERROR.into()
+            })
+    }
+
+    fn forecast(
+        &self,
+        client: &dyn HttpClient,
+        location: &GeoLocation,
+    ) -> Result<Forecast, String> {
+        let offset = Local::now().offset().fix();
+        client
+            .get(&self.url(location))
+            .map_err(|e| e.to_string())
+            .and_then(|body| parse_forecast(&body, offset))
+            .map_err(|e| {
+                error!("weather::MetNorway::forecast: {}", e);
+                DEFAULT_ERROR.into()
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let body = std::fs::read_to_string("tests/metno/compact.json").unwrap();
+        let location = GeoLocation::from_coordinates(37.77, -122.42).unwrap();
+        let report = parse(&body, &location).unwrap();
+
+        assert_eq!(report.temperature, 12.3);
+        assert_eq!(report.feels_like, None);
+        assert_eq!(report.condition, Condition::Rain);
+        assert_eq!(report.humidity, Some(81.0));
+        assert_eq!(report.wind_speed, Some(4.1));
+        assert_eq!(report.wind_direction, Some(250.0));
+        assert_eq!(report.pressure, Some(1012.4));
+        assert_eq!(report.precipitation, Some(0.4));
+        assert_eq!(report.clouds, Some(75.0));
+        let date = chrono::NaiveDate::from_ymd_opt(2026, 10, 18).unwrap();
+        let (sunrise, sunset) = sun::sun_times(37.77, -122.42, date).unwrap();
+        assert_eq!(report.sunrise, Some(sunrise));
+        assert_eq!(report.sunset, Some(sunset));
+        assert_eq!(
+            report.is_day("2026-10-18T20:00:00Z".parse().unwrap()),
+            Some(true)
+        );
+    }
+
+    #[test]
+    fn test_parse_forecast() {
+        let body = std::fs::read_to_string("tests/metno/forecast.json").unwrap();
+        let offset = FixedOffset::east_opt(2 * 3600).unwrap();
+        let forecast = parse_forecast(&body, offset).unwrap();
+
+        assert_eq!(forecast.hourly.len(), 48);
+        assert_eq!(forecast.hourly[0].condition, Condition::Rain);
+        assert_eq!(forecast.hourly[0].precipitation, Some(0.4));
+
+        assert_eq!(forecast.daily.len(), 7);
+        assert_eq!(forecast.daily[0].date.to_string(), "2026-10-18");
+        assert_eq!(forecast.daily[0].high, 15.2);
+        assert_eq!(forecast.daily[3].condition, Condition::Snow);
+        assert_eq!(forecast.daily[3].precipitation, Some(6.0));
+    }
+
+    #[test]
+    fn test_conditions() {
+        assert_eq!(condition("clearsky_day"), Condition::Clear);
+        assert_eq!(condition("fair_night"), Condition::PartlyCloudy);
+        assert_eq!(
+            condition("heavyrainshowersandthunder_day"),
+            Condition::Thunderstorm
+        );
+        assert_eq!(
+            condition("lightrainshowers_polartwilight"),
+            Condition::Showers
+        );
+        assert_eq!(condition("lightsleet"), Condition::Sleet);
+        assert_eq!(condition("cloudy"), Condition::Cloudy);
+        assert_eq!(condition("sandstorm"), Condition::Unknown);
+    }
+}
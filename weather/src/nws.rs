@@ -0,0 +1,141 @@
+use chrono::{DateTime, Utc};
+use log::error;
+use serde::Deserialize;
+
+use crate::alert::{Alert, AlertProvider};
+use crate::provider;
+use crate::{GeoLocation, HttpClient, DEFAULT_ERROR};
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Properties {
+    id: String,
+    event: String,
+    sender_name: Option<String>,
+
+    #[serde(default)]
+    description: String,
+
+    onset: Option<DateTime<Utc>>,
+    effective: Option<DateTime<Utc>>,
+    ends: Option<DateTime<Utc>>,
+    expires: Option<DateTime<Utc>>,
+}
+
+#[derive(Deserialize)]
+struct Feature {
+    properties: Properties,
+}
+
+#[derive(Deserialize)]
+struct Response {
+    features: Vec<Feature>,
+}
+
+/// US National Weather Service alerts, which require no API key
+pub struct Nws {
+    base_url: String, // base URL of the API
+}
+
+impl Nws {
+    /// Creates the provider
+    ///
+    /// # Arguments
+    ///
+    /// - `base_url`: Base URL of the API
+    pub fn new(base_url: &str) -> Nws {
+        Nws {
+            base_url: base_url.into(),
+        }
+    }
+
+    fn url(&self, location: &GeoLocation) -> String {
+        // The API rejects points with more than four decimals
+        let path = format!(
+            "/alerts/active?point={:.4},{:.4}",
+            location.lat, location.lon
+        );
+        provider::join(&self.base_url, &path)
+    }
+}
+
+/// Parses an active alerts response
+///
+/// # Arguments
+///
+/// - `body`: GeoJSON response body
+///
+/// # Returns
+///
+/// The alerts, or a description of why the body is invalid
+pub(crate) fn parse(body: &str) -> Result<Vec<Alert>, String> {
+    let resp: Response = serde_json::from_str(body).map_err(|e| e.to_string())?;
+
+    let alerts = resp
+        .features
+        .into_iter()
+        .map(|v| v.properties)
+        .map(|v| Alert {
+            id: v.id,
+            event: v.event,
+            sender: v.sender_name,
+            description: v.description,
+            start: v.onset.or(v.effective),
+            end: v.ends.or(v.expires),
+        })
+        .collect();
+
+    Ok(alerts)
+}
+
+impl AlertProvider for Nws {
+    fn alerts(
+        &self,
+        client: &dyn HttpClient,
+        location: &GeoLocation,
+    ) -> Result<Vec<Alert>, String> {
+        client
+            .get(&self.url(location))
+            .map_err(|e| e.to_string())
+            .and_then(|body| parse(&body))
+            .map_err(|e| {
+                error!("weather::Nws::alerts: {}", e);
+                DEFAULT_ERROR.into()
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let body = std::fs::read_to_string("tests/nws/alerts.json").unwrap();
+        let alerts = parse(&body).unwrap();
+
+        assert_eq!(alerts.len(), 2);
+        assert_eq!(alerts[0].event, "Wind Advisory");
+        assert_eq!(alerts[0].sender.as_deref(), Some("NWS San Francisco CA"));
+        assert_eq!(
+            alerts[0].start.unwrap().to_rfc3339(),
+            "2026-10-18T17:00:00+00:00"
+        );
+        assert_eq!(
+            alerts[0].end.unwrap().to_rfc3339(),
+            "2026-10-19T04:00:00+00:00"
+        );
+
+        // Without an end, alerts expire with the message
+        assert_eq!(
+            alerts[1].end.unwrap().to_rfc3339(),
+            "2026-10-19T01:00:00+00:00"
+        );
+    }
+
+    #[test]
+    fn test_parse_empty() {
+        let alerts = parse("{\"type\": \"FeatureCollection\", \"features\": []}").unwrap();
+        assert!(alerts.is_empty());
+    }
+}
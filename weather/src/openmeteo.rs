@@ -0,0 +1,422 @@
+use log::error;
+use serde::Deserialize;
+
+use crate::air::{AirQuality, AirQualityProvider};
+use crate::aqi::Concentrations;
+use crate::forecast::{self, Day, Hour, HOURLY_HOURS};
+use crate::provider::{self, WeatherProvider};
+use crate::{Condition, Forecast, GeoLocation, HttpClient, WeatherReport, DEFAULT_ERROR};
+
+// WMO weather interpretation codes, see https://open-meteo.com/en/docs
+const CONDITIONS: &[(u32, u32, Condition)] = &[
+    (0, 0, Condition::Clear),
+    (1, 2, Condition::PartlyCloudy),
+    (3, 3, Condition::Cloudy),
+    (45, 48, Condition::Fog),
+    (51, 55, Condition::Drizzle),
+    (56, 57, Condition::FreezingRain),
+    (61, 65, Condition::Rain),
+    (66, 67, Condition::FreezingRain),
+    (71, 77, Condition::Snow),
+    (80, 82, Condition::Showers),
+    (85, 86, Condition::Snow),
+    (95, 99, Condition::Thunderstorm),
+];
+
+// Current variables requested from the forecast endpoint
+const CURRENT: &str = "temperature_2m,apparent_temperature,relative_humidity_2m,weather_code,\
+                       wind_speed_10m,wind_direction_10m,pressure_msl,precipitation,cloud_cover,\
+                       visibility,uv_index";
+
+// Daily variables requested along with the current ones
+const CURRENT_DAILY: &str = "sunrise,sunset";
+
+// Hourly and daily variables requested from the forecast endpoint
+const HOURLY: &str = "temperature_2m,weather_code,precipitation";
+const DAILY: &str = "weather_code,temperature_2m_max,temperature_2m_min,precipitation_sum";
+
+// Current variables requested from the air quality endpoint
+const AIR_QUALITY: &str = "pm2_5,pm10,ozone,nitrogen_dioxide,sulphur_dioxide,carbon_monoxide,\
+                           alder_pollen,birch_pollen,grass_pollen,mugwort_pollen,olive_pollen,\
+                           ragweed_pollen";
+
+#[derive(Deserialize)]
+struct Current {
+    temperature_2m: f64,
+    apparent_temperature: Option<f64>,
+    relative_humidity_2m: Option<f64>,
+    weather_code: Option<u32>,
+    wind_speed_10m: Option<f64>,
+    wind_direction_10m: Option<f64>,
+    pressure_msl: Option<f64>,
+    precipitation: Option<f64>,
+    cloud_cover: Option<f64>,
+    visibility: Option<f64>,
+    uv_index: Option<f64>,
+}
+
+#[derive(Deserialize)]
+struct Sun {
+    sunrise: Vec<i64>,
+    sunset: Vec<i64>,
+}
+
+#[derive(Deserialize)]
+struct Response {
+    current: Current,
+    daily: Option<Sun>,
+}
+
+// Pollen counts are only modelled for Europe and null elsewhere
+#[derive(Deserialize)]
+struct CurrentAirQuality {
+    pm2_5: Option<f64>,
+    pm10: Option<f64>,
+    ozone: Option<f64>,
+    nitrogen_dioxide: Option<f64>,
+    sulphur_dioxide: Option<f64>,
+    carbon_monoxide: Option<f64>,
+    alder_pollen: Option<f64>,
+    birch_pollen: Option<f64>,
+    grass_pollen: Option<f64>,
+    mugwort_pollen: Option<f64>,
+    olive_pollen: Option<f64>,
+    ragweed_pollen: Option<f64>,
+}
+
+#[derive(Deserialize)]
+struct AirQualityResponse {
+    current: CurrentAirQuality,
+}
+
+#[derive(Deserialize)]
+struct Hourly {
+    time: Vec<i64>,
+    temperature_2m: Vec<Option<f64>>,
+    weather_code: Vec<Option<u32>>,
+    precipitation: Vec<Option<f64>>,
+}
+
+#[derive(Deserialize)]
+struct Daily {
+    time: Vec<i64>,
+    weather_code: Vec<Option<u32>>,
+    temperature_2m_max: Vec<Option<f64>>,
+    temperature_2m_min: Vec<Option<f64>>,
+    precipitation_sum: Vec<Option<f64>>,
+}
+
+#[derive(Deserialize)]
+struct ForecastResponse {
+    utc_offset_seconds: i64,
+    hourly: Hourly,
+    daily: Daily,
+}
+
+/// Open-Meteo forecast, which requires no API key
+pub struct OpenMeteo {
+    base_url: String, // base URL of the API
+}
+
+impl OpenMeteo {
+    /// Creates the provider
+    ///
+    /// # Arguments
+    ///
+    /// - `base_url`: Base URL of the API
+    pub fn new(base_url: &str) -> OpenMeteo {
+        OpenMeteo {
+            base_url: base_url.into(),
+        }
+    }
+
+    fn url(&self, location: &GeoLocation) -> String {
+        let path = format!(
+            "/v1/forecast?latitude={}&longitude={}&current={}&daily={}&wind_speed_unit=ms\
+             &forecast_days=1&timezone=auto&timeformat=unixtime",
+            location.lat, location.lon, CURRENT, CURRENT_DAILY
+        );
+        provider::join(&self.base_url, &path)
+    }
+
+    fn forecast_url(&self, location: &GeoLocation) -> String {
+        let path = format!(
+            "/v1/forecast?latitude={}&longitude={}&hourly={}&daily={}&forecast_hours={}\
+             &forecast_days={}&timezone=auto&timeformat=unixtime",
+            location.lat,
+            location.lon,
+            HOURLY,
+            DAILY,
+            HOURLY_HOURS,
+            forecast::DAILY_DAYS
+        );
+        provider::join(&self.base_url, &path)
+    }
+}
+
+/// Open-Meteo air quality, which requires no API key
+///
+/// The air quality API is served from its own host, so it's a separate
+/// provider from the forecast.
+pub struct OpenMeteoAirQuality {
+    base_url: String, // base URL of the API
+}
+
+impl OpenMeteoAirQuality {
+    /// Creates the provider
+    ///
+    /// # Arguments
+    ///
+    /// - `base_url`: Base URL of the API
+    pub fn new(base_url: &str) -> OpenMeteoAirQuality {
+        OpenMeteoAirQuality {
+            base_url: base_url.into(),
+        }
+    }
+
+    fn url(&self, location: &GeoLocation) -> String {
+        let path = format!(
+            "/v1/air-quality?latitude={}&longitude={}&current={}&timeformat=unixtime",
+            location.lat, location.lon, AIR_QUALITY
+        );
+        provider::join(&self.base_url, &path)
+    }
+}
+
+/// Parses a forecast response
+///
+/// # Arguments
+///
+/// - `body`: Response body with wind speeds in m/s and Unix times
+///
+/// # Returns
+///
+/// The neutral report, or a description of why the body is invalid
+pub(crate) fn parse(body: &str) -> Result<WeatherReport, String> {
+    let resp: Response = serde_json::from_str(body).map_err(|e| e.to_string())?;
+    let current = resp.current;
+
+    let condition = current
+        .weather_code
+        .map(|v| provider::lookup(CONDITIONS, v))
+        .unwrap_or(Condition::Unknown);
+
+    let mut report = WeatherReport::new(current.temperature_2m, condition);
+    report.feels_like = current.apparent_temperature;
+    report.humidity = current.relative_humidity_2m;
+    report.wind_speed = current.wind_speed_10m;
+    report.wind_direction = current.wind_direction_10m;
+    report.pressure = current.pressure_msl;
+    report.precipitation = current.precipitation;
+    report.clouds = current.cloud_cover;
+    report.visibility = current.visibility;
+    report.uv_index = current.uv_index;
+    if let Some(sun) = resp.daily {
+        report.sunrise = sun.sunrise.first().copied().map(forecast::unix);
+        report.sunset = sun.sunset.first().copied().map(forecast::unix);
+    }
+
+    Ok(report)
+}
+
+/// Parses a forecast response of hourly and daily variables
+///
+/// # Arguments
+///
+/// - `body`: Response body with Unix times
+///
+/// # Returns
+///
+/// The forecast, or a description of why the body is invalid
+pub(crate) fn parse_forecast(body: &str) -> Result<Forecast, String> {
+    let resp: ForecastResponse = serde_json::from_str(body).map_err(|e| e.to_string())?;
+    let condition = |v: Option<&Option<u32>>| match v {
+        Some(Some(v)) => provider::lookup(CONDITIONS, *v),
+        _ => Condition::Unknown,
+    };
+
+    // Steps without a temperature are beyond the model's range
+    let hourly = &resp.hourly;
+    let hourly = hourly
+        .time
+        .iter()
+        .enumerate()
+        .filter_map(|(i, time)| {
+            Some(Hour {
+                time: forecast::unix(*time),
+                temperature: (*hourly.temperature_2m.get(i)?)?,
+                condition: condition(hourly.weather_code.get(i)),
+                precipitation: hourly.precipitation.get(i).copied().flatten(),
+            })
+        })
+        .collect();
+
+    // Days start at local midnight
+    let daily = &resp.daily;
+    let daily = daily
+        .time
+        .iter()
+        .enumerate()
+        .filter_map(|(i, time)| {
+            Some(Day {
+                date: forecast::unix(time + resp.utc_offset_seconds).date_naive(),
+                low: (*daily.temperature_2m_min.get(i)?)?,
+                high: (*daily.temperature_2m_max.get(i)?)?,
+                condition: condition(daily.weather_code.get(i)),
+                precipitation: daily.precipitation_sum.get(i).copied().flatten(),
+            })
+        })
+        .collect();
+
+    let mut forecast = Forecast { hourly, daily };
+    forecast.truncate();
+    Ok(forecast)
+}
+
+/// Parses an air quality response
+///
+/// # Arguments
+///
+/// - `body`: Response body with concentrations in μg/m³
+///
+/// # Returns
+///
+/// The air quality, or a description of why the body is invalid
+pub(crate) fn parse_air_quality(body: &str) -> Result<AirQuality, String> {
+    let resp: AirQualityResponse = serde_json::from_str(body).map_err(|e| e.to_string())?;
+    let current = resp.current;
+
+    let mut air_quality = AirQuality::new(Concentrations {
+        pm2_5: current.pm2_5,
+        pm10: current.pm10,
+        o3: current.ozone,
+        no2: current.nitrogen_dioxide,
+        so2: current.sulphur_dioxide,
+        co: current.carbon_monoxide,
+    });
+    air_quality.pollen = [
+        ("alder", current.alder_pollen),
+        ("birch", current.birch_pollen),
+        ("grass", current.grass_pollen),
+        ("mugwort", current.mugwort_pollen),
+        ("olive", current.olive_pollen),
+        ("ragweed", current.ragweed_pollen),
+    ]
+    .iter()
+    .filter_map(|(plant, count)| count.map(|v| (plant.to_string(), v)))
+    .collect();
+
+    Ok(air_quality)
+}
+
+impl AirQualityProvider for OpenMeteoAirQuality {
+    fn air_quality(
+        &self,
+        client: &dyn HttpClient,
+        location: &GeoLocation,
+    ) -> Result<AirQuality, String> {
+        client
+            .get(&self.url(location))
+            .map_err(|e| e.to_string())
+            .and_then(|body| parse_air_quality(&body))
+            .map_err(|e| {
+                error!("weather::OpenMeteoAirQuality::air_quality: {}", e);
+                DEFAULT_ERROR.into()
+            })
+    }
+}
+
+impl WeatherProvider for OpenMeteo {
+    fn current(
+        &self,
+        client: &dyn HttpClient,
+        location: &GeoLocation,
+    ) -> Result<WeatherReport, String> {
+        client
+            .get(&self.url(location))
+            .map_err(|e| e.to_string())
+            .and_then(|body| parse(&body))
+            .map_err(|e| {
+                error!("weather::OpenMeteo::current: {}", e);
+                DEFAULT_ERROR.into()
+            })
+    }
+
+    fn forecast(
+        &self,
+        client: &dyn HttpClient,
+        location: &GeoLocation,
+    ) -> Result<Forecast, String> {
+        client
+            .get(&self.forecast_url(location))
+            .map_err(|e| e.to_string())
+            .and_then(|body| parse_forecast(&body))
+            .map_err(|e| {
+                error!("weather::OpenMeteo::forecast: {}", e);
+                DEFAULT_ERROR.into()
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let body = std::fs::read_to_string("tests/openmeteo/forecast.json").unwrap();
+        let report = parse(&body).unwrap();
+
+        assert_eq!(report.temperature, 12.3);
+        assert_eq!(report.feels_like, Some(10.9));
+        assert_eq!(report.condition, Condition::Rain);
+        assert_eq!(report.humidity, Some(81.0));
+        assert_eq!(report.wind_speed, Some(4.1));
+        assert_eq!(report.wind_direction, Some(250.0));
+        assert_eq!(report.pressure, Some(1012.4));
+        assert_eq!(report.precipitation, Some(0.4));
+        assert_eq!(report.clouds, Some(90.0));
+        assert_eq!(report.visibility, Some(9000.0));
+        assert_eq!(report.uv_index, Some(1.2));
+        assert_eq!(report.sunrise, Some(forecast::unix(1792332900)));
+        assert_eq!(report.sunset, Some(forecast::unix(1792373220)));
+    }
+
+    #[test]
+    fn test_parse_forecast() {
+        let body = std::fs::read_to_string("tests/openmeteo/hourly-daily.json").unwrap();
+        let forecast = parse_forecast(&body).unwrap();
+
+        assert_eq!(forecast.hourly.len(), 48);
+        assert_eq!(
+            forecast.hourly[0].time.to_rfc3339(),
+            "2026-10-17T22:00:00+00:00"
+        );
+        assert_eq!(forecast.hourly[15].temperature, 14.1);
+        assert_eq!(forecast.hourly[15].condition, Condition::PartlyCloudy);
+
+        assert_eq!(forecast.daily.len(), 7);
+        assert_eq!(forecast.daily[0].date.to_string(), "2026-10-18");
+        assert_eq!(forecast.daily[1].condition, Condition::Showers);
+        assert_eq!(forecast.daily[1].precipitation, Some(4.2));
+    }
+
+    #[test]
+    fn test_parse_air_quality() {
+        let body = std::fs::read_to_string("tests/openmeteo/air-quality.json").unwrap();
+        let air_quality = parse_air_quality(&body).unwrap();
+
+        let c = &air_quality.concentrations;
+        assert_eq!(c.pm2_5, Some(11.4));
+        assert_eq!(c.pm10, Some(17.2));
+        assert_eq!(c.o3, Some(61.0));
+        assert_eq!(c.co, Some(212.0));
+        assert_eq!(air_quality.pollen.len(), 4);
+        assert_eq!(air_quality.pollen[1], ("grass".to_string(), 14.3));
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        assert!(parse("{\"error\": true, \"reason\": \"Latitude must be in range\"}").is_err());
+    }
+}
@@ -0,0 +1,485 @@
+use log::error;
+use serde::Deserialize;
+
+use chrono::FixedOffset;
+
+use crate::air::{AirQuality, AirQualityProvider};
+use crate::alert::{Alert, AlertProvider};
+use crate::aqi::Concentrations;
+use crate::forecast::{self, Hour};
+use crate::provider::{self, WeatherProvider};
+use crate::{Condition, Forecast, GeoLocation, HttpClient, WeatherReport, DEFAULT_ERROR};
+
+// Condition ids, see https://openweathermap.org/weather-conditions
+const CONDITIONS: &[(u32, u32, Condition)] = &[
+    (200, 299, Condition::Thunderstorm),
+    (300, 399, Condition::Drizzle),
+    (500, 504, Condition::Rain),
+    (511, 511, Condition::FreezingRain),
+    (520, 531, Condition::Showers),
+    (611, 616, Condition::Sleet),
+    (600, 699, Condition::Snow),
+    (701, 701, Condition::Fog),
+    (741, 741, Condition::Fog),
+    (700, 799, Condition::Haze),
+    (800, 800, Condition::Clear),
+    (801, 802, Condition::PartlyCloudy),
+    (803, 804, Condition::Cloudy),
+];
+
+#[derive(Deserialize)]
+struct Weather {
+    id: u32,
+}
+
+#[derive(Deserialize)]
+struct Main {
+    temp: f64,
+    feels_like: Option<f64>,
+    pressure: Option<f64>,
+    humidity: Option<f64>,
+}
+
+#[derive(Deserialize)]
+struct Wind {
+    speed: Option<f64>,
+    deg: Option<f64>,
+}
+
+#[derive(Deserialize)]
+struct Volume {
+    #[serde(rename = "1h")]
+    one_hour: Option<f64>,
+
+    #[serde(rename = "3h")]
+    three_hours: Option<f64>,
+}
+
+#[derive(Deserialize)]
+struct Clouds {
+    all: Option<f64>,
+}
+
+#[derive(Deserialize)]
+struct Sys {
+    sunrise: Option<i64>,
+    sunset: Option<i64>,
+}
+
+#[derive(Deserialize)]
+struct Response {
+    main: Main,
+    weather: Vec<Weather>,
+    wind: Option<Wind>,
+    rain: Option<Volume>,
+    snow: Option<Volume>,
+    clouds: Option<Clouds>,
+    visibility: Option<f64>,
+    sys: Option<Sys>,
+}
+
+#[derive(Deserialize)]
+struct Step {
+    dt: i64,
+    main: Main,
+    weather: Vec<Weather>,
+    rain: Option<Volume>,
+    snow: Option<Volume>,
+}
+
+#[derive(Deserialize)]
+struct City {
+    #[serde(default)]
+    timezone: i32,
+}
+
+#[derive(Deserialize)]
+struct ForecastResponse {
+    list: Vec<Step>,
+    city: City,
+}
+
+#[derive(Deserialize)]
+struct OneCallAlert {
+    sender_name: Option<String>,
+    event: String,
+    start: i64,
+    end: i64,
+
+    #[serde(default)]
+    description: String,
+}
+
+#[derive(Deserialize)]
+struct OneCallResponse {
+    #[serde(default)]
+    alerts: Vec<OneCallAlert>,
+}
+
+#[derive(Deserialize)]
+struct Components {
+    pm2_5: Option<f64>,
+    pm10: Option<f64>,
+    o3: Option<f64>,
+    no2: Option<f64>,
+    so2: Option<f64>,
+    co: Option<f64>,
+}
+
+#[derive(Deserialize)]
+struct Pollution {
+    components: Components,
+}
+
+#[derive(Deserialize)]
+struct PollutionResponse {
+    list: Vec<Pollution>,
+}
+
+/// OpenWeatherMap current weather, 5 day forecast, One Call alerts and air
+/// pollution
+pub struct OpenWeatherMap {
+    base_url: String, // base URL of the API
+    api_key: String,  // OpenWeather API key
+}
+
+impl OpenWeatherMap {
+    /// Creates the provider
+    ///
+    /// # Arguments
+    ///
+    /// - `base_url`: Base URL of the API
+    /// - `api_key`: OpenWeather API key
+    pub fn new(base_url: &str, api_key: &str) -> OpenWeatherMap {
+        OpenWeatherMap {
+            base_url: base_url.into(),
+            api_key: api_key.into(),
+        }
+    }
+
+    fn url(&self, location: &GeoLocation) -> String {
+        let path = format!(
+            "/data/2.5/weather?lat={}&lon={}&units=metric&appid={}",
+            location.lat, location.lon, self.api_key
+        );
+        provider::join(&self.base_url, &path)
+    }
+
+    fn forecast_url(&self, location: &GeoLocation) -> String {
+        let path = format!(
+            "/data/2.5/forecast?lat={}&lon={}&units=metric&appid={}",
+            location.lat, location.lon, self.api_key
+        );
+        provider::join(&self.base_url, &path)
+    }
+
+    fn alerts_url(&self, location: &GeoLocation) -> String {
+        let path = format!(
+            "/data/3.0/onecall?lat={}&lon={}&exclude=current,minutely,hourly,daily&appid={}",
+            location.lat, location.lon, self.api_key
+        );
+        provider::join(&self.base_url, &path)
+    }
+
+    fn air_quality_url(&self, location: &GeoLocation) -> String {
+        let path = format!(
+            "/data/2.5/air_pollution?lat={}&lon={}&appid={}",
+            location.lat, location.lon, self.api_key
+        );
+        provider::join(&self.base_url, &path)
+    }
+}
+
+/// Returns the condition of the first weather entry
+fn condition(weather: &[Weather]) -> Condition {
+    weather
+        .first()
+        .map(|v| provider::lookup(CONDITIONS, v.id))
+        .unwrap_or(Condition::Unknown)
+}
+
+/// Parses a current weather response
+///
+/// # Arguments
+///
+/// - `body`: Response body in metric units
+///
+/// # Returns
+///
+/// The neutral report, or a description of why the body is invalid
+pub(crate) fn parse(body: &str) -> Result<WeatherReport, String> {
+    let resp: Response = serde_json::from_str(body).map_err(|e| e.to_string())?;
+
+    let mut report = WeatherReport::new(resp.main.temp, condition(&resp.weather));
+    report.feels_like = resp.main.feels_like;
+    report.humidity = resp.main.humidity;
+    report.pressure = resp.main.pressure;
+    if let Some(wind) = resp.wind {
+        report.wind_speed = wind.speed;
+        report.wind_direction = wind.deg;
+    }
+    report.clouds = resp.clouds.and_then(|v| v.all);
+    report.visibility = resp.visibility;
+    if let Some(sys) = resp.sys {
+        report.sunrise = sys.sunrise.map(forecast::unix);
+        report.sunset = sys.sunset.map(forecast::unix);
+    }
+
+    // Volumes are omitted when nothing fell
+    let volume = |v: Option<Volume>| v.and_then(|v| v.one_hour).unwrap_or(0.0);
+    report.precipitation = Some(volume(resp.rain) + volume(resp.snow));
+
+    Ok(report)
+}
+
+/// Parses a 5 day forecast response
+///
+/// The forecast has 3 hour steps, which the days are derived from in the
+/// time zone of the location.
+///
+/// # Arguments
+///
+/// - `body`: Response body in metric units
+///
+/// # Returns
+///
+/// The forecast, or a description of why the body is invalid
+pub(crate) fn parse_forecast(body: &str) -> Result<Forecast, String> {
+    let resp: ForecastResponse = serde_json::from_str(body).map_err(|e| e.to_string())?;
+    let offset = match FixedOffset::east_opt(resp.city.timezone) {
+        Some(v) => v,
+        None => return Err(format!("invalid time zone offset {}", resp.city.timezone)),
+    };
+
+    let volume = |v: &Option<Volume>| v.as_ref().and_then(|v| v.three_hours).unwrap_or(0.0);
+    let steps = resp
+        .list
+        .iter()
+        .map(|v| Hour {
+            time: forecast::unix(v.dt),
+            temperature: v.main.temp,
+            condition: condition(&v.weather),
+            precipitation: Some(volume(&v.rain) + volume(&v.snow)),
+        })
+        .collect();
+
+    Ok(Forecast::from_steps(steps, offset))
+}
+
+/// Parses the alerts of a One Call response
+///
+/// Alerts have no identifier, so one is derived from the sender, event and
+/// start time.
+///
+/// # Arguments
+///
+/// - `body`: Response body
+///
+/// # Returns
+///
+/// The alerts, or a description of why the body is invalid
+pub(crate) fn parse_alerts(body: &str) -> Result<Vec<Alert>, String> {
+    let resp: OneCallResponse = serde_json::from_str(body).map_err(|e| e.to_string())?;
+
+    let alerts = resp
+        .alerts
+        .into_iter()
+        .map(|v| Alert {
+            id: format!(
+                "{}/{}/{}",
+                v.sender_name.as_deref().unwrap_or_default(),
+                v.event,
+                v.start
+            ),
+            event: v.event,
+            sender: v.sender_name,
+            description: v.description,
+            start: Some(forecast::unix(v.start)),
+            end: Some(forecast::unix(v.end)),
+        })
+        .collect();
+
+    Ok(alerts)
+}
+
+/// Parses an air pollution response
+///
+/// The API also reports its own 1 to 5 index, which is ignored in favor of
+/// the configured one.
+///
+/// # Arguments
+///
+/// - `body`: Response body with concentrations in μg/m³
+///
+/// # Returns
+///
+/// The air quality, or a description of why the body is invalid
+pub(crate) fn parse_air_quality(body: &str) -> Result<AirQuality, String> {
+    let resp: PollutionResponse = serde_json::from_str(body).map_err(|e| e.to_string())?;
+    let components = match resp.list.into_iter().next() {
+        Some(v) => v.components,
+        None => return Err("no air pollution data".into()),
+    };
+
+    Ok(AirQuality::new(Concentrations {
+        pm2_5: components.pm2_5,
+        pm10: components.pm10,
+        o3: components.o3,
+        no2: components.no2,
+        so2: components.so2,
+        co: components.co,
+    }))
+}
+
+impl AirQualityProvider for OpenWeatherMap {
+    fn air_quality(
+        &self,
+        client: &dyn HttpClient,
+        location: &GeoLocation,
+    ) -> Result<AirQuality, String> {
+        client
+            .get(&self.air_quality_url(location))
+            .map_err(|e| e.to_string())
+            .and_then(|body| parse_air_quality(&body))
+            .map_err(|e| {
+                error!("weather::OpenWeatherMap::air_quality: {}", e);
+                DEFAULT_ERROR.into()
+            })
+    }
+}
+
+impl AlertProvider for OpenWeatherMap {
+    fn alerts(
+        &self,
+        client: &dyn HttpClient,
+        location: &GeoLocation,
+    ) -> Result<Vec<Alert>, String> {
+        client
+            .get(&self.alerts_url(location))
+            .map_err(|e| e.to_string())
+            .and_then(|body| parse_alerts(&body))
+            .map_err(|e| {
+                error!("weather::OpenWeatherMap::alerts: {}", e);
+                DEFAULT_ERROR.into()
+            })
+    }
+}
+
+impl WeatherProvider for OpenWeatherMap {
+    fn current(
+        &self,
+        client: &dyn HttpClient,
+        location: &GeoLocation,
+    ) -> Result<WeatherReport, String> {
+        client
+            .get(&self.url(location))
+            .map_err(|e| e.to_string())
+            .and_then(|body| parse(&body))
+            .map_err(|e| {
+                error!("weather::OpenWeatherMap::current: {}", e);
+                DEFAULT_ERROR.into()
+            })
+    }
+
+    fn forecast(
+        &self,
+        client: &dyn HttpClient,
+        location: &GeoLocation,
+    ) -> Result<Forecast, String> {
+        client
+            .get(&self.forecast_url(location))
+            .map_err(|e| e.to_string())
+            .and_then(|body| parse_forecast(&body))
+            .map_err(|e| {
+                error!("weather::OpenWeatherMap::forecast: {}", e);
+                DEFAULT_ERROR.into()
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let body = std::fs::read_to_string("tests/openweathermap/current.json").unwrap();
+        let report = parse(&body).unwrap();
+
+        assert_eq!(report.temperature, 12.3);
+        assert_eq!(report.feels_like, Some(11.5));
+        assert_eq!(report.condition, Condition::Rain);
+        assert_eq!(report.humidity, Some(81.0));
+        assert_eq!(report.wind_speed, Some(4.1));
+        assert_eq!(report.wind_direction, Some(250.0));
+        assert_eq!(report.pressure, Some(1012.0));
+        assert_eq!(report.precipitation, Some(0.4));
+        assert_eq!(report.clouds, Some(75.0));
+        assert_eq!(report.visibility, Some(9000.0));
+        assert_eq!(report.uv_index, None);
+        assert_eq!(report.sunrise, Some(forecast::unix(1792332900)));
+        assert_eq!(report.sunset, Some(forecast::unix(1792373220)));
+    }
+
+    #[test]
+    fn test_parse_forecast() {
+        let body = std::fs::read_to_string("tests/openweathermap/forecast.json").unwrap();
+        let forecast = parse_forecast(&body).unwrap();
+
+        assert_eq!(forecast.hourly.len(), 16);
+        assert_eq!(forecast.hourly[1].condition, Condition::Rain);
+        assert_eq!(forecast.hourly[1].precipitation, Some(1.2));
+
+        assert_eq!(forecast.daily.len(), 6);
+        assert_eq!(forecast.daily[0].date.to_string(), "2026-10-18");
+        assert_eq!(forecast.daily[0].low, 10.5);
+        assert_eq!(forecast.daily[0].high, 15.0);
+        assert_eq!(forecast.daily[1].condition, Condition::Clear);
+    }
+
+    #[test]
+    fn test_parse_alerts() {
+        let body = std::fs::read_to_string("tests/openweathermap/onecall.json").unwrap();
+        let alerts = parse_alerts(&body).unwrap();
+
+        assert_eq!(alerts.len(), 2);
+        assert_eq!(
+            alerts[0].id,
+            "NWS San Francisco CA/Wind Advisory/1792342800"
+        );
+        assert_eq!(alerts[0].event, "Wind Advisory");
+        assert_eq!(alerts[0].end, Some(forecast::unix(1792382400)));
+
+        // Alerts are omitted when none are active
+        assert!(parse_alerts("{\"lat\": 37.77, \"lon\": -122.42}")
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_parse_air_quality() {
+        let body = std::fs::read_to_string("tests/openweathermap/air-pollution.json").unwrap();
+        let air_quality = parse_air_quality(&body).unwrap();
+
+        let c = &air_quality.concentrations;
+        assert_eq!(c.pm2_5, Some(8.93));
+        assert_eq!(c.o3, Some(68.66));
+        assert_eq!(c.co, Some(201.94));
+        assert!(air_quality.pollen.is_empty());
+
+        assert!(parse_air_quality("{\"coord\": {}, \"list\": []}").is_err());
+    }
+
+    #[test]
+    fn test_conditions() {
+        let condition = |id| provider::lookup(CONDITIONS, id);
+        assert_eq!(condition(211), Condition::Thunderstorm);
+        assert_eq!(condition(511), Condition::FreezingRain);
+        assert_eq!(condition(612), Condition::Sleet);
+        assert_eq!(condition(601), Condition::Snow);
+        assert_eq!(condition(741), Condition::Fog);
+        assert_eq!(condition(721), Condition::Haze);
+        assert_eq!(condition(804), Condition::Cloudy);
+        assert_eq!(condition(900), Condition::Unknown);
+    }
+}
@@ -0,0 +1,109 @@
+use log::error;
+use serde::{Deserialize, Serialize};
+
+use crate::metno::MetNorway;
+use crate::openmeteo::OpenMeteo;
+use crate::openweathermap::OpenWeatherMap;
+use crate::{Config, Forecast, GeoLocation, HttpClient, WeatherReport, DEFAULT_ERROR};
+
+/// Source of current weather reports
+pub trait WeatherProvider {
+    /// Fetches the current weather at a location
+    ///
+    /// # Arguments
+    ///
+    /// - `client`: Client the request is sent with
+    /// - `location`: Location to report the weather of
+    ///
+    /// # Returns
+    ///
+    /// The current weather, or an i3blocks error string
+    fn current(
+        &self,
+        client: &dyn HttpClient,
+        location: &GeoLocation,
+    ) -> Result<WeatherReport, String>;
+
+    /// Fetches the hourly and daily forecasts at a location
+    ///
+    /// # Arguments
+    ///
+    /// - `client`: Client the request is sent with
+    /// - `location`: Location to forecast the weather of
+    ///
+    /// # Returns
+    ///
+    /// The forecast, or an i3blocks error string
+    fn forecast(&self, client: &dyn HttpClient, location: &GeoLocation)
+        -> Result<Forecast, String>;
+}
+
+/// Weather providers selectable in the configuration
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Provider {
+    #[default]
+    OpenWeatherMap, // api.openweathermap.org, requires an API key
+    OpenMeteo, // api.open-meteo.com, keyless
+    MetNo,     // MET Norway locationforecast, keyless
+}
+
+impl Provider {
+    /// Creates the configured weather provider
+    ///
+    /// # Arguments
+    ///
+    /// - `config`: Parsed configuration
+    ///
+    /// # Returns
+    ///
+    /// The provider, or an i3blocks error string if its settings are missing
+    pub fn build(config: &Config) -> Result<Box<dyn WeatherProvider>, String> {
+        let endpoints = &config.endpoints;
+        match config.provider {
+            Provider::OpenWeatherMap => match &config.open_weather_api_key {
+                Some(key) => Ok(Box::new(OpenWeatherMap::new(
+                    &endpoints.openweathermap,
+                    key,
+                ))),
+                None => {
+                    error!(
+                        "weather::Provider::build: `open_weather_api_key` is required by \
+                        the openweathermap provider"
+                    );
+                    Err(DEFAULT_ERROR.into())
+                }
+            },
+            Provider::OpenMeteo => Ok(Box::new(OpenMeteo::new(&endpoints.openmeteo))),
+            Provider::MetNo => Ok(Box::new(MetNorway::new(&endpoints.metno))),
+        }
+    }
+}
+
+/// Joins a configured base URL and a path
+///
+/// # Arguments
+///
+/// - `base`: Base URL, with or without a trailing slash
+/// - `path`: Path and query, starting with a slash
+pub(crate) fn join(base: &str, path: &str) -> String {
+    format!("{}{}", base.trim_end_matches('/'), path)
+}
+
+/// Maps a numeric condition code onto a `Condition`
+///
+/// # Arguments
+///
+/// - `table`: Inclusive code ranges and their conditions
+/// - `code`: Provider condition code
+///
+/// # Returns
+///
+/// The matching condition, or `Condition::Unknown`
+pub(crate) fn lookup(table: &[(u32, u32, crate::Condition)], code: u32) -> crate::Condition {
+    table
+        .iter()
+        .find(|(first, last, _)| (*first..=*last).contains(&code))
+        .map(|(_, _, condition)| *condition)
+        .unwrap_or(crate::Condition::Unknown)
+}
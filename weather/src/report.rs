@@ -0,0 +1,527 @@
+use std::fmt;
+
+use chrono::{DateTime, Duration, FixedOffset, Local, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::format::{self, Formatter, Value};
+use crate::{Forecast, I3Block, View};
+
+// Constants
+pub(crate) const STALE_COLOR: &str = "#808080";
+
+/// Provider-neutral weather condition
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub enum Condition {
+    Clear,
+    PartlyCloudy,
+    Cloudy,
+    Fog,
+    Haze,
+    Drizzle,
+    Rain,
+    FreezingRain,
+    Showers,
+    Sleet,
+    Snow,
+    Thunderstorm,
+    Unknown,
+}
+
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Condition::Clear => "clear",
+            Condition::PartlyCloudy => "partly cloudy",
+            Condition::Cloudy => "cloudy",
+            Condition::Fog => "fog",
+            Condition::Haze => "haze",
+            Condition::Drizzle => "drizzle",
+            Condition::Rain => "rain",
+            Condition::FreezingRain => "freezing rain",
+            Condition::Showers => "showers",
+            Condition::Sleet => "sleet",
+            Condition::Snow => "snow",
+            Condition::Thunderstorm => "thunderstorm",
+            Condition::Unknown => "unknown",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Current weather as reported by any `WeatherProvider`
+///
+/// Providers convert their responses to metric units so that the rendering
+/// doesn't depend on where the report came from. Readings a provider doesn't
+/// offer are left as `None`.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct WeatherReport {
+    pub temperature: f64,               // air temperature (°C)
+    pub feels_like: Option<f64>,        // apparent temperature (°C)
+    pub condition: Condition,           // prevailing condition
+    pub humidity: Option<f64>,          // relative humidity (%)
+    pub wind_speed: Option<f64>,        // wind speed (m/s)
+    pub wind_direction: Option<f64>,    // direction the wind blows from (°)
+    pub pressure: Option<f64>,          // sea level pressure (hPa)
+    pub precipitation: Option<f64>,     // precipitation over the last hour (mm)
+    pub clouds: Option<f64>,            // cloud cover (%)
+    pub visibility: Option<f64>,        // visibility (m)
+    pub uv_index: Option<f64>,          // UV index
+    pub sunrise: Option<DateTime<Utc>>, // today's sunrise
+    pub sunset: Option<DateTime<Utc>>,  // today's sunset
+    pub scale: char,                    // temperature scale to display
+
+    #[serde(default)]
+    pub forecast: Option<Forecast>, // hourly and daily forecasts, if fetched
+
+    #[serde(skip)]
+    pub age: Option<i64>, // age of a stale report (s)
+
+    #[serde(skip)]
+    view: Option<(View, DateTime<FixedOffset>)>, // view to display and local time
+
+    #[serde(skip)]
+    formatter: Formatter, // templates and units of the current view
+
+    #[serde(skip)]
+    alert: Option<(String, String)>, // full and short label of an active alert
+}
+
+impl WeatherReport {
+    /// Creates a report of a temperature and condition
+    ///
+    /// # Arguments
+    ///
+    /// - `temperature`: Air temperature in °C
+    /// - `condition`: Prevailing condition
+    pub fn new(temperature: f64, condition: Condition) -> WeatherReport {
+        WeatherReport {
+            temperature,
+            feels_like: None,
+            condition,
+            humidity: None,
+            wind_speed: None,
+            wind_direction: None,
+            pressure: None,
+            precipitation: None,
+            clouds: None,
+            visibility: None,
+            uv_index: None,
+            sunrise: None,
+            sunset: None,
+            scale: 'C',
+            forecast: None,
+            age: None,
+            view: None,
+            formatter: Formatter::default(),
+            alert: None,
+        }
+    }
+
+    /// Sets the label of an active alert appended to the texts
+    ///
+    /// # Arguments
+    ///
+    /// - `alert`: Full and short labels, `None` without active alerts
+    pub fn set_alert(&mut self, alert: Option<(String, String)>) {
+        self.alert = alert;
+    }
+
+    /// Sets the templates and units of the current view
+    pub fn set_formatter(&mut self, formatter: Formatter) {
+        self.formatter = formatter;
+    }
+
+    /// Returns whether the sun is up
+    ///
+    /// Sunrise and sunset may be of another day, only their time of day is
+    /// compared.
+    ///
+    /// # Arguments
+    ///
+    /// - `now`: Present time
+    ///
+    /// # Returns
+    ///
+    /// Whether it's day, or `None` if sunrise or sunset is unknown
+    pub fn is_day(&self, now: DateTime<Utc>) -> Option<bool> {
+        let (sunrise, sunset) = (self.sunrise?, self.sunset?);
+        let since_sunrise = (now - sunrise).num_seconds().rem_euclid(86_400);
+        let daylight = (sunset - sunrise).num_seconds().rem_euclid(86_400);
+        Some(since_sunrise < daylight)
+    }
+
+    /// Sets the view to display
+    ///
+    /// Forecast views fall back to the current weather when the forecast
+    /// doesn't cover them.
+    ///
+    /// # Arguments
+    ///
+    /// - `view`: View to display
+    /// - `now`: Present local time, which forecasts are relative to
+    pub fn set_view(&mut self, view: View, now: DateTime<FixedOffset>) {
+        self.view = Some((view, now));
+    }
+
+    /// Marks the report as stale
+    ///
+    /// Stale reports are shown dimmed with their age.
+    ///
+    /// # Arguments
+    ///
+    /// - `age`: Time since the report was fetched in seconds
+    pub fn set_age(&mut self, age: i64) {
+        self.age = Some(age);
+    }
+
+    /// Sets the scale temperatures are displayed in
+    ///
+    /// # Arguments
+    ///
+    /// - `scale`: One of 'C', 'F' or 'K'
+    pub fn set_scale(&mut self, scale: char) {
+        self.scale = scale;
+    }
+
+    /// Converts a temperature to the display scale
+    ///
+    /// # Arguments
+    ///
+    /// - `celsius`: Temperature in °C
+    ///
+    /// # Returns
+    ///
+    /// The temperature in the display scale
+    pub fn convert(&self, celsius: f64) -> f64 {
+        convert(celsius, self.scale)
+    }
+}
+
+impl fmt::Display for WeatherReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{:.1}°{}, {}",
+            self.convert(self.temperature),
+            self.scale,
+            self.condition
+        )
+    }
+}
+
+/// Converts a temperature from °C
+///
+/// # Arguments
+///
+/// - `celsius`: Temperature in °C
+/// - `scale`: One of 'C', 'F' or 'K'
+///
+/// # Returns
+///
+/// The temperature in the scale
+pub(crate) fn convert(celsius: f64, scale: char) -> f64 {
+    match scale {
+        'F' => 1.8 * celsius + 32.0,
+        'K' => celsius + 273.15,
+        _ => celsius,
+    }
+}
+
+/// Formats the age of a stale report
+///
+/// # Arguments
+///
+/// - `age`: Age in seconds
+///
+/// # Returns
+///
+/// The age in the largest whole unit, e.g. "2h ago"
+pub(crate) fn format_age(age: i64) -> String {
+    let minutes = age.max(0) / 60;
+    match minutes {
+        0..=59 => format!("{}m ago", minutes),
+        60..=2879 => format!("{}h ago", minutes / 60),
+        _ => format!("{}d ago", minutes / (24 * 60)),
+    }
+}
+
+impl WeatherReport {
+    /// Returns the text of a template value
+    ///
+    /// # Arguments
+    ///
+    /// - `value`: Value to format
+    ///
+    /// # Returns
+    ///
+    /// The formatted value, or `None` if the provider didn't report it
+    fn resolve(&self, value: Value) -> Option<String> {
+        let temperature = |v: f64| format!("{:.1}°{}", self.convert(v), self.scale);
+        let offset = match self.view {
+            Some((_, now)) => *now.offset(),
+            None => *Local::now().offset(),
+        };
+        let time = |v: DateTime<Utc>| v.with_timezone(&offset).format("%H:%M").to_string();
+        let now = match self.view {
+            Some((_, now)) => now.with_timezone(&Utc),
+            None => Utc::now(),
+        };
+
+        match value {
+            Value::Clouds => self.clouds.map(|v| format!("{:.0}", v)),
+            Value::Condition => Some(self.condition.to_string()),
+            Value::FeelsLike => self.feels_like.map(temperature),
+            Value::Humidity => self.humidity.map(|v| format!("{:.0}", v)),
+            Value::Icon => {
+                // Without sunrise and sunset, assume it's day
+                let day = self.is_day(now).unwrap_or(true);
+                Some(self.formatter.icons.icon(self.condition, day).into())
+            }
+            Value::Precipitation => self.precipitation.map(|v| format!("{:.1}", v)),
+            Value::Pressure => self
+                .pressure
+                .map(|v| self.formatter.pressure_unit.format(v)),
+            Value::Sunrise => self.sunrise.map(time),
+            Value::Sunset => self.sunset.map(time),
+            Value::Temperature => Some(temperature(self.temperature)),
+            Value::Uv => self.uv_index.map(|v| format!("{:.0}", v)),
+            Value::Visibility => self.visibility.map(|v| format!("{:.1}", v / 1000.0)),
+            Value::Wind => self.wind_speed.map(|v| self.formatter.wind_unit.format(v)),
+            Value::WindDirection => self.wind_direction.map(format::compass).map(String::from),
+        }
+    }
+
+    /// Formats the full and short texts of the displayed view
+    ///
+    /// # Returns
+    ///
+    /// The full and short texts, and the temperature (°C) they're colored by
+    fn texts(&self) -> (String, String, f64) {
+        let current = || {
+            (
+                self.formatter.full_text.render(|v| self.resolve(v)),
+                self.formatter.short_text.render(|v| self.resolve(v)),
+                self.temperature,
+            )
+        };
+
+        let (forecast, view, now) = match (&self.forecast, self.view) {
+            (Some(forecast), Some((view, now))) => (forecast, view, now),
+            _ => return current(),
+        };
+
+        let day = |label: &str, days: i64| {
+            forecast
+                .day((now + Duration::days(days)).date_naive())
+                .map(|v| {
+                    let range = format!(
+                        "{} {:.1}/{:.1}°{}",
+                        label,
+                        self.convert(v.high),
+                        self.convert(v.low),
+                        self.scale
+                    );
+                    (
+                        format!("WTR {}, {}", range, v.condition),
+                        format!("WTR {}", range),
+                        v.high,
+                    )
+                })
+        };
+
+        let texts = match view {
+            View::Current => None,
+            View::Next3h => forecast
+                .hour((now + Duration::hours(3)).with_timezone(&Utc))
+                .map(|v| {
+                    let temperature =
+                        format!("3h {:.1}°{}", self.convert(v.temperature), self.scale);
+                    (
+                        format!("WTR {}, {}", temperature, v.condition),
+                        format!("WTR {}", temperature),
+                        v.temperature,
+                    )
+                }),
+            View::Today => day("today", 0),
+            View::Tomorrow => day("tmrw", 1),
+        };
+
+        texts.unwrap_or_else(current)
+    }
+}
+
+impl I3Block for WeatherReport {
+    fn format_i3(&self) -> String {
+        let (mut full_text, mut short_text, temperature) = self.texts();
+        let mut color = self.formatter.color(self.convert(temperature));
+        if let Some((full, short)) = &self.alert {
+            full_text = format!("{} {}", full_text, full);
+            short_text = format!("{} {}", short_text, short);
+        }
+        if let Some(age) = self.age {
+            full_text = format!("{} · {}", full_text, format_age(age));
+            color = STALE_COLOR.into();
+        }
+
+        format!("{}\n{}\n{}", full_text, short_text, color)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_convert() {
+        let mut report = WeatherReport::new(20.0, Condition::Clear);
+        assert_eq!(report.convert(20.0), 20.0);
+
+        report.set_scale('F');
+        assert_eq!(report.convert(20.0), 68.0);
+
+        report.set_scale('K');
+        assert_eq!(report.convert(20.0), 293.15);
+    }
+
+    #[test]
+    fn test_format_i3() {
+        let mut report = WeatherReport::new(12.34, Condition::PartlyCloudy);
+        report.set_scale('F');
+        assert_eq!(
+            report.format_i3(),
+            "WTR 54.2°F, partly cloudy\nWTR 54.2°F\n#FFFFFF"
+        );
+    }
+
+    #[test]
+    fn test_format_i3_stale() {
+        let mut report = WeatherReport::new(12.3, Condition::Rain);
+        report.set_age(2 * 60 * 60 + 59);
+        assert_eq!(
+            report.format_i3(),
+            "WTR 12.3°C, rain · 2h ago\nWTR 12.3°C\n#808080"
+        );
+    }
+
+    #[test]
+    fn test_format_i3_template() {
+        let body = std::fs::read_to_string("tests/openweathermap/current.json").unwrap();
+        let mut report = crate::openweathermap::parse(&body).unwrap();
+        report.set_view(
+            View::Current,
+            DateTime::parse_from_rfc3339("2026-10-18T12:00:00-07:00").unwrap(),
+        );
+        report.set_formatter(
+            Formatter::new(&crate::Format {
+                full_text: "{temp} feels {feels_like}, {humidity}% {pressure} {wind} {wind_dir} \
+                            {clouds}% {visibility} km {precip} mm[ uv {uv}] {sunrise}-{sunset}"
+                    .into(),
+                short_text: "{temp}[ uv {uv}]".into(),
+                wind_unit: crate::WindUnit::Kmh,
+                pressure_unit: crate::PressureUnit::Inhg,
+                ..Default::default()
+            })
+            .unwrap(),
+        );
+
+        assert_eq!(
+            report.format_i3(),
+            "12.3°C feels 11.5°C, 81% 29.88 inHg 15 km/h WSW 75% 9.0 km 0.4 mm 07:15-18:27\n\
+             12.3°C\n#FFFFFF"
+        );
+    }
+
+    #[test]
+    fn test_format_i3_icon_color() {
+        let format: crate::Format = serde_yaml::from_str(
+            "full_text: '{icon} {temp}'\nshort_text: '{icon}'\nicon_theme: text\n\
+             color_stops:\n  - {threshold: 32, color: '#0000FF'}\n  \
+             - {threshold: 50, color: '#00FF00'}\n  - {threshold: 86, color: '#FF0000'}",
+        )
+        .unwrap();
+        let time = |v| DateTime::parse_from_rfc3339(v).unwrap();
+
+        let mut report = WeatherReport::new(12.3, Condition::Clear);
+        report.set_scale('F');
+        report.set_formatter(Formatter::new(&format).unwrap());
+        report.sunrise = Some(time("2026-10-18T07:00:00Z").into());
+        report.sunset = Some(time("2026-10-18T18:00:00Z").into());
+
+        // Thresholds are in the display scale, 12.3°C is 54.1°F
+        report.set_view(View::Current, time("2026-10-18T12:00:00Z"));
+        assert_eq!(report.format_i3(), "SUN 54.1°F\nSUN\n#00FF00");
+
+        report.set_view(View::Current, time("2026-10-18T21:00:00Z"));
+        assert_eq!(report.format_i3(), "MOON 54.1°F\nMOON\n#00FF00");
+
+        report.temperature = -3.0;
+        assert_eq!(report.format_i3(), "MOON 26.6°F\nMOON\n#0000FF");
+    }
+
+    #[test]
+    fn test_format_i3_alert() {
+        let mut report = WeatherReport::new(31.0, Condition::Clear);
+        report.set_scale('C');
+        report.set_alert(Some(("⚠ Heat warning".into(), "⚠".into())));
+        report.set_age(7200);
+        assert_eq!(
+            report.format_i3(),
+            "WTR 31.0°C, clear ⚠ Heat warning · 2h ago\nWTR 31.0°C ⚠\n#808080"
+        );
+    }
+
+    #[test]
+    fn test_is_day() {
+        let mut report = WeatherReport::new(12.3, Condition::Clear);
+        let time = |v| DateTime::parse_from_rfc3339(v).unwrap().with_timezone(&Utc);
+        assert_eq!(report.is_day(time("2026-10-18T12:00:00Z")), None);
+
+        report.sunrise = Some(time("2026-10-18T06:00:00Z"));
+        report.sunset = Some(time("2026-10-18T17:00:00Z"));
+        assert_eq!(report.is_day(time("2026-10-18T12:00:00Z")), Some(true));
+        assert_eq!(report.is_day(time("2026-10-18T18:00:00Z")), Some(false));
+        assert_eq!(report.is_day(time("2026-10-19T07:00:00Z")), Some(true));
+        assert_eq!(report.is_day(time("2026-10-18T05:00:00Z")), Some(false));
+    }
+
+    #[test]
+    fn test_format_i3_views() {
+        let body = std::fs::read_to_string("tests/openmeteo/hourly-daily.json").unwrap();
+        let mut report = WeatherReport::new(12.3, Condition::Rain);
+        report.forecast = Some(crate::openmeteo::parse_forecast(&body).unwrap());
+
+        let now = DateTime::parse_from_rfc3339("2026-10-18T12:10:00+02:00").unwrap();
+        let mut text = |view| {
+            report.set_view(view, now);
+            report.format_i3()
+        };
+
+        assert_eq!(text(View::Current), "WTR 12.3°C, rain\nWTR 12.3°C\n#FFFFFF");
+        assert_eq!(
+            text(View::Next3h),
+            "WTR 3h 14.1°C, partly cloudy\nWTR 3h 14.1°C\n#FFFFFF"
+        );
+        assert_eq!(
+            text(View::Today),
+            "WTR today 15.3/9.1°C, rain\nWTR today 15.3/9.1°C\n#FFFFFF"
+        );
+        assert_eq!(
+            text(View::Tomorrow),
+            "WTR tmrw 13.2/8.0°C, showers\nWTR tmrw 13.2/8.0°C\n#FFFFFF"
+        );
+
+        // Days beyond the forecast fall back to the current weather
+        let now = now + Duration::days(30);
+        report.set_view(View::Tomorrow, now);
+        assert_eq!(report.format_i3(), "WTR 12.3°C, rain\nWTR 12.3°C\n#FFFFFF");
+    }
+
+    #[test]
+    fn test_format_age() {
+        assert_eq!(format_age(-5), "0m ago");
+        assert_eq!(format_age(59 * 60), "59m ago");
+        assert_eq!(format_age(60 * 60), "1h ago");
+        assert_eq!(format_age(47 * 60 * 60), "47h ago");
+        assert_eq!(format_age(3 * 24 * 60 * 60), "3d ago");
+    }
+}
@@ -0,0 +1,91 @@
+use chrono::{DateTime, NaiveDate, Utc};
+
+use crate::forecast;
+
+// Constants
+const J2000: f64 = 2_451_545.0; // Julian date of 2000-01-01 12:00 UTC
+const UNIX_EPOCH: f64 = 2_440_587.5; // Julian date of 1970-01-01 00:00 UTC
+const OBLIQUITY: f64 = 23.4397; // axial tilt of the Earth (°)
+const HORIZON: f64 = -0.833; // solar altitude at sunrise and sunset (°)
+
+/// Computes the times of sunrise and sunset
+///
+/// Used for providers that don't report them. The sunrise equation is
+/// accurate to within a few minutes away from the poles.
+///
+/// # Arguments
+///
+/// - `lat`: Latitude (°)
+/// - `lon`: Longitude (°)
+/// - `date`: UTC date
+///
+/// # Returns
+///
+/// The sunrise and sunset, or `None` during polar day or night
+pub(crate) fn sun_times(
+    lat: f64,
+    lon: f64,
+    date: NaiveDate,
+) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+    let midnight = date.and_hms_opt(0, 0, 0)?.and_utc().timestamp() as f64;
+    let julian = midnight / 86_400.0 + UNIX_EPOCH + 0.5;
+
+    // Mean solar time, anomaly and ecliptic longitude
+    let noon = (julian - J2000 + 0.0008).round() - lon / 360.0;
+    let anomaly = (357.5291 + 0.985_600_28 * noon)
+        .rem_euclid(360.0)
+        .to_radians();
+    let center =
+        1.9148 * anomaly.sin() + 0.02 * (2.0 * anomaly).sin() + 0.0003 * (3.0 * anomaly).sin();
+    let ecliptic = (anomaly.to_degrees() + center + 180.0 + 102.9372)
+        .rem_euclid(360.0)
+        .to_radians();
+    let transit = J2000 + noon + 0.0053 * anomaly.sin() - 0.0069 * (2.0 * ecliptic).sin();
+
+    // Hour angle of the sun at the horizon
+    let declination = (ecliptic.sin() * OBLIQUITY.to_radians().sin()).asin();
+    let lat = lat.to_radians();
+    let cos_angle = (HORIZON.to_radians().sin() - lat.sin() * declination.sin())
+        / (lat.cos() * declination.cos());
+    if !(-1.0..=1.0).contains(&cos_angle) {
+        return None;
+    }
+
+    let angle = cos_angle.acos().to_degrees() / 360.0;
+    let time = |julian: f64| forecast::unix(((julian - UNIX_EPOCH) * 86_400.0).round() as i64);
+
+    Some((time(transit - angle), time(transit + angle)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Asserts that a time is within two minutes of a reference time
+    fn assert_near(time: DateTime<Utc>, reference: &str) {
+        let reference: DateTime<Utc> = reference.parse().unwrap();
+        let error = (time - reference).num_seconds().abs();
+        assert!(error <= 120, "{} is {}s from {}", time, error, reference);
+    }
+
+    #[test]
+    fn test_sun_times() {
+        // Paris at the solstice, sunrise 05:47 and sunset 21:58 CEST
+        let date = NaiveDate::from_ymd_opt(2026, 6, 21).unwrap();
+        let (sunrise, sunset) = sun_times(48.86, 2.34, date).unwrap();
+        assert_near(sunrise, "2026-06-21T03:47:00Z");
+        assert_near(sunset, "2026-06-21T19:58:00Z");
+
+        // San Francisco, sunset falls on the next UTC day
+        let date = NaiveDate::from_ymd_opt(2026, 10, 18).unwrap();
+        let (sunrise, sunset) = sun_times(37.77, -122.42, date).unwrap();
+        assert_near(sunrise, "2026-10-18T14:19:00Z");
+        assert_near(sunset, "2026-10-19T01:27:00Z");
+    }
+
+    #[test]
+    fn test_sun_times_polar() {
+        let date = NaiveDate::from_ymd_opt(2026, 6, 21).unwrap();
+        assert_eq!(sun_times(78.22, 15.65, date), None);
+    }
+}
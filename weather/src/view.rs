@@ -0,0 +1,64 @@
+use serde::{Deserialize, Serialize};
+
+// Constants
+pub const LEFT_BUTTON: u8 = 1;
+pub const RIGHT_BUTTON: u8 = 3;
+
+/// Views of the `weather` block cycled by left-clicking it
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum View {
+    #[default]
+    Current, // current weather
+    Next3h,   // forecast three hours from now
+    Today,    // today's high and low
+    Tomorrow, // tomorrow's high and low
+}
+
+impl View {
+    /// Returns the view following this one among the configured views
+    ///
+    /// # Arguments
+    ///
+    /// - `views`: Configured views in cycling order
+    ///
+    /// # Returns
+    ///
+    /// The next view, or the first configured view if this one isn't
+    /// configured
+    pub fn next(self, views: &[View]) -> View {
+        let first = views.first().copied().unwrap_or_default();
+        match views.iter().position(|v| *v == self) {
+            Some(i) => views.get(i + 1).copied().unwrap_or(first),
+            None => first,
+        }
+    }
+
+    /// Returns whether the view is rendered from the forecast
+    pub fn is_forecast(self) -> bool {
+        self != View::Current
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_next() {
+        let views = [View::Current, View::Today, View::Tomorrow];
+        assert_eq!(View::Current.next(&views), View::Today);
+        assert_eq!(View::Tomorrow.next(&views), View::Current);
+        assert_eq!(View::Next3h.next(&views), View::Current);
+        assert_eq!(View::Today.next(&[]), View::Current);
+    }
+
+    #[test]
+    fn test_deserialize() {
+        let views: Vec<View> = serde_yaml::from_str("[current, next3h, today, tomorrow]").unwrap();
+        assert_eq!(
+            views,
+            vec![View::Current, View::Next3h, View::Today, View::Tomorrow]
+        );
+    }
+}
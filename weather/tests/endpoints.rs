@@ -0,0 +1,432 @@
+mod support;
+
+use std::time::Duration;
+
+use support::{fixture, ok, slow, status, Server};
+use weather::{
+    AirQualityProvider, AlertProvider, Condition, GeoLocation, HttpClient, HttpError, IPv4,
+    MetNorway, MeteoAlarm, Nws, OpenMeteo, OpenMeteoAirQuality, OpenWeatherMap, ReqwestClient,
+    StaticLocation, WeatherProvider, DEFAULT_ERROR,
+};
+
+const IP: &str = "203.0.113.7";
+
+fn client() -> ReqwestClient {
+    ReqwestClient::new(Duration::from_millis(500)).unwrap()
+}
+
+fn locate(client: &dyn HttpClient) -> GeoLocation {
+    let server = Server::start(vec![
+        ("/json/", ok(&fixture("geolocation/success.json"))),
+        ("/", ok(IP)),
+    ]);
+    let ip = IPv4::new(client, server.url(), false).unwrap();
+    GeoLocation::new(client, server.url(), ip, false).unwrap()
+}
+
+#[test]
+fn test_ipv4() {
+    let server = Server::start(vec![("/", ok(&format!("{}\n", IP)))]);
+    let ip = IPv4::new(&client(), server.url(), false).unwrap();
+
+    assert_eq!(ip.to_string(), IP);
+    assert_eq!(server.requests(), vec!["/"]);
+}
+
+#[test]
+fn test_ipv4_captive_portal() {
+    let login = "<html><body>Sign in to the network</body></html>";
+    let server = Server::start(vec![("/", ok(login))]);
+    let ip = IPv4::new(&client(), server.url(), false);
+
+    assert_eq!(ip.unwrap_err(), DEFAULT_ERROR);
+}
+
+#[test]
+fn test_geolocation() {
+    let client = client();
+    let location = locate(&client);
+
+    assert_eq!(location.to_string(), "lat: 37.775, lon: -122.419");
+}
+
+#[test]
+fn test_geolocation_request() {
+    let server = Server::start(vec![
+        ("/json/", ok(&fixture("geolocation/success.json"))),
+        ("/", ok(IP)),
+    ]);
+    let client = client();
+    let ip = IPv4::new(&client, server.url(), false).unwrap();
+    GeoLocation::new(&client, &format!("{}/", server.url()), ip, false).unwrap();
+
+    assert_eq!(
+        server.requests()[1],
+        "/json/203.0.113.7?fields=status,message,lat,lon"
+    );
+}
+
+#[test]
+fn test_geolocation_fail() {
+    let server = Server::start(vec![
+        ("/json/", ok(&fixture("geolocation/fail.json"))),
+        ("/", ok(IP)),
+    ]);
+    let client = client();
+    let ip = IPv4::new(&client, server.url(), false).unwrap();
+    let location = GeoLocation::new(&client, server.url(), ip, false);
+
+    assert_eq!(location.err().unwrap(), DEFAULT_ERROR);
+}
+
+#[test]
+fn test_geolocation_malformed() {
+    let server = Server::start(vec![("/json/", ok("{\"status\": ")), ("/", ok(IP))]);
+    let client = client();
+    let ip = IPv4::new(&client, server.url(), false).unwrap();
+    let location = GeoLocation::new(&client, server.url(), ip, false);
+
+    assert_eq!(location.err().unwrap(), DEFAULT_ERROR);
+}
+
+#[test]
+fn test_openweathermap() {
+    let client = client();
+    let location = locate(&client);
+    let server = Server::start(vec![(
+        "/data/2.5/weather",
+        ok(&fixture("openweathermap/current.json")),
+    )]);
+
+    let provider = OpenWeatherMap::new(server.url(), "key");
+    let report = provider.current(&client, &location).unwrap();
+
+    assert_eq!(report.temperature, 12.3);
+    assert_eq!(report.condition, Condition::Rain);
+    assert_eq!(
+        server.requests(),
+        vec!["/data/2.5/weather?lat=37.7749&lon=-122.4194&units=metric&appid=key"]
+    );
+}
+
+#[test]
+fn test_openweathermap_empty_weather() {
+    let client = client();
+    let location = locate(&client);
+    let server = Server::start(vec![(
+        "/data/2.5/weather",
+        ok(&fixture("openweathermap/empty-weather.json")),
+    )]);
+
+    let provider = OpenWeatherMap::new(server.url(), "key");
+    let report = provider.current(&client, &location).unwrap();
+
+    assert_eq!(report.temperature, 12.3);
+    assert_eq!(report.condition, Condition::Unknown);
+}
+
+#[test]
+fn test_openweathermap_malformed() {
+    let client = client();
+    let location = locate(&client);
+    let server = Server::start(vec![("/data/2.5/weather", ok("{\"main\": {}}"))]);
+
+    let provider = OpenWeatherMap::new(server.url(), "key");
+    let report = provider.current(&client, &location);
+
+    assert_eq!(report.err().unwrap(), DEFAULT_ERROR);
+}
+
+#[test]
+fn test_openweathermap_unauthorized() {
+    let client = client();
+    let location = locate(&client);
+    let body = fixture("openweathermap/unauthorized.json");
+    let server = Server::start(vec![("/", status(401, &body))]);
+
+    let url = format!("{}/data/2.5/weather", server.url());
+    assert_eq!(client.get(&url), Err(HttpError::Status(401)));
+
+    let provider = OpenWeatherMap::new(server.url(), "bad");
+    let report = provider.current(&client, &location);
+    assert_eq!(report.err().unwrap(), DEFAULT_ERROR);
+}
+
+#[test]
+fn test_openweathermap_rate_limited() {
+    let client = client();
+    let location = locate(&client);
+    let body = fixture("openweathermap/rate-limited.json");
+    let server = Server::start(vec![("/", status(429, &body))]);
+
+    let url = format!("{}/data/2.5/weather", server.url());
+    assert_eq!(client.get(&url), Err(HttpError::Status(429)));
+
+    let provider = OpenWeatherMap::new(server.url(), "key");
+    let report = provider.current(&client, &location);
+    assert_eq!(report.err().unwrap(), DEFAULT_ERROR);
+}
+
+#[test]
+fn test_timeout() {
+    let client = ReqwestClient::new(Duration::from_millis(100)).unwrap();
+    let location = locate(&client);
+    let body = fixture("openweathermap/current.json");
+    let server = Server::start(vec![("/", slow(&body, Duration::from_millis(1000)))]);
+
+    let url = format!("{}/data/2.5/weather", server.url());
+    assert_eq!(client.get(&url), Err(HttpError::Timeout));
+
+    let provider = OpenWeatherMap::new(server.url(), "key");
+    let report = provider.current(&client, &location);
+    assert_eq!(report.err().unwrap(), DEFAULT_ERROR);
+}
+
+#[test]
+fn test_openmeteo() {
+    let client = client();
+    let location = locate(&client);
+    let server = Server::start(vec![(
+        "/v1/forecast",
+        ok(&fixture("openmeteo/forecast.json")),
+    )]);
+
+    let report = OpenMeteo::new(server.url())
+        .current(&client, &location)
+        .unwrap();
+
+    assert_eq!(report.feels_like, Some(10.9));
+    assert_eq!(report.condition, Condition::Rain);
+    assert!(server.requests()[0].starts_with("/v1/forecast?latitude=37.7749&longitude=-122.4194"));
+}
+
+#[test]
+fn test_metno() {
+    let client = client();
+    let location = locate(&client);
+    let server = Server::start(vec![(
+        "/weatherapi/locationforecast/2.0/compact",
+        ok(&fixture("metno/compact.json")),
+    )]);
+
+    let report = MetNorway::new(server.url())
+        .current(&client, &location)
+        .unwrap();
+
+    assert_eq!(report.pressure, Some(1012.4));
+    assert_eq!(report.condition, Condition::Rain);
+    assert_eq!(
+        server.requests(),
+        vec!["/weatherapi/locationforecast/2.0/compact?lat=37.7749&lon=-122.4194"]
+    );
+}
+
+#[test]
+fn test_geocoding() {
+    let server = Server::start(vec![("/v1/search", ok(&fixture("geocoding/paris.json")))]);
+    let location = StaticLocation::City {
+        city: "Paris".into(),
+        country: Some("FR".into()),
+    };
+    let location = location.resolve(&client(), server.url(), false).unwrap();
+
+    assert_eq!(location.to_string(), "lat: 48.853, lon: 2.349");
+    assert_eq!(
+        server.requests(),
+        vec!["/v1/search?name=Paris&count=10&format=json"]
+    );
+}
+
+#[test]
+fn test_geocoding_ambiguous() {
+    let server = Server::start(vec![("/v1/search", ok(&fixture("geocoding/paris.json")))]);
+    let location = StaticLocation::City {
+        city: "Paris".into(),
+        country: None,
+    };
+    let location = location.resolve(&client(), server.url(), false);
+
+    assert_eq!(location.err().unwrap(), DEFAULT_ERROR);
+}
+
+#[test]
+fn test_geocoding_coordinates() {
+    let location = StaticLocation::Coordinates {
+        latitude: 48.85,
+        longitude: 2.35,
+    };
+    let location = location.resolve(&client(), "http://127.0.0.1:9", false);
+
+    assert_eq!(location.unwrap().to_string(), "lat: 48.850, lon: 2.350");
+}
+
+#[test]
+fn test_openmeteo_forecast() {
+    let client = client();
+    let location = locate(&client);
+    let server = Server::start(vec![(
+        "/v1/forecast",
+        ok(&fixture("openmeteo/hourly-daily.json")),
+    )]);
+
+    let forecast = OpenMeteo::new(server.url())
+        .forecast(&client, &location)
+        .unwrap();
+
+    assert_eq!(forecast.hourly.len(), 48);
+    assert_eq!(forecast.daily.len(), 7);
+    assert!(server.requests()[0].contains("&forecast_hours=48&forecast_days=7&timezone=auto"));
+}
+
+#[test]
+fn test_openweathermap_forecast() {
+    let client = client();
+    let location = locate(&client);
+    let server = Server::start(vec![(
+        "/data/2.5/forecast",
+        ok(&fixture("openweathermap/forecast.json")),
+    )]);
+
+    let provider = OpenWeatherMap::new(server.url(), "key");
+    let forecast = provider.forecast(&client, &location).unwrap();
+
+    assert_eq!(forecast.hourly.len(), 16);
+    assert_eq!(
+        server.requests(),
+        vec!["/data/2.5/forecast?lat=37.7749&lon=-122.4194&units=metric&appid=key"]
+    );
+}
+
+#[test]
+fn test_metno_forecast() {
+    let client = client();
+    let location = locate(&client);
+    let server = Server::start(vec![("/", ok(&fixture("metno/forecast.json")))]);
+
+    let forecast = MetNorway::new(server.url())
+        .forecast(&client, &location)
+        .unwrap();
+
+    assert_eq!(forecast.hourly.len(), 48);
+    assert!(!forecast.daily.is_empty());
+}
+
+#[test]
+fn test_openweathermap_alerts() {
+    let client = client();
+    let location = locate(&client);
+    let server = Server::start(vec![(
+        "/data/3.0/onecall",
+        ok(&fixture("openweathermap/onecall.json")),
+    )]);
+
+    let alerts = OpenWeatherMap::new(server.url(), "key")
+        .alerts(&client, &location)
+        .unwrap();
+
+    assert_eq!(alerts.len(), 2);
+    assert_eq!(
+        server.requests(),
+        vec![
+            "/data/3.0/onecall?lat=37.7749&lon=-122.4194&exclude=current,minutely,hourly,daily\
+             &appid=key"
+        ]
+    );
+}
+
+#[test]
+fn test_openweathermap_alerts_unauthorized() {
+    let client = client();
+    let location = locate(&client);
+    let server = Server::start(vec![(
+        "/",
+        status(401, &fixture("openweathermap/unauthorized.json")),
+    )]);
+
+    let alerts = OpenWeatherMap::new(server.url(), "key").alerts(&client, &location);
+    assert_eq!(alerts.err().unwrap(), DEFAULT_ERROR);
+}
+
+#[test]
+fn test_nws_alerts() {
+    let client = client();
+    let location = locate(&client);
+    let server = Server::start(vec![("/alerts/active", ok(&fixture("nws/alerts.json")))]);
+
+    let alerts = Nws::new(server.url()).alerts(&client, &location).unwrap();
+
+    assert_eq!(alerts[0].event, "Wind Advisory");
+    assert_eq!(
+        server.requests(),
+        vec!["/alerts/active?point=37.7749,-122.4194"]
+    );
+}
+
+#[test]
+fn test_meteoalarm_alerts() {
+    let client = client();
+    let location = locate(&client);
+    let server = Server::start(vec![(
+        "/api/v1/warnings/feeds-germany",
+        ok(&fixture("meteoalarm/feed.json")),
+    )]);
+
+    let alerts = MeteoAlarm::new(server.url(), "Germany", "Berlin")
+        .alerts(&client, &location)
+        .unwrap();
+
+    assert_eq!(alerts.len(), 1);
+    assert_eq!(server.requests(), vec!["/api/v1/warnings/feeds-germany"]);
+}
+
+#[test]
+fn test_openweathermap_air_quality() {
+    let client = client();
+    let location = locate(&client);
+    let server = Server::start(vec![(
+        "/data/2.5/air_pollution",
+        ok(&fixture("openweathermap/air-pollution.json")),
+    )]);
+
+    let air_quality = OpenWeatherMap::new(server.url(), "key")
+        .air_quality(&client, &location)
+        .unwrap();
+
+    assert_eq!(air_quality.concentrations.pm2_5, Some(8.93));
+    assert_eq!(
+        server.requests(),
+        vec!["/data/2.5/air_pollution?lat=37.7749&lon=-122.4194&appid=key"]
+    );
+}
+
+#[test]
+fn test_openweathermap_air_quality_unauthorized() {
+    let client = client();
+    let location = locate(&client);
+    let server = Server::start(vec![(
+        "/",
+        status(401, &fixture("openweathermap/unauthorized.json")),
+    )]);
+
+    let air_quality = OpenWeatherMap::new(server.url(), "key").air_quality(&client, &location);
+    assert_eq!(air_quality.err().unwrap(), DEFAULT_ERROR);
+}
+
+#[test]
+fn test_openmeteo_air_quality() {
+    let client = client();
+    let location = locate(&client);
+    let server = Server::start(vec![(
+        "/v1/air-quality",
+        ok(&fixture("openmeteo/air-quality.json")),
+    )]);
+
+    let air_quality = OpenMeteoAirQuality::new(server.url())
+        .air_quality(&client, &location)
+        .unwrap();
+
+    assert_eq!(air_quality.concentrations.o3, Some(61.0));
+    assert!(!air_quality.pollen.is_empty());
+    assert!(server.requests()[0]
+        .starts_with("/v1/air-quality?latitude=37.7749&longitude=-122.4194&current=pm2_5,"));
+}
@@ -0,0 +1,105 @@
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+/// Canned response of a route
+#[derive(Clone)]
+pub struct Response {
+    status: u16,     // status code
+    body: String,    // response body
+    delay: Duration, // time to wait before answering
+}
+
+/// Answers with a 200 and a body
+pub fn ok(body: &str) -> Response {
+    status(200, body)
+}
+
+/// Answers with a status and a body
+pub fn status(status: u16, body: &str) -> Response {
+    Response {
+        status,
+        body: body.into(),
+        delay: Duration::from_secs(0),
+    }
+}
+
+/// Answers with a 200 and a body after a delay
+pub fn slow(body: &str, delay: Duration) -> Response {
+    Response { delay, ..ok(body) }
+}
+
+/// Reads a canned body from the `tests` directory
+pub fn fixture(path: &str) -> String {
+    std::fs::read_to_string(format!("tests/{}", path)).unwrap()
+}
+
+/// In-process HTTP server answering requests with canned responses
+///
+/// Routes are matched by path prefix in order, and unmatched requests are
+/// answered with a 404. The server lives until the test process exits.
+pub struct Server {
+    url: String,                       // base URL of the server
+    requests: Arc<Mutex<Vec<String>>>, // paths requested so far
+}
+
+impl Server {
+    pub fn start(routes: Vec<(&'static str, Response)>) -> Server {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let routes = Arc::new(routes);
+
+        let log = Arc::clone(&requests);
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let routes = Arc::clone(&routes);
+                let log = Arc::clone(&log);
+                thread::spawn(move || answer(stream, &routes, &log));
+            }
+        });
+
+        Server { url, requests }
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    pub fn requests(&self) -> Vec<String> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+fn answer(mut stream: TcpStream, routes: &[(&str, Response)], log: &Mutex<Vec<String>>) {
+    // Read the request line and skip the headers
+    let mut reader = BufReader::new(stream.try_clone().unwrap());
+    let mut line = String::new();
+    reader.read_line(&mut line).unwrap();
+    let path = line.split_whitespace().nth(1).unwrap_or("/").to_string();
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header).unwrap() == 0 || header == "\r\n" {
+            break;
+        }
+    }
+    log.lock().unwrap().push(path.clone());
+
+    let response = routes
+        .iter()
+        .find(|(prefix, _)| path.starts_with(prefix))
+        .map(|(_, response)| response.clone())
+        .unwrap_or_else(|| status(404, "not found"));
+
+    thread::sleep(response.delay);
+    let _ = write!(
+        stream,
+        "HTTP/1.1 {} Stub\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\
+         Connection: close\r\n\r\n{}",
+        response.status,
+        response.body.len(),
+        response.body
+    );
+}